@@ -144,7 +144,9 @@ where
     node_exec.set_sample_rate(sample_rate);
     
     let input_bufs = [[0.0; hexodsp::dsp::MAX_BLOCK_SIZE]; 2];
-    let mut outputbufs = [[0.0; hexodsp::dsp::MAX_BLOCK_SIZE]; 2];
+    let out_chans = channels.clamp(1, hexodsp::dsp::MAX_OUTPUT_CHANNELS);
+    let mut outputbufs =
+        [[0.0; hexodsp::dsp::MAX_BLOCK_SIZE]; hexodsp::dsp::MAX_OUTPUT_CHANNELS];
 
     let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
     let stream = device.build_output_stream(
@@ -165,15 +167,14 @@ where
 
                 let input = &[&input_bufs[0][0..cur_nframes], &input_bufs[1][0..cur_nframes]];
 
-                let split = outputbufs.split_at_mut(1);
+                let mut output = outputbufs.each_mut().map(|buf| &mut buf[0..cur_nframes]);
 
-                let mut output =
-                    [&mut ((split.0[0])[0..cur_nframes]), &mut ((split.1[0])[0..cur_nframes])];
+                let mut context =
+                    Context { nframes: cur_nframes, output: &mut output[..out_chans], input };
 
-                let mut context = Context { nframes: cur_nframes, output: &mut output[..], input };
-
-                context.output[0].fill(0.0);
-                context.output[1].fill(0.0);
+                for buf in context.output.iter_mut() {
+                    buf.fill(0.0);
+                }
 
                 node_exec.process(&mut context);
 
@@ -190,7 +191,7 @@ where
                             *sample = value;
 
                             ctx_chan += 1;
-                            if ctx_chan > context.output.len() {
+                            if ctx_chan >= context.output.len() {
                                 ctx_chan = context.output.len() - 1;
                             }
                         }
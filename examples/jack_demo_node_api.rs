@@ -273,36 +273,20 @@ fn start_backend<F: FnMut()>(node_exec: NodeExecutor, mut frontend_loop: F) {
         // updates that were sent by the frontend thread:
         node_exec.process_graph_updates();
 
-        let mut frames_left = nframes;
-        let mut offs = 0;
-
-        while frames_left > 0 {
-            let cur_nframes = if frames_left >= hexodsp::dsp::MAX_BLOCK_SIZE {
-                hexodsp::dsp::MAX_BLOCK_SIZE
-            } else {
-                frames_left
-            };
-
-            frames_left -= cur_nframes;
+        let output = &mut [&mut out_a_p[..], &mut out_b_p[..]];
+        let input = &[&in_a_p[..], &in_b_p[..]];
 
-            let output = &mut [
-                &mut out_a_p[offs..(offs + cur_nframes)],
-                &mut out_b_p[offs..(offs + cur_nframes)],
-            ];
-            let input = &[&in_a_p[offs..(offs + cur_nframes)], &in_b_p[offs..(offs + cur_nframes)]];
+        let mut context = Context { nframes, output, input };
 
-            let mut context = Context { nframes: cur_nframes, output, input };
-
-            for i in 0..context.nframes {
-                context.output[0][i] = 0.0;
-                context.output[1][i] = 0.0;
-            }
-
-            node_exec.process(&mut context);
-
-            offs += cur_nframes;
+        for i in 0..context.nframes {
+            context.output[0][i] = 0.0;
+            context.output[1][i] = 0.0;
         }
 
+        // Periods longer than the maximum block size of the
+        // NodeExecutor are split up by it:
+        node_exec.process(&mut context);
+
         jack::Control::Continue
     };
 
@@ -0,0 +1,255 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+/*! Mapping of MIDI controllers (CC) to parameters.
+
+The [crate::Matrix] holds a table of [CcMapping], that routes the incoming
+MIDI control change messages to the parameters of the nodes. The host passes
+the raw messages to [crate::Matrix::handle_cc]:
+
+```
+use hexodsp::*;
+use hexodsp::cc_map::CcMapping;
+
+let (node_conf, mut _node_exec) = new_node_engine();
+let mut matrix = Matrix::new(node_conf, 3, 3);
+
+let gain = NodeId::Out(0).inp_param("gain").unwrap();
+matrix.add_cc_mapping(CcMapping::new(None, 7, gain).range(0.0, 0.5));
+
+matrix.handle_cc(0, 7, 127);
+assert_eq!(matrix.get_param(&gain).unwrap().f(), 0.5);
+
+// Or let the user move a controller to bind it:
+let det = NodeId::Sin(0).inp_param("det").unwrap();
+matrix.learn_cc(Some(det));
+matrix.handle_cc(2, 74, 10);
+assert_eq!(matrix.cc_mappings()[1], CcMapping::new(Some(2), 74, det));
+```
+
+The mappings are saved and loaded with the [crate::matrix_repr::MatrixRepr].
+*/
+
+use crate::dsp::{ParamId, SAtom};
+
+/// With soft takeover the controller picks up the parameter if it gets
+/// at least this close to the current (normalized) value.
+const TAKEOVER_THRESHOLD: f32 = 1.0 / 127.0;
+
+/// Maps a MIDI controller to a parameter.
+///
+/// The parameter values `min` and `max` are normalized, like the
+/// values passed in [SAtom::param]. For setting atoms the range 0.0 to 1.0
+/// covers all of the settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CcMapping {
+    /// The MIDI channel (0 to 15), `None` receives from all channels.
+    pub channel: Option<u8>,
+    /// The MIDI CC number.
+    pub cc: u8,
+    pub param: ParamId,
+    /// The parameter value at controller value 0.
+    pub min: f32,
+    /// The parameter value at controller value 127.
+    pub max: f32,
+    /// Swaps `min` and `max`.
+    pub invert: bool,
+    /// If enabled, the controller only changes the parameter after it
+    /// reached the current value of the parameter. This prevents jumps
+    /// if the parameter was changed by other means.
+    pub soft_takeover: bool,
+}
+
+impl CcMapping {
+    /// Maps the whole range of the controller to the whole
+    /// range of the parameter.
+    pub fn new(channel: Option<u8>, cc: u8, param: ParamId) -> Self {
+        Self { channel, cc, param, min: 0.0, max: 1.0, invert: false, soft_takeover: false }
+    }
+
+    pub fn range(mut self, min: f32, max: f32) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    pub fn soft_takeover(mut self, soft_takeover: bool) -> Self {
+        self.soft_takeover = soft_takeover;
+        self
+    }
+
+    /// Returns true if this mapping receives the controller `cc` on `channel`.
+    pub fn matches(&self, channel: u8, cc: u8) -> bool {
+        self.cc == cc && self.channel.map(|c| c == channel).unwrap_or(true)
+    }
+
+    /// Returns the normalized parameter value for the controller `value`.
+    pub fn map_value(&self, value: u8) -> f32 {
+        let x = (value.min(127) as f32) / 127.0;
+        let x = if self.invert { 1.0 - x } else { x };
+        self.min + (self.max - self.min) * x
+    }
+
+    /// Converts the normalized value `v` into the [SAtom] of the parameter.
+    fn value_atom(&self, v: f32) -> SAtom {
+        match (self.param.as_atom_def(), self.param.setting_min_max()) {
+            (SAtom::Setting(_), Some((min, max))) => {
+                let v = v.clamp(0.0, 1.0);
+                SAtom::setting(min + ((max - min) as f32 * v).round() as i64)
+            }
+            _ => SAtom::param(v),
+        }
+    }
+
+    /// Converts the current value of the parameter into
+    /// a normalized value, see also [CcMapping::value_atom].
+    fn atom_value(&self, at: &SAtom) -> f32 {
+        match (at, self.param.setting_min_max()) {
+            (SAtom::Setting(i), Some((min, max))) if max > min => {
+                (*i - min) as f32 / (max - min) as f32
+            }
+            _ => at.f(),
+        }
+    }
+}
+
+/// The soft takeover state of a [CcMapping].
+#[derive(Debug, Clone, Copy, Default)]
+struct Takeover {
+    /// The value that was last received from the controller.
+    last_in: Option<f32>,
+    /// The value that was last assigned to the parameter.
+    last_out: Option<f32>,
+}
+
+impl Takeover {
+    /// Returns true if the controller value `v` may change the parameter
+    /// that currently has the value `cur`.
+    fn pick_up(&mut self, cur: f32, v: f32) -> bool {
+        let in_sync = self.last_out.map(|o| (o - cur).abs() < 1e-6).unwrap_or(false);
+        let crossed = self.last_in.map(|i| (i - cur) * (v - cur) <= 0.0).unwrap_or(false);
+        self.last_in = Some(v);
+
+        in_sync || crossed || (v - cur).abs() <= TAKEOVER_THRESHOLD
+    }
+}
+
+/// The table of [CcMapping], as held by the [crate::Matrix].
+#[derive(Debug, Clone, Default)]
+pub struct CcMap {
+    mappings: Vec<CcMapping>,
+    takeover: Vec<Takeover>,
+    learn: Option<ParamId>,
+}
+
+impl CcMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mappings(&self) -> &[CcMapping] {
+        &self.mappings[..]
+    }
+
+    /// Adds the `mapping`. An existing mapping of the same controller
+    /// to the same parameter is replaced.
+    pub fn add(&mut self, mapping: CcMapping) {
+        if let Some(idx) = self.mappings.iter().position(|m| {
+            m.channel == mapping.channel && m.cc == mapping.cc && m.param == mapping.param
+        }) {
+            self.mappings[idx] = mapping;
+            self.takeover[idx] = Takeover::default();
+        } else {
+            self.mappings.push(mapping);
+            self.takeover.push(Takeover::default());
+        }
+    }
+
+    /// Removes all mappings to the parameter `param`.
+    pub fn remove_param(&mut self, param: &ParamId) {
+        let mut i = 0;
+        while i < self.mappings.len() {
+            if self.mappings[i].param == *param {
+                self.mappings.remove(i);
+                self.takeover.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.mappings.clear();
+        self.takeover.clear();
+        self.learn = None;
+    }
+
+    /// Binds the next incoming controller to `param`.
+    /// `None` cancels the learn mode.
+    pub fn learn(&mut self, param: Option<ParamId>) {
+        self.learn = param;
+    }
+
+    /// Returns the parameter that waits for a controller to be learned.
+    pub fn learn_param(&self) -> Option<ParamId> {
+        self.learn
+    }
+
+    /// Handles the controller message. In learn mode the controller
+    /// is bound to the parameter, replacing any previous mapping of it.
+    /// Otherwise the new parameter values are pushed to `updates`.
+    /// `get_param` retrieves the current parameter values for
+    /// the soft takeover.
+    ///
+    /// Returns true if the controller is mapped or was learned.
+    pub fn handle_cc<F>(
+        &mut self,
+        channel: u8,
+        cc: u8,
+        value: u8,
+        get_param: F,
+        updates: &mut Vec<(ParamId, SAtom)>,
+    ) -> bool
+    where
+        F: Fn(&ParamId) -> Option<SAtom>,
+    {
+        if let Some(param) = self.learn.take() {
+            self.remove_param(&param);
+            self.add(CcMapping::new(Some(channel), cc, param));
+            return true;
+        }
+
+        let mut handled = false;
+        for (mapping, takeover) in self.mappings.iter().zip(self.takeover.iter_mut()) {
+            if !mapping.matches(channel, cc) {
+                continue;
+            }
+            handled = true;
+
+            let v = mapping.map_value(value);
+            let at = mapping.value_atom(v);
+
+            if mapping.soft_takeover {
+                // Settings are rounded, so compare the actually assigned values:
+                let v = mapping.atom_value(&at);
+                let cur = get_param(&mapping.param).map(|at| mapping.atom_value(&at));
+                if let Some(cur) = cur {
+                    if !takeover.pick_up(cur, v) {
+                        continue;
+                    }
+                }
+                takeover.last_out = Some(v);
+            }
+
+            updates.push((mapping.param, at));
+        }
+
+        handled
+    }
+}
@@ -78,6 +78,39 @@ impl BiquadCoefs {
         BiquadCoefs { a1, a2, b0, b1, b2 }
     }
 
+    /// Returns settings for a highpass filter with a specific q
+    #[inline]
+    pub fn highpass(sample_rate: f32, q: f32, cutoff: f32) -> BiquadCoefs {
+        let f = (cutoff * PI / sample_rate).tan();
+        let a0r = 1.0 / (1.0 + f / q + f * f);
+
+        let b0 = a0r;
+        let b1 = -2.0 * b0;
+        let b2 = b0;
+        let a1 = 2.0 * (f * f - 1.0) * a0r;
+        let a2 = (1.0 - f / q + f * f) * a0r;
+
+        BiquadCoefs { a1, a2, b0, b1, b2 }
+    }
+
+    /// Returns settings for a high shelf filter with a specific q.
+    /// `gain_db` is the gain above the `cutoff` frequency in dB.
+    #[inline]
+    pub fn high_shelf(sample_rate: f32, q: f32, cutoff: f32, gain_db: f32) -> BiquadCoefs {
+        let f = (cutoff * PI / sample_rate).tan();
+        let a0r = 1.0 / (1.0 + f / q + f * f);
+        let vh = (10.0_f32).powf(gain_db / 20.0);
+        let vb = vh.sqrt();
+
+        let b0 = (vh + vb * f / q + f * f) * a0r;
+        let b1 = 2.0 * (f * f - vh) * a0r;
+        let b2 = (vh - vb * f / q + f * f) * a0r;
+        let a1 = 2.0 * (f * f - 1.0) * a0r;
+        let a2 = (1.0 - f / q + f * f) * a0r;
+
+        BiquadCoefs { a1, a2, b0, b1, b2 }
+    }
+
     /// Returns settings for a constant-gain bandpass resonator.
     /// The center frequency is given in Hz.
     /// Bandwidth is the difference in Hz between -3 dB points of the filter response.
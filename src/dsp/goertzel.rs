@@ -3,6 +3,60 @@
 // See README.md and COPYING for details.
 const PI: f32 = std::f32::consts::PI;
 
+/// The lowest level in dBFS that is reported by [Goertzel::amplitude_db].
+pub const GOERTZEL_MIN_DB: f32 = -144.0;
+
+/// Window functions that can be applied to the input of a [Goertzel] filter.
+/// A window reduces the leakage of frequencies next to the analyzed
+/// one, at the cost of a wider main lobe.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GoertzelWindow {
+    /// No window at all.
+    #[default]
+    Rect,
+    Hann,
+    Hamming,
+    BlackmanHarris,
+}
+
+impl GoertzelWindow {
+    /// Maps a setting value (for instance from a node atom) to a window.
+    pub fn from_setting(s: i64) -> Self {
+        match s {
+            1 => GoertzelWindow::Hann,
+            2 => GoertzelWindow::Hamming,
+            3 => GoertzelWindow::BlackmanHarris,
+            _ => GoertzelWindow::Rect,
+        }
+    }
+
+    /// Returns the weight of sample `pos` in a window of `len` samples.
+    #[inline]
+    pub fn weight(&self, pos: usize, len: usize) -> f32 {
+        let x = 2.0 * PI * (pos as f32) / (len as f32);
+        match self {
+            GoertzelWindow::Rect => 1.0,
+            GoertzelWindow::Hann => 0.5 - 0.5 * x.cos(),
+            GoertzelWindow::Hamming => 0.54 - 0.46 * x.cos(),
+            GoertzelWindow::BlackmanHarris => {
+                0.35875 - 0.48829 * x.cos() + 0.14128 * (2.0 * x).cos() - 0.01168 * (3.0 * x).cos()
+            }
+        }
+    }
+
+    /// Returns the coherent gain of the window, the average of all weights.
+    /// This is used to normalize the amplitude of a windowed signal.
+    #[inline]
+    pub fn coherent_gain(&self) -> f32 {
+        match self {
+            GoertzelWindow::Rect => 1.0,
+            GoertzelWindow::Hann => 0.5,
+            GoertzelWindow::Hamming => 0.54,
+            GoertzelWindow::BlackmanHarris => 0.35875,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct GoertzelParams {}
 #[derive(Debug, Clone, Default)]
@@ -10,9 +64,11 @@ pub struct Goertzel {
     pub target_freq: f32,
     pub coeff: f32,
 
+    w: f32,
     q0: f32,
     q1: f32,
     q2: f32,
+    count: usize,
 }
 const DEFAULT_BUFFSIZE: usize = 100;
 // Calculates an individual term of the Discrete Fourier Series
@@ -21,7 +77,7 @@ impl Goertzel {
     pub fn new() -> Self {
         let mut s: Goertzel = Default::default();
         s.setCoeff(880.0, DEFAULT_BUFFSIZE, 44100.0);
-        (s.q0, s.q1, s.q2) = (0.0, 0.0, 0.0);
+        s.reset();
         s
     }
 
@@ -36,27 +92,374 @@ impl Goertzel {
         self.q0 = 0.0;
         self.q1 = 0.0;
         self.q2 = 0.0;
+        self.count = 0;
     }
 
+    /// Sets the analyzed frequency to the DFT bin that is closest to `tfreq`
+    /// for a window of `buffsize` samples. For short windows the analyzed
+    /// frequency can be quite far off from `tfreq`, see also [Goertzel::set_freq].
     pub fn setCoeff(&mut self, tfreq: f32, buffsize: usize, srate: f32) {
         self.target_freq = tfreq;
-        let k = (0.5 * ((buffsize as f32 * self.target_freq) / srate as f32)).floor() as f32;
-        let w = (2.0 * PI * k / buffsize as f32) as f32;
-        let c = f32::cos(w);
+        // k is the bin closest to the target frequency for a window of `buffsize`:
+        let k = (0.5 + ((buffsize as f32 * self.target_freq) / srate)).floor();
+        self.w = 2.0 * PI * k / buffsize as f32;
+        let c = f32::cos(self.w);
         self.coeff = 2.0 * c;
     }
 
-    #[inline]
-    pub fn tick(&mut self, input: f32) -> f32 {
-        let x0 = input;
+    /// Sets the analyzed frequency to exactly `tfreq`. This is the generalized
+    /// Goertzel algorithm with a non-integer `k`, which works for
+    /// any window length.
+    pub fn set_freq(&mut self, tfreq: f32, srate: f32) {
+        self.target_freq = tfreq;
+        self.w = 2.0 * PI * tfreq / srate;
+        self.coeff = 2.0 * f32::cos(self.w);
+    }
 
-        self.q0 = self.coeff * self.q1 - self.q2 + x0;
+    /// Feeds one sample into the filter, without calculating the magnitude.
+    /// Use this if you only need the magnitude at the end of a window,
+    /// and query it with [Goertzel::magnitude] or [Goertzel::amplitude].
+    /// For windowed analysis multiply `input` with [GoertzelWindow::weight].
+    #[inline]
+    pub fn step(&mut self, input: f32) {
+        self.q0 = self.coeff * self.q1 - self.q2 + input;
         self.q2 = self.q1;
         self.q1 = self.q0;
+        self.count += 1;
+    }
+
+    /// Returns the (unnormalized) magnitude of the bin
+    /// for the samples fed in since the last [Goertzel::reset].
+    #[inline]
+    pub fn magnitude(&self) -> f32 {
+        let mag_squared = self.q1.powi(2) + self.q2.powi(2) - (self.q1 * self.q2 * self.coeff);
+
+        f32::sqrt(mag_squared.max(0.0))
+    }
+
+    /// Returns the (unnormalized) real and imaginary part of the bin
+    /// for the samples fed in since the last [Goertzel::reset].
+    /// The phase is relative to the first sample after the reset,
+    /// a cosine starting at that sample has a phase of 0.
+    #[inline]
+    pub fn complex(&self) -> (f32, f32) {
+        if self.count == 0 {
+            return (0.0, 0.0);
+        }
+
+        let (sin_w, cos_w) = self.w.sin_cos();
+        let re = self.q1 - self.q2 * cos_w;
+        let im = self.q2 * sin_w;
+
+        // The filter state refers to the last sample,
+        // rotate the result back to the first one:
+        let rot = ((self.w as f64) * ((self.count - 1) as f64))
+            .rem_euclid(2.0 * std::f64::consts::PI) as f32;
+        let (sin_r, cos_r) = rot.sin_cos();
+
+        (re * cos_r + im * sin_r, im * cos_r - re * sin_r)
+    }
+
+    /// Returns the phase of the bin in radians (-PI..PI),
+    /// see also [Goertzel::complex].
+    #[inline]
+    pub fn phase(&self) -> f32 {
+        let (re, im) = self.complex();
+        im.atan2(re)
+    }
+
+    /// Returns the real and imaginary part of the bin, normalized like
+    /// [Goertzel::amplitude].
+    #[inline]
+    pub fn complex_normalized(&self, window: GoertzelWindow) -> (f32, f32) {
+        if self.count == 0 {
+            return (0.0, 0.0);
+        }
+
+        let norm = 2.0 / ((self.count as f32) * window.coherent_gain());
+        let (re, im) = self.complex();
+        (re * norm, im * norm)
+    }
+
+    /// Returns the amplitude of the analyzed frequency in the samples fed
+    /// in since the last [Goertzel::reset]. A sine with an amplitude of 1.0
+    /// at the analyzed frequency results in roughly 1.0. `window` is
+    /// the window function that was applied to the input.
+    #[inline]
+    pub fn amplitude(&self, window: GoertzelWindow) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+
+        (2.0 * self.magnitude()) / ((self.count as f32) * window.coherent_gain())
+    }
+
+    /// Like [Goertzel::amplitude], but returns the level in dBFS. The result
+    /// is limited to [GOERTZEL_MIN_DB].
+    #[inline]
+    pub fn amplitude_db(&self, window: GoertzelWindow) -> f32 {
+        amp_to_db(self.amplitude(window))
+    }
+
+    /// Feeds one sample into the filter and returns the amplitude of the
+    /// samples fed in since the last [Goertzel::reset], see [Goertzel::amplitude].
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> f32 {
+        self.step(input);
+        self.amplitude(GoertzelWindow::Rect)
+    }
+}
+
+/// Converts a linear amplitude to dBFS, limited to [GOERTZEL_MIN_DB].
+#[inline]
+pub fn amp_to_db(amp: f32) -> f32 {
+    (20.0 * amp.max(0.0).log10()).max(GOERTZEL_MIN_DB)
+}
+
+/// The maximum number of bands a [GoertzelBank] can analyze.
+pub const MAX_BANK_BANDS: usize = 32;
+
+/// The maximum number of overlapping windows a [GoertzelBank] runs
+/// per band. This limits the smallest possible hop size to
+/// `window / MAX_BANK_OVERLAP`.
+pub const MAX_BANK_OVERLAP: usize = 8;
+
+/// A bank of [Goertzel] filters that analyze the same input signal
+/// over a common window length.
+///
+/// The analysis runs in a hopped (sliding) mode: A new window is started
+/// every `hop` samples, and each band runs one [Goertzel] per overlapping
+/// window. Whenever a window is completed, the amplitudes of all bands are
+/// latched and can be retrieved with [GoertzelBank::level].
+/// Window length and hop size are given in samples, so the results
+/// do not depend on how the input is split up into audio blocks.
+/// If the hop size equals the window length the windows do not overlap.
+///
+/// The bands use the generalized Goertzel algorithm, so they analyze
+/// exactly the requested frequencies, independent of the window length.
+/// A [GoertzelWindow] can be applied with [GoertzelBank::set_window_func].
+#[derive(Debug, Clone)]
+pub struct GoertzelBank {
+    bands: [[Goertzel; MAX_BANK_OVERLAP]; MAX_BANK_BANDS],
+    /// The center frequencies of the bands. Each window takes them over
+    /// when it starts.
+    freqs: [f32; MAX_BANK_BANDS],
+    levels: [f32; MAX_BANK_BANDS],
+    complex: [(f32, f32); MAX_BANK_BANDS],
+    /// Position of each overlapping window relative to its start,
+    /// negative while the window waits for it's first start.
+    win_pos: [isize; MAX_BANK_OVERLAP],
+    band_count: usize,
+    win_len: usize,
+    hop: usize,
+    overlap: usize,
+    hop_pos: usize,
+    window: GoertzelWindow,
+    srate: f32,
+}
 
-        let mag_squared =
-            (self.q1.powi(2) + (self.q2.powi(2)) - (self.q1 * self.q2 * self.coeff)) as f32;
+impl GoertzelBank {
+    pub fn new() -> Self {
+        let mut this = Self {
+            bands: Default::default(),
+            freqs: [0.0; MAX_BANK_BANDS],
+            levels: [0.0; MAX_BANK_BANDS],
+            complex: [(0.0, 0.0); MAX_BANK_BANDS],
+            win_pos: [0; MAX_BANK_OVERLAP],
+            band_count: 0,
+            win_len: DEFAULT_BUFFSIZE,
+            hop: DEFAULT_BUFFSIZE,
+            overlap: 1,
+            hop_pos: 0,
+            window: GoertzelWindow::Rect,
+            srate: 44100.0,
+        };
+        this.reset();
+        this
+    }
+
+    /// Sets the sample rate. The analysis is only restarted if the
+    /// sample rate actually changed.
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        if srate != self.srate {
+            self.srate = srate;
+            self.recalc_coeffs();
+            self.reset();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for band in self.bands.iter_mut() {
+            for g in band.iter_mut() {
+                g.reset();
+            }
+        }
+        for (i, wp) in self.win_pos.iter_mut().enumerate() {
+            *wp = -((i * self.hop) as isize);
+        }
+        self.levels = [0.0; MAX_BANK_BANDS];
+        self.complex = [(0.0, 0.0); MAX_BANK_BANDS];
+        self.hop_pos = 0;
+    }
+
+    fn recalc_coeffs(&mut self) {
+        for (band, freq) in self.bands.iter_mut().zip(self.freqs.iter()).take(self.band_count) {
+            for g in band.iter_mut() {
+                g.set_freq(*freq, self.srate);
+            }
+        }
+    }
+
+    /// Sets the window length and the hop size in samples.
+    /// The hop size is limited to the range between
+    /// `win_len / MAX_BANK_OVERLAP` and `win_len`.
+    /// The bands restart their analysis if anything changed.
+    pub fn set_window(&mut self, win_len: usize, hop: usize) {
+        let win_len = win_len.max(1);
+        let hop = hop.clamp(win_len.div_ceil(MAX_BANK_OVERLAP), win_len);
+
+        if win_len != self.win_len || hop != self.hop {
+            self.win_len = win_len;
+            self.hop = hop;
+            self.overlap = win_len.div_ceil(hop);
+            self.reset();
+        }
+    }
+
+    /// Sets the window function that is applied to the input of each
+    /// analysis window. The bands restart their analysis if it changed.
+    pub fn set_window_func(&mut self, window: GoertzelWindow) {
+        if window != self.window {
+            self.window = window;
+            self.reset();
+        }
+    }
+
+    /// Sets the center frequencies of the bands. At most [MAX_BANK_BANDS]
+    /// frequencies are used. If the number of bands changed, the
+    /// analysis is restarted. If only frequencies changed, the windows
+    /// that are already running finish with the previous frequencies,
+    /// and each window that starts afterwards uses the new ones.
+    pub fn set_bands(&mut self, freqs: &[f32]) {
+        let count = freqs.len().min(MAX_BANK_BANDS);
+
+        for (bf, f) in self.freqs.iter_mut().zip(freqs.iter()) {
+            *bf = *f;
+        }
+
+        if count != self.band_count {
+            self.band_count = count;
+            self.recalc_coeffs();
+            self.reset();
+        }
+    }
+
+    #[inline]
+    pub fn band_count(&self) -> usize {
+        self.band_count
+    }
+
+    #[inline]
+    pub fn window(&self) -> usize {
+        self.win_len
+    }
+
+    /// Returns the effective hop size in samples.
+    #[inline]
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Returns true if the next sample passed to [GoertzelBank::tick]
+    /// starts a new hop. This is the place to apply parameter changes
+    /// without making them depend on the audio block size.
+    #[inline]
+    pub fn is_hop_start(&self) -> bool {
+        self.hop_pos == 0
+    }
+
+    /// Returns the amplitude of the band `idx` as measured in the most
+    /// recently completed window. A sine with amplitude 1.0 at the center
+    /// frequency of the band results in roughly 1.0.
+    #[inline]
+    pub fn level(&self, idx: usize) -> f32 {
+        self.levels[idx]
+    }
+
+    /// Returns the normalized real and imaginary part of the band `idx`
+    /// as measured in the most recently completed window. The phase
+    /// is relative to the start of that window.
+    #[inline]
+    pub fn complex(&self, idx: usize) -> (f32, f32) {
+        self.complex[idx]
+    }
+
+    /// Returns the phase of the band `idx` in radians (-PI..PI),
+    /// see also [GoertzelBank::complex].
+    #[inline]
+    pub fn phase(&self, idx: usize) -> f32 {
+        let (re, im) = self.complex[idx];
+        im.atan2(re)
+    }
+
+    /// Like [GoertzelBank::level], but returns the level in dBFS.
+    #[inline]
+    pub fn level_db(&self, idx: usize) -> f32 {
+        amp_to_db(self.levels[idx])
+    }
+
+    /// Feeds one sample into all bands. Returns true if a window
+    /// was completed and new levels are available.
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> bool {
+        let win_len = self.win_len as isize;
+        let period = (self.overlap * self.hop) as isize;
+        let mut completed = false;
+
+        for (i, wp) in self.win_pos.iter_mut().enumerate().take(self.overlap) {
+            if *wp == 0 {
+                for (band, freq) in
+                    self.bands.iter_mut().zip(self.freqs.iter()).take(self.band_count)
+                {
+                    if band[i].target_freq != *freq {
+                        band[i].set_freq(*freq, self.srate);
+                    }
+                }
+            }
+
+            if *wp >= 0 && *wp < win_len {
+                let input = input * self.window.weight(*wp as usize, self.win_len);
+                for band in self.bands.iter_mut().take(self.band_count) {
+                    band[i].step(input);
+                }
+
+                if *wp == win_len - 1 {
+                    for (j, band) in self.bands.iter_mut().enumerate().take(self.band_count) {
+                        self.levels[j] = band[i].amplitude(self.window);
+                        self.complex[j] = band[i].complex_normalized(self.window);
+                        band[i].reset();
+                    }
+                    completed = true;
+                }
+            }
+
+            *wp += 1;
+            if *wp >= period {
+                *wp = 0;
+            }
+        }
+
+        self.hop_pos += 1;
+        if self.hop_pos >= self.hop {
+            self.hop_pos = 0;
+        }
+
+        completed
+    }
+}
 
-        f32::sqrt(mag_squared) / 100.0
+impl Default for GoertzelBank {
+    fn default() -> Self {
+        Self::new()
     }
 }
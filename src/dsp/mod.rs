@@ -496,24 +496,42 @@ mod node_bosc;
 #[allow(non_upper_case_globals)]
 mod node_bowstri;
 #[allow(non_upper_case_globals)]
+mod node_chroma;
+#[allow(non_upper_case_globals)]
+mod node_clock;
+#[allow(non_upper_case_globals)]
 mod node_comb;
 #[allow(non_upper_case_globals)]
 mod node_cqnt;
 #[allow(non_upper_case_globals)]
 mod node_delay;
 #[allow(non_upper_case_globals)]
+mod node_dtmf;
+#[allow(non_upper_case_globals)]
 mod node_fbwr_fbrd;
 #[allow(non_upper_case_globals)]
+mod node_follow;
+#[allow(non_upper_case_globals)]
+mod node_gzbank;
+#[allow(non_upper_case_globals)]
+mod node_in;
+#[allow(non_upper_case_globals)]
 mod node_map;
 #[allow(non_upper_case_globals)]
+mod node_midip;
+#[allow(non_upper_case_globals)]
 mod node_mix3;
 #[allow(non_upper_case_globals)]
 mod node_mux9;
 #[allow(non_upper_case_globals)]
 mod node_noise;
 #[allow(non_upper_case_globals)]
+mod node_onset;
+#[allow(non_upper_case_globals)]
 mod node_out;
 #[allow(non_upper_case_globals)]
+mod node_ptrack;
+#[allow(non_upper_case_globals)]
 mod node_pverb;
 #[allow(non_upper_case_globals)]
 mod node_quant;
@@ -531,18 +549,21 @@ mod node_smap;
 mod node_test;
 #[allow(non_upper_case_globals)]
 mod node_tseq;
-#[allow(non_upper_case_globals)]
-mod node_goertzel;
-pub mod goertzel;
 mod node_tslfo;
 #[allow(non_upper_case_globals)]
+mod node_vocoder;
+#[allow(non_upper_case_globals)]
+mod node_voice;
+#[allow(non_upper_case_globals)]
 mod node_vosc;
 
 pub mod biquad;
 pub mod dattorro;
+pub mod goertzel;
 pub mod helpers;
 mod satom;
 pub mod tracker;
+pub mod yin;
 
 use crate::nodes::NodeAudioContext;
 use crate::nodes::NodeExecContext;
@@ -559,15 +580,28 @@ use crate::fa_amp_neg_att;
 use crate::fa_biqfilt_ord;
 use crate::fa_biqfilt_type;
 use crate::fa_bosc_wtype;
+use crate::fa_chroma_oct;
+use crate::fa_chroma_qual;
+use crate::fa_clock_div;
 use crate::fa_comb_mode;
 use crate::fa_cqnt;
 use crate::fa_cqnt_omax;
 use crate::fa_cqnt_omin;
 use crate::fa_delay_mode;
 use crate::fa_distort;
+use crate::fa_follow_mode;
+use crate::fa_gzbank_bands;
+use crate::fa_gzbank_freqs;
+use crate::fa_gzbank_scale;
+use crate::fa_gzbank_unit;
+use crate::fa_gzbank_wfun;
+use crate::fa_in_chpair;
 use crate::fa_map_clip;
+use crate::fa_midip_chan;
+use crate::fa_midip_pbr;
 use crate::fa_mux9_in_cnt;
 use crate::fa_noise_mode;
+use crate::fa_onset_mode;
 use crate::fa_out_mono;
 use crate::fa_quant;
 use crate::fa_sampl_dclick;
@@ -578,6 +612,7 @@ use crate::fa_smap_clip;
 use crate::fa_smap_mode;
 use crate::fa_test_s;
 use crate::fa_tseq_cmode;
+use crate::fa_vocoder_bands;
 use crate::fa_vosc_ovrsmpl;
 
 use node_ad::Ad;
@@ -586,20 +621,28 @@ use node_amp::Amp;
 use node_biqfilt::BiqFilt;
 use node_bosc::BOsc;
 use node_bowstri::BowStri;
+use node_chroma::Chroma;
+use node_clock::Clock;
 use node_comb::Comb;
 use node_cqnt::CQnt;
 use node_delay::Delay;
+use node_dtmf::Dtmf;
 use node_fbwr_fbrd::FbRd;
 use node_fbwr_fbrd::FbWr;
+use node_follow::Follow;
+use node_gzbank::GzBank;
+use node_in::In;
 use node_map::Map;
+use node_midip::MidiP;
 use node_mix3::Mix3;
 use node_mux9::Mux9;
 use node_noise::Noise;
+use node_onset::Onset;
 use node_out::Out;
+use node_ptrack::PTrack;
 use node_pverb::PVerb;
 use node_quant::Quant;
 use node_rndwk::RndWk;
-use node_goertzel::Gz3Filt;
 use node_sampl::Sampl;
 use node_sfilter::SFilter;
 use node_sin::Sin;
@@ -607,12 +650,23 @@ use node_smap::SMap;
 use node_test::Test;
 use node_tseq::TSeq;
 use node_tslfo::TsLFO;
+use node_vocoder::Vocoder;
+use node_voice::Voice;
 use node_vosc::VOsc;
 
 pub const MIDI_MAX_FREQ: f32 = 13289.75;
 
+/// The default maximum number of frames the nodes process at once.
+/// A different maximum can be given to
+/// [crate::nodes::new_node_engine_with_block_size], which sizes the [ProcBuf]
+/// instances accordingly. The [crate::nodes::NodeExecutor] splits longer
+/// audio periods into blocks of it's maximum block size, see
+/// [crate::nodes::NodeExecutor::set_max_block_size].
 pub const MAX_BLOCK_SIZE: usize = 128;
 
+/// The maximum number of audio channels the `Out` node writes to.
+pub const MAX_OUTPUT_CHANNELS: usize = 16;
+
 /// A context structure that holds temporary information about the
 /// currently executed node.
 /// This structure is created by the [crate::nodes::NodeExecutor] on the fly.
@@ -625,6 +679,9 @@ pub struct NodeContext<'a> {
     pub out_connected: u64,
     /// The node parameters, which are usually not accessed directly.
     pub params: &'a [ProcBuf],
+    /// The voice this node is playing, if it is part of the voice region.
+    /// See also [crate::nodes::NodeConfigurator::set_voice_region].
+    pub voice: usize,
 }
 
 /// This trait is an interface between the graph functions
@@ -665,6 +722,9 @@ pub trait DspNode {
     /// * `inputs` contain all the possible inputs. In contrast to `params`
     /// these inputs might be overwritten by outputs of other nodes.
     /// * `outputs` are the output buffers of this node.
+    ///
+    /// Nodes that use the input/output ports of `ctx` or anything
+    /// from `ectx` must be listed in [NodeId::is_context_bound].
     fn process<T: NodeAudioContext>(
         &mut self,
         ctx: &mut T,
@@ -682,6 +742,26 @@ pub trait DspNode {
     }
 }
 
+impl NodeId {
+    /// Returns true if the node accesses the [NodeAudioContext] or the
+    /// [NodeExecContext] in [DspNode::process]. These nodes are always
+    /// executed on the audio thread, while the other nodes might be
+    /// executed on the worker threads, see
+    /// [crate::nodes::NodeConfigurator::set_worker_threads].
+    pub fn is_context_bound(&self) -> bool {
+        matches!(
+            self,
+            NodeId::Out(_)
+                | NodeId::In(_)
+                | NodeId::FbWr(_)
+                | NodeId::FbRd(_)
+                | NodeId::MidiP(_)
+                | NodeId::Voice(_)
+                | NodeId::Clock(_)
+        )
+    }
+}
+
 /// A processing buffer with the exact right maximum size.
 /// This is an unsafe abstraction, and should be used with a lot of care.
 /// You will have to manually free the buffer, and take care if you
@@ -707,17 +787,22 @@ pub trait DspNode {
 /// buf.free(); // YOU MUST DO THIS!
 ///```
 #[derive(Clone, Copy)]
-pub struct ProcBuf(*mut [f32; MAX_BLOCK_SIZE]);
+pub struct ProcBuf(*mut [f32]);
 
 impl ProcBuf {
     /// Creates a new ProcBuf with the size of [MAX_BLOCK_SIZE].
     pub fn new() -> Self {
-        ProcBuf(Box::into_raw(Box::new([0.0; MAX_BLOCK_SIZE])))
+        Self::with_len(MAX_BLOCK_SIZE)
+    }
+
+    /// Creates a new ProcBuf that holds `len` samples.
+    pub fn with_len(len: usize) -> Self {
+        ProcBuf(Box::into_raw(vec![0.0; len].into_boxed_slice()))
     }
 
     /// Create a new null ProcBuf, that can't be used.
     pub fn null() -> Self {
-        ProcBuf(std::ptr::null_mut())
+        ProcBuf(std::ptr::slice_from_raw_parts_mut(std::ptr::null_mut(), 0))
     }
 }
 
@@ -727,7 +812,7 @@ impl crate::monitor::MonitorSource for &ProcBuf {
     /// * `len` - the number of samples to copy from this [ProcBuf].
     /// * `slice` - the slice to copy to.
     fn copy_to(&self, len: usize, slice: &mut [f32]) {
-        unsafe { slice.copy_from_slice(&(*self.0)[0..len]) }
+        unsafe { slice.copy_from_slice(&(&*self.0)[0..len]) }
     }
 }
 
@@ -745,17 +830,17 @@ impl ProcBuf {
     }
 
     /// Writes the samples from `slice` to this [ProcBuf].
-    /// Be careful, the `slice` must not exceed [MAX_BLOCK_SIZE], or else
+    /// Be careful, the `slice` must not exceed [ProcBuf::capacity], or else
     /// you will get UB.
     #[inline]
     pub fn write_from(&mut self, slice: &[f32]) {
         unsafe {
-            (*self.0)[0..slice.len()].copy_from_slice(slice);
+            (&mut *self.0)[0..slice.len()].copy_from_slice(slice);
         }
     }
 
     /// Reads a sample at `idx`. Be careful to not let the `idx`
-    /// land outside of [MAX_BLOCK_SIZE].
+    /// land outside of [ProcBuf::capacity].
     #[inline]
     pub fn read(&self, idx: usize) -> f32 {
         unsafe { (*self.0)[idx] }
@@ -775,6 +860,12 @@ impl ProcBuf {
         self.0.is_null()
     }
 
+    /// The number of samples this [ProcBuf] holds.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.0.len()
+    }
+
     /// Deallocates the [ProcBuf]. If you still keep around
     /// other copies of this [ProcBuf], you will most likely land in
     /// UB land.
@@ -792,8 +883,8 @@ impl std::fmt::Debug for ProcBuf {
             if self.0.is_null() {
                 write!(f, "NULL ")?;
             } else {
-                for i in 0..MAX_BLOCK_SIZE {
-                    write!(f, "{:5.4} ", (*self.0)[i])?;
+                for v in (*self.0).iter() {
+                    write!(f, "{:5.4} ", v)?;
                 }
             }
             write!(f, ")")
@@ -1250,6 +1341,8 @@ define_lin! {n_pgin d_pgin 1.0, 10.0}
 
 define_lin! {n_vps d_vps 0.0, 20.0}
 
+define_lin! {n_gzspc d_gzspc 0.0, 12.0}
+
 // A note about the input-indicies:
 //
 // Atoms and Input parameters share the same global ID space
@@ -1394,13 +1487,32 @@ macro_rules! node_list {
             out => Out UIType::Generic UICategory::IOUtil
                (0  ch1   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
                (1  ch2   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
-               (2  gain  n_ogin    d_ogin r_id  f_def  stp_d  0.0, 1.0, 1.0)
+               (2  ch3   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (3  ch4   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (4  ch5   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (5  ch6   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (6  ch7   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (7  ch8   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (8  ch9   n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (9  ch10  n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (10 ch11  n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (11 ch12  n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (12 ch13  n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (13 ch14  n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (14 ch15  n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (15 ch16  n_id      d_id  r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (16 gain  n_ogin    d_ogin r_id  f_def  stp_d  0.0, 1.0, 1.0)
              // node_param_idx      UI widget type (mode, knob, sample)
              // | atom_idx          |     format fun
              // | | name constructor|     |     min max
              // | | |    |       def|ult_v|lue  |  /
              // | | |    |       |  |     |     |  |
-               {3 0 mono setting(0) mode fa_out_mono 0  1},
+               {17 0 mono setting(0) mode fa_out_mono 0  1},
+            in   => In   UIType::Generic UICategory::IOUtil
+               (0  gain  n_ogin    d_ogin r_id  f_def  stp_d  0.0, 1.0, 1.0)
+               {1 0 chp  setting(0) mode fa_in_chpair 0  7}
+               [0 sig1]
+               [1 sig2],
             fbwr => FbWr UIType::Generic UICategory::IOUtil
                (0  inp   n_id      d_id  r_id   f_def stp_d -1.0, 1.0, 0.0),
             fbrd => FbRd UIType::Generic UICategory::IOUtil
@@ -1421,6 +1533,15 @@ macro_rules! node_list {
                 (1 trig  n_id      d_id  r_id   f_def stp_d -1.0, 1.0, 0.0)
                 (2 rev   n_id      d_id  r_id   f_def stp_d  0.0, 1.0, 0.5)
                 [0 sig],
+            clock => Clock UIType::Generic UICategory::Mod
+                (0 swing n_id      d_id  r_id   f_def stp_d  0.0, 1.0, 0.0)
+                (1 pw    n_id      d_id  r_id   f_def stp_d  0.0, 1.0, 0.5)
+                {2 0 div   setting(7) mode fa_clock_div 0 15}
+                [0 clk]
+                [1 beat]
+                [2 bar]
+                [3 rst]
+                [4 phase],
             rndwk => RndWk UIType::Generic UICategory::Mod
                 (0 trig  n_id      d_id  r_id   f_def stp_d -1.0, 1.0, 0.0)
                 (1 step  n_id      d_id  r_id   f_def stp_d  0.0, 1.0, 0.2)
@@ -1429,6 +1550,30 @@ macro_rules! node_list {
                 (4 max   n_id      d_id  r_id   f_def stp_d  0.0, 1.0, 1.0)
                 (5 slew  n_timz   d_timz r_tmz  f_ms  stp_m  0.0, 1.0, 75.0)
                 [0 sig],
+            follow => Follow UIType::Generic UICategory::Mod
+                (0 inp   n_id      d_id  r_id   f_def stp_d -1.0, 1.0, 0.0)
+                (1 atk   n_env     d_env r_ems  f_ms  stp_m  0.0, 1.0, 5.0)
+                (2 rel   n_env     d_env r_ems  f_ms  stp_m  0.0, 1.0, 100.0)
+                (3 thrs  n_att     d_att r_id   f_def stp_d  0.0, 1.0, 0.1)
+                (4 hyst  n_id      d_id  r_id   f_def stp_d  0.0, 1.0, 0.25)
+                {5 0 mode setting(0) mode fa_follow_mode 0 2}
+                [0 env]
+                [1 gat],
+            voice => Voice UIType::Generic UICategory::Mod
+                (0 vsens n_id      d_id  r_id   f_def stp_d  0.0, 1.0, 1.0)
+                [0 pit]
+                [1 gate]
+                [2 vel],
+            midip => MidiP UIType::Generic UICategory::Mod
+                (0 glide n_timz   d_timz r_tmz  f_ms  stp_m  0.0, 1.0, 0.0)
+                {1 0 chan setting(0) mode fa_midip_chan 0 16}
+                {2 1 pbr  setting(2) mode fa_midip_pbr  0 24}
+                [0 pit]
+                [1 gate]
+                [2 vel]
+                [3 at]
+                [4 pb]
+                [5 mw],
             delay => Delay UIType::Generic UICategory::Signal
                (0  inp   n_id      d_id  r_id   f_def stp_d -1.0, 1.0, 0.0)
                (1  trig  n_id      d_id  r_id   f_def stp_d -1.0, 1.0, 0.0)
@@ -1485,16 +1630,95 @@ macro_rules! node_list {
                (14 mix   n_id      d_id  r_id   f_def stp_d  0.0, 1.0, 0.5)
                [0 sig_l]
                [1 sig_r],
-               
-            goertzel => Gz3Filt UIType::Generic UICategory::Signal
-            (0 inp n_id d_id r_id f_def stp_d -1.0, 1.0, 0.0)
-            (1 freq1   n_pit     d_pit r_fq  f_freq stp_d 0.0, 20000.0, 220.0)
-            (2 freq2   n_pit     d_pit r_fq  f_freq stp_d 0.0, 20000.0, 330.0)
-            (3 freq3   n_pit     d_pit r_fq  f_freq stp_d 0.0, 20000.0, 440.0)
-            (4 latency n_pit     d_pit r_fq  f_ms   stp_d 256.0, 65536.0, 2048.0)
-            (5 gain   n_ogin   d_ogin r_id   f_def stp_d 0.0, 1.0, 1.0)
-            [0 sigf1][1 sigf2][2 sigf3],
-
+            gzbank => GzBank UIType::Generic UICategory::Signal
+               (0 inp    n_id      d_id  r_id   f_def stp_d -1.0, 1.0, 0.0)
+               (1 freq   n_pit     d_pit r_fq  f_freq stp_d -1.0, 0.5647131, 110.0)
+               (2 spc    n_gzspc  d_gzspc r_id  f_def stp_d  0.0, 1.0, 1.0)
+               (3 win    n_time   d_time r_tms  f_ms  stp_m  0.0, 1.0, 50.0)
+               (4 hop    n_time   d_time r_tms  f_ms  stp_m  0.0, 1.0, 50.0)
+               (5 gain   n_ogin   d_ogin r_id   f_def stp_d  0.0, 1.0, 1.0)
+               {6 0 bands setting(8)   mode  fa_gzbank_bands 1 32}
+               {7 1 scale setting(0)   mode  fa_gzbank_scale 0 3}
+               {8 2 freqs micro(&[])   micro fa_gzbank_freqs 0 0}
+               {9 3 wfun  setting(0)   mode  fa_gzbank_wfun 0 3}
+               {10 4 pband setting(1)  mode  fa_gzbank_bands 1 32}
+               {11 5 unit  setting(0)  mode  fa_gzbank_unit 0 1}
+               [0 b1]
+               [1 b2]
+               [2 b3]
+               [3 b4]
+               [4 b5]
+               [5 b6]
+               [6 b7]
+               [7 b8]
+               [8 b9]
+               [9 b10]
+               [10 b11]
+               [11 b12]
+               [12 b13]
+               [13 b14]
+               [14 b15]
+               [15 b16]
+               [16 b17]
+               [17 b18]
+               [18 b19]
+               [19 b20]
+               [20 b21]
+               [21 b22]
+               [22 b23]
+               [23 b24]
+               [24 b25]
+               [25 b26]
+               [26 b27]
+               [27 b28]
+               [28 b29]
+               [29 b30]
+               [30 b31]
+               [31 b32]
+               [32 re]
+               [33 im]
+               [34 ph],
+            dtmf => Dtmf UIType::Generic UICategory::Signal
+               (0 inp    n_id      d_id   r_id   f_def stp_d -1.0, 1.0, 0.0)
+               (1 thrs   n_id      d_id   r_id   f_def stp_d  0.0, 1.0, 0.1)
+               (2 win    n_time   d_time r_tms  f_ms  stp_m  0.0, 1.0, 30.0)
+               [0 key]
+               [1 t]
+               [2 gat],
+            chroma => Chroma UIType::Generic UICategory::Signal
+               (0 inp    n_id      d_id   r_id   f_def stp_d -1.0, 1.0, 0.0)
+               (1 thrs   n_id      d_id   r_id   f_def stp_d  0.0, 1.0, 0.05)
+               (2 win    n_time   d_time r_tms  f_ms  stp_m  0.0, 1.0, 150.0)
+               {3 0 qual setting(0)    mode  fa_chroma_qual 0 10}
+               {4 1 oct  setting(3)    mode  fa_chroma_oct  1 5}
+               [0 root]
+               [1 conf]
+               [2 t],
+            ptrack => PTrack UIType::Generic UICategory::Signal
+               (0 inp    n_id      d_id   r_id   f_def  stp_d -1.0, 1.0, 0.0)
+               (1 fmin   n_pit     d_pit  r_fq   f_freq stp_d -1.0, 0.5647131, 50.0)
+               (2 fmax   n_pit     d_pit  r_fq   f_freq stp_d -1.0, 0.5647131, 2000.0)
+               (3 thrs   n_id      d_id   r_id   f_def  stp_d  0.0, 1.0, 0.8)
+               [0 freq]
+               [1 conf]
+               [2 gat],
+            vocoder => Vocoder UIType::Generic UICategory::Signal
+               (0 modu   n_id      d_id   r_id   f_def stp_d -1.0, 1.0, 0.0)
+               (1 car    n_id      d_id   r_id   f_def stp_d -1.0, 1.0, 0.0)
+               (2 atk    n_env     d_env  r_ems  f_ms  stp_m  0.0, 1.0, 5.0)
+               (3 rel    n_env     d_env  r_ems  f_ms  stp_m  0.0, 1.0, 50.0)
+               (4 fshft  n_det     d_det  r_det  f_det stp_f -0.2, 0.2, 0.0)
+               (5 gain   n_ogin    d_ogin r_id   f_def stp_d  0.0, 1.0, 1.0)
+               {6 0 bands setting(16) mode fa_vocoder_bands 4 32}
+               [0 sig],
+            onset => Onset UIType::Generic UICategory::Signal
+               (0 inp    n_id      d_id   r_id   f_def stp_d -1.0, 1.0, 0.0)
+               (1 thrs   n_id      d_id   r_id   f_def stp_d  0.0, 1.0, 0.05)
+               (2 avg    n_time   d_time r_tms  f_ms  stp_m  0.0, 1.0, 250.0)
+               (3 hold   n_time   d_time r_tms  f_ms  stp_m  0.0, 1.0, 50.0)
+               {4 0 mode setting(0) mode fa_onset_mode 0 1}
+               [0 t]
+               [1 odf],
             test => Test UIType::Generic UICategory::IOUtil
                (0 f     n_id      d_id   r_id   f_def stp_d 0.0, 1.0, 0.5)
                {1 0 p     param(0.0) knob fa_test_s 0  10}
@@ -1857,7 +2081,7 @@ macro_rules! make_node_info_enum {
         #[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq, Ord, Hash)]
         pub enum NodeId {
             $v1,
-            $($variant(u8)),+
+            $($variant(u16)),+
         }
 
         impl std::fmt::Display for NodeId {
@@ -1873,7 +2097,7 @@ macro_rules! make_node_info_enum {
             pub fn to_instance(&self, instance: usize) -> NodeId {
                 match self {
                     NodeId::$v1           => NodeId::$v1,
-                    $(NodeId::$variant(_) => NodeId::$variant(instance as u8)),+
+                    $(NodeId::$variant(_) => NodeId::$variant(instance as u16)),+
                 }
             }
 
@@ -2371,7 +2595,7 @@ macro_rules! make_node_enum {
             pub fn to_id(&self, instance: usize) -> NodeId {
                 match self {
                     Node::$v1               => NodeId::$v1,
-                    $(Node::$variant { .. } => NodeId::$variant(instance as u8)),+
+                    $(Node::$variant { .. } => NodeId::$variant(instance as u16)),+
                 }
             }
 
@@ -2511,8 +2735,9 @@ mod tests {
 
     #[test]
     fn check_node_size_staying_small() {
-        assert_eq!(std::mem::size_of::<Node>(), 56);
-        assert_eq!(std::mem::size_of::<NodeId>(), 2);
+        assert_eq!(std::mem::size_of::<Node>(), 48);
+        // The instance is a u16, to allow more than 256 nodes of a kind:
+        assert_eq!(std::mem::size_of::<NodeId>(), 4);
         assert_eq!(std::mem::size_of::<ParamId>(), 24);
     }
 
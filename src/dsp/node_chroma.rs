@@ -0,0 +1,286 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::goertzel::{GoertzelBank, GoertzelWindow};
+use crate::dsp::helpers::TrigSignal;
+use crate::dsp::{DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+#[macro_export]
+macro_rules! fa_chroma_qual {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let s = match ($v.round() as usize) {
+            0 => "Maj/Min",
+            1 => "Maj",
+            2 => "Min",
+            3 => "Dim",
+            4 => "Aug",
+            5 => "Sus2",
+            6 => "Sus4",
+            7 => "7",
+            8 => "Maj7",
+            9 => "Min7",
+            10 => "Note",
+            _ => "?",
+        };
+        write!($formatter, "{}", s)
+    }};
+}
+
+#[macro_export]
+macro_rules! fa_chroma_oct {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let o = ($v.round() as usize).clamp(1, 5);
+        write!($formatter, "C{}-B{}", o, o + 3)
+    }};
+}
+
+/// Number of analyzed octaves.
+const CHROMA_OCTAVES: usize = 4;
+
+/// The intervals of the chord templates, in semitones above the root.
+const CHORD_MAJ: &[usize] = &[0, 4, 7];
+const CHORD_MIN: &[usize] = &[0, 3, 7];
+const CHORD_DIM: &[usize] = &[0, 3, 6];
+const CHORD_AUG: &[usize] = &[0, 4, 8];
+const CHORD_SUS2: &[usize] = &[0, 2, 7];
+const CHORD_SUS4: &[usize] = &[0, 5, 7];
+const CHORD_DOM7: &[usize] = &[0, 4, 7, 10];
+const CHORD_MAJ7: &[usize] = &[0, 4, 7, 11];
+const CHORD_MIN7: &[usize] = &[0, 3, 7, 10];
+const CHORD_NOTE: &[usize] = &[0];
+
+/// Returns the chord templates that are matched for the `qual` setting.
+fn chord_templates(qual: i64) -> &'static [&'static [usize]] {
+    match qual {
+        1 => &[CHORD_MAJ],
+        2 => &[CHORD_MIN],
+        3 => &[CHORD_DIM],
+        4 => &[CHORD_AUG],
+        5 => &[CHORD_SUS2],
+        6 => &[CHORD_SUS4],
+        7 => &[CHORD_DOM7],
+        8 => &[CHORD_MAJ7],
+        9 => &[CHORD_MIN7],
+        10 => &[CHORD_NOTE],
+        _ => &[CHORD_MAJ, CHORD_MIN],
+    }
+}
+
+/// Matches the (unnormalized) `chroma` vector against the chord `templates`.
+/// Returns the best matching root pitch class (0 = C) and the cosine
+/// similarity of the match as confidence (0..1).
+fn match_chord(chroma: &[f32; 12], templates: &[&[usize]]) -> (usize, f32) {
+    let norm = chroma.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if norm <= 0.0 {
+        return (0, 0.0);
+    }
+
+    let mut best = (0, 0.0);
+    for tmpl in templates.iter() {
+        let tmpl_norm = (tmpl.len() as f32).sqrt();
+
+        for root in 0..12 {
+            let sum: f32 = tmpl.iter().map(|i| chroma[(root + i) % 12]).sum();
+            let score = sum / (norm * tmpl_norm);
+            if score > best.1 {
+                best = (root, score);
+            }
+        }
+    }
+
+    best
+}
+
+/// A chromagram based chord detector
+#[derive(Debug, Clone)]
+pub struct Chroma {
+    banks: Box<[GoertzelBank; CHROMA_OCTAVES]>,
+    trig_sig: TrigSignal,
+    srate: f32,
+    oct: i64,
+    root: Option<u8>,
+    conf: f32,
+}
+
+impl Chroma {
+    pub fn new(_nid: &NodeId) -> Self {
+        let mut banks: Box<[GoertzelBank; CHROMA_OCTAVES]> = Box::default();
+        for bank in banks.iter_mut() {
+            bank.set_window_func(GoertzelWindow::Hann);
+        }
+
+        Self { banks, trig_sig: TrigSignal::new(), srate: 44100.0, oct: -1, root: None, conf: 0.0 }
+    }
+    pub const inp: &'static str = "Chroma inp\nSignal input\nRange: (-1..1)\n";
+    pub const thrs: &'static str =
+        "Chroma thrs\nMinimum amplitude of the loudest pitch class. If the input \
+        is quieter, no chord is detected and 'conf' goes to 0.0.\nRange: (0..1)\n";
+    pub const win: &'static str =
+        "Chroma win\nAnalysis window length. Longer windows separate the notes \
+        better, especially in the lower octaves, but react slower.\nRange: (0..1)\n";
+    pub const qual: &'static str =
+        "Chroma qual\nThe chord quality that is detected. 'Maj/Min' detects \
+        major and minor triads, 'Note' detects just the strongest note.\n";
+    pub const oct: &'static str = "Chroma oct\nThe range of the four analyzed octaves.\n";
+    pub const root: &'static str =
+        "Chroma root\nPitch of the detected root note, in the octave from C4 to B4. \
+        The same pitch format as the 'Quant' and 'CQnt' outputs. \
+        The last root is held if no chord is detected.\nRange: (-1..1)\n";
+    pub const conf: &'static str = "Chroma conf\nConfidence of the detected chord, 1.0 means the \
+        input contains exactly the notes of the chord.\nRange: (0..1)\n";
+    pub const t: &'static str =
+        "Chroma t\nEmits a trigger each time a new root is detected.\nRange: (0..1)\n";
+    pub const DESC: &'static str = r#"Chromagram Chord Detector
+
+Analyzes the notes in the input signal and detects the root of a chord of the selected quality. The root is emitted as pitch, with a confidence output.
+"#;
+    pub const HELP: &'static str = r#"Chroma - Chromagram Chord Detector
+
+This node measures the level of every semitone over four octaves of the
+input signal with Goertzel filters. The levels are folded into the
+12 pitch classes (C, C#, ... B), the so called chroma vector.
+
+The chroma vector is compared to the chords of the quality selected
+with 'qual' on each of the 12 possible roots. The root of the best
+matching chord is emitted as pitch on 'root', in the same format as the
+'Quant' and 'CQnt' nodes emit, within the octave from C4 to B4. How
+well the chord matches is emitted on 'conf'. A 1.0 means that exactly
+the notes of the chord are sounding, lower values mean that other notes
+are present or notes of the chord are missing. Each time the root
+changes a trigger is emitted on 't'.
+
+If the input is quieter than 'thrs', 'conf' is 0.0 and the last root is
+held. Use 'conf' to gate sequencers or envelopes, so that only
+clearly detected chords are followed.
+
+Select the analyzed range with 'oct', it should cover the notes of the
+played chords, the default is C3 to B6. Lower octaves need longer
+windows ('win') to separate neighbouring semitones.
+
+Feed the 'root' output into a 'Quant' or directly into an oscillator's
+'freq' to make a patch follow the harmony of a live input.
+"#;
+
+    fn update_bands(&mut self, oct: i64) {
+        self.oct = oct;
+        let nyquist = self.srate * 0.5;
+
+        for (i, bank) in self.banks.iter_mut().enumerate() {
+            let mut freqs = [0.0; 12];
+            for (pc, f) in freqs.iter_mut().enumerate() {
+                // Octave 4 with A at 440Hz is the reference:
+                let semis = ((oct + i as i64 - 4) * 12) as f32 + (pc as f32 - 9.0);
+                *f = (440.0 * (2.0_f32).powf(semis / 12.0)).min(nyquist);
+            }
+            bank.set_bands(&freqs[..]);
+        }
+    }
+
+    fn detect(&mut self, thrs: f32, qual: i64) {
+        let mut chroma = [0.0; 12];
+        for bank in self.banks.iter() {
+            for (pc, c) in chroma.iter_mut().enumerate() {
+                *c += bank.level(pc);
+            }
+        }
+
+        let max = chroma.iter().fold(0.0_f32, |a, c| a.max(*c));
+        if max < thrs {
+            self.conf = 0.0;
+            return;
+        }
+
+        let (root, conf) = match_chord(&chroma, chord_templates(qual));
+        self.conf = conf;
+
+        let root = root as u8;
+        if self.root != Some(root) {
+            self.root = Some(root);
+            self.trig_sig.trigger();
+        }
+    }
+
+    fn root_pitch(&self) -> f32 {
+        self.root.map(|r| (r as f32 - 9.0) / 120.0).unwrap_or(0.0)
+    }
+}
+
+impl DspNode for Chroma {
+    fn outputs() -> usize {
+        3
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        for bank in self.banks.iter_mut() {
+            bank.set_sample_rate(srate);
+        }
+        self.trig_sig.set_sample_rate(srate);
+        self.oct = -1;
+    }
+
+    fn reset(&mut self) {
+        for bank in self.banks.iter_mut() {
+            bank.reset();
+        }
+        self.trig_sig.reset();
+        self.root = None;
+        self.conf = 0.0;
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        _ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        use crate::dsp::{at, denorm, inp, out};
+
+        let inp = inp::Chroma::inp(inputs);
+        let thrs = inp::Chroma::thrs(inputs);
+        let win = inp::Chroma::win(inputs);
+        let qual = at::Chroma::qual(atoms).i();
+        let oct = at::Chroma::oct(atoms).i().clamp(1, 5);
+
+        for frame in 0..ctx.nframes() {
+            if self.banks[0].is_hop_start() {
+                if oct != self.oct {
+                    self.update_bands(oct);
+                }
+
+                let win_len = ((denorm::Chroma::win(win, frame) * self.srate) / 1000.0).round();
+                let win_len = win_len as usize;
+                for bank in self.banks.iter_mut() {
+                    bank.set_window(win_len, win_len / 2);
+                }
+            }
+
+            let sample = inp.read(frame);
+            let mut completed = false;
+            for bank in self.banks.iter_mut() {
+                completed = bank.tick(sample);
+            }
+
+            if completed {
+                self.detect(denorm::Chroma::thrs(thrs, frame), qual);
+            }
+
+            let root = out::Chroma::root(outputs);
+            root.write(frame, self.root_pitch());
+            let conf = out::Chroma::conf(outputs);
+            conf.write(frame, self.conf);
+            let t = out::Chroma::t(outputs);
+            t.write(frame, self.trig_sig.next());
+        }
+
+        ctx_vals[0].set(self.conf);
+        ctx_vals[1].set(self.root.map(|r| (r as f32 + 1.0) / 12.0).unwrap_or(0.0));
+    }
+}
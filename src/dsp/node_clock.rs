@@ -0,0 +1,252 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::helpers::TrigSignal;
+use crate::dsp::{
+    at, denorm, inp, out, DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom,
+};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+#[macro_export]
+macro_rules! fa_clock_div {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let s = match ($v.round() as usize) {
+            0 => "4/1",
+            1 => "2/1",
+            2 => "1/1",
+            3 => "1/2.",
+            4 => "1/2",
+            5 => "1/2T",
+            6 => "1/4.",
+            7 => "1/4",
+            8 => "1/4T",
+            9 => "1/8.",
+            10 => "1/8",
+            11 => "1/8T",
+            12 => "1/16.",
+            13 => "1/16",
+            14 => "1/16T",
+            15 => "1/32",
+            _ => "?",
+        };
+        write!($formatter, "{}", s)
+    }};
+}
+
+/// The lengths of the `div` settings in quarter notes.
+const DIVISIONS: [f64; 16] = [
+    16.0,
+    8.0,
+    4.0,
+    3.0,
+    2.0,
+    4.0 / 3.0,
+    1.5,
+    1.0,
+    2.0 / 3.0,
+    0.75,
+    0.5,
+    1.0 / 3.0,
+    0.375,
+    0.25,
+    1.0 / 6.0,
+    0.125,
+];
+
+#[derive(Debug, Clone)]
+struct ClockState {
+    /// The expected song position of the next block, to detect jumps.
+    next_pos: Option<f64>,
+    last_beat: i64,
+    last_bar: i64,
+    beat_trig: TrigSignal,
+    bar_trig: TrigSignal,
+    rst_trig: TrigSignal,
+}
+
+/// A clock that is synchronized to the host transport
+#[derive(Debug, Clone)]
+pub struct Clock {
+    srate: f32,
+    state: Box<ClockState>,
+}
+
+impl Clock {
+    pub fn new(_nid: &NodeId) -> Self {
+        Self {
+            srate: 44100.0,
+            state: Box::new(ClockState {
+                next_pos: None,
+                last_beat: 0,
+                last_bar: 0,
+                beat_trig: TrigSignal::new(),
+                bar_trig: TrigSignal::new(),
+                rst_trig: TrigSignal::new(),
+            }),
+        }
+    }
+
+    pub const swing: &'static str =
+        "Clock swing\nDelays every second 'clk' pulse. At 1.0 it's delayed \
+        by half of the division.\nRange: (0..1)";
+    pub const pw: &'static str =
+        "Clock pw\nThe pulse width of the 'clk' gates, relative to the time \
+        until the next pulse.\nRange: (0..1)";
+    pub const div: &'static str =
+        "Clock div\nThe note value of the 'clk' pulses. 'T' are triplets and \
+        '.' dotted notes.\n";
+    pub const clk: &'static str =
+        "Clock clk\nThe clock gate signal, with a pulse for each division.\nRange: (0..1)";
+    pub const beat: &'static str =
+        "Clock beat\nA trigger at the start of each beat of the time signature.\nRange: (0..1)";
+    pub const bar: &'static str = "Clock bar\nA trigger at the start of each bar.\nRange: (0..1)";
+    pub const rst: &'static str =
+        "Clock rst\nA trigger when the transport starts playing or jumps to \
+        another song position.\nRange: (0..1)";
+    pub const phase: &'static str =
+        "Clock phase\nThe position in the current bar, rising from 0.0 to 1.0. \
+        Can be used as 'clock' input of 'TSeq' in the 'Phase' mode.\nRange: (0..1)";
+    pub const DESC: &'static str = r#"Transport Clock
+
+A clock that follows the tempo and song position of the host transport. It generates gates for note divisions with swing, and triggers for beats, bars and transport resets.
+"#;
+    pub const HELP: &'static str = r#"Clock - Transport Clock
+
+This node follows the tempo and song position, which the host (like a
+DAW or the JACK transport) passes to the DSP engine. So sequencers, LFOs
+and delays can be synchronized to the host.
+
+The 'clk' output generates a gate for each note value selected with 'div',
+from whole notes to 32th notes, including triplets and dotted notes.
+With 'swing' every second pulse is delayed, and 'pw' sets the length of
+the gates.
+
+'beat' and 'bar' send a trigger at the start of each beat and bar of
+the time signature. 'rst' sends a trigger when the transport is started or
+jumps to another position, use it to reset sequencers.
+
+'phase' rises from 0.0 to 1.0 during each bar, it can clock the 'TSeq'
+node in the 'Phase' mode.
+
+All outputs stay at 0.0 while the transport is stopped.
+"#;
+}
+
+impl DspNode for Clock {
+    fn outputs() -> usize {
+        5
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.state.beat_trig.set_sample_rate(srate);
+        self.state.bar_trig.set_sample_rate(srate);
+        self.state.rst_trig.set_sample_rate(srate);
+    }
+
+    fn reset(&mut self) {
+        self.state.next_pos = None;
+        self.state.beat_trig.reset();
+        self.state.bar_trig.reset();
+        self.state.rst_trig.reset();
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        let swing = inp::Clock::swing(inputs);
+        let pw = inp::Clock::pw(inputs);
+        let div = at::Clock::div(atoms);
+
+        let transport = &ectx.transport;
+        let st = &mut *self.state;
+        let nframes = ctx.nframes();
+
+        let div_len = DIVISIONS[(div.i().max(0) as usize).min(DIVISIONS.len() - 1)];
+        let beat_len = transport.beat_len();
+        let bar_len = transport.bar_len();
+        let beats_per_bar = transport.sig_num.max(1) as i64;
+        let bps = transport.beats_per_sample(self.srate);
+
+        if transport.playing {
+            let jumped =
+                st.next_pos.map(|pos| (pos - transport.beat).abs() > bps.max(1e-9)).unwrap_or(true);
+
+            if jumped {
+                st.rst_trig.trigger();
+
+                // Only trigger the beat and bar if the position is at their start:
+                let prev_pos = transport.beat - transport.bar_start - bps;
+                let bar = (prev_pos / bar_len).floor();
+                st.last_bar = transport.bar + bar as i64;
+                st.last_beat = st.last_bar * beats_per_bar
+                    + ((prev_pos - bar * bar_len) / beat_len).floor() as i64;
+            }
+
+            st.next_pos = Some(transport.beat + nframes as f64 * bps);
+        } else {
+            st.next_pos = None;
+        }
+
+        let mut last_clk = 0.0;
+        let mut last_phase = 0.0;
+
+        for frame in 0..nframes {
+            let (clk, phase) = if transport.playing {
+                let pos = transport.beat + frame as f64 * bps;
+
+                let rel_pos = pos - transport.bar_start;
+                let bar_offs = (rel_pos / bar_len).floor();
+                let bar = transport.bar + bar_offs as i64;
+                let bar_pos = rel_pos - bar_offs * bar_len;
+                let beat = bar * beats_per_bar + (bar_pos / beat_len).floor() as i64;
+
+                if beat != st.last_beat {
+                    st.beat_trig.trigger();
+                    st.last_beat = beat;
+                }
+                if bar != st.last_bar {
+                    st.bar_trig.trigger();
+                    st.last_bar = bar;
+                }
+
+                // The pulses come in pairs, the second one is delayed by the swing:
+                let swing = denorm::Clock::swing(swing, frame).clamp(0.0, 1.0) as f64;
+                let pw = denorm::Clock::pw(pw, frame).clamp(0.01, 0.99) as f64;
+                let second_start = div_len * (1.0 + 0.5 * swing);
+                let pair_pos = pos.rem_euclid(2.0 * div_len);
+
+                let gate = if pair_pos < second_start {
+                    pair_pos < pw * second_start
+                } else {
+                    pair_pos - second_start < pw * (2.0 * div_len - second_start)
+                };
+
+                (if gate { 1.0 } else { 0.0 }, (bar_pos / bar_len) as f32)
+            } else {
+                (0.0, 0.0)
+            };
+
+            out::Clock::clk(outputs).write(frame, clk);
+            out::Clock::beat(outputs).write(frame, st.beat_trig.next());
+            out::Clock::bar(outputs).write(frame, st.bar_trig.next());
+            out::Clock::rst(outputs).write(frame, st.rst_trig.next());
+            out::Clock::phase(outputs).write(frame, phase);
+
+            last_clk = clk;
+            last_phase = phase;
+        }
+
+        ctx_vals[0].set(last_clk);
+        ctx_vals[1].set(last_phase);
+    }
+}
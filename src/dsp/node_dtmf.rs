@@ -0,0 +1,227 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::goertzel::{GoertzelBank, GoertzelWindow};
+use crate::dsp::helpers::TrigSignal;
+use crate::dsp::{DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+/// The four row (low group) and four column (high group) frequencies.
+const DTMF_FREQS: [f32; 8] = [697.0, 770.0, 852.0, 941.0, 1209.0, 1336.0, 1477.0, 1633.0];
+
+/// The keys in the order of the `key` output, row by row.
+pub const DTMF_KEYS: [char; 16] =
+    ['1', '2', '3', 'A', '4', '5', '6', 'B', '7', '8', '9', 'C', '*', '0', '#', 'D'];
+
+/// Maximum level of the high group above the low group (in dB).
+const MAX_NORMAL_TWIST_DB: f32 = 8.0;
+/// Maximum level of the low group above the high group (in dB).
+const MAX_REVERSE_TWIST_DB: f32 = 4.0;
+/// The other tones of a group need to be at least this factor
+/// below the detected tone (about -6dB).
+const MAX_GROUP_NEIGHBOUR: f32 = 0.5;
+/// Number of successive hops that need to agree before a key
+/// press or release is accepted.
+const DEBOUNCE_HOPS: u8 = 2;
+
+/// Number of hops per analysis window.
+const HOPS_PER_WIN: usize = 3;
+
+/// A DTMF decoder
+#[derive(Debug, Clone)]
+pub struct Dtmf {
+    bank: Box<GoertzelBank>,
+    trig_sig: TrigSignal,
+    srate: f32,
+    candidate: Option<u8>,
+    cand_count: u8,
+    cur_key: Option<u8>,
+    last_key: Option<u8>,
+}
+
+impl Dtmf {
+    pub fn new(_nid: &NodeId) -> Self {
+        let mut bank = Box::new(GoertzelBank::new());
+        bank.set_window_func(GoertzelWindow::Hann);
+        bank.set_bands(&DTMF_FREQS[..]);
+
+        Self {
+            bank,
+            trig_sig: TrigSignal::new(),
+            srate: 44100.0,
+            candidate: None,
+            cand_count: 0,
+            cur_key: None,
+            last_key: None,
+        }
+    }
+    pub const inp: &'static str = "Dtmf inp\nSignal input\nRange: (-1..1)\n";
+    pub const thrs: &'static str = "Dtmf thrs\nMinimum amplitude of both tones of a key. Quieter \
+        tones are ignored.\nRange: (0..1)\n";
+    pub const win: &'static str =
+        "Dtmf win\nAnalysis window length. Shorter windows react faster, \
+        but separate the tones worse.\nRange: (0..1)\n";
+    pub const key: &'static str = "Dtmf key\nThe last decoded key as stepped value: 1/16 for the \
+        first key (1) up to 16/16 for the last one (D). The value is held \
+        after the key is released. It is 0.0 until the first key is decoded.\
+        \nRange: (0..1)\n";
+    pub const t: &'static str =
+        "Dtmf t\nEmits a trigger each time a key press is detected.\nRange: (0..1)\n";
+    pub const gat: &'static str =
+        "Dtmf gat\nThis gate is high as long as a key is pressed.\nRange: (0..1)\n";
+    pub const DESC: &'static str = r#"DTMF Decoder
+
+Decodes the DTMF (touch tone) keys of a telephone keypad from the input signal. The key is emitted as stepped value, with a trigger on each key press.
+"#;
+    pub const HELP: &'static str = r#"Dtmf - DTMF Decoder
+
+This node detects the dual tones (DTMF) that telephones use to signal
+the keys of their keypad. Each key is a mix of one low 'row' frequency
+and one high 'column' frequency:
+
+             1209 Hz  1336 Hz  1477 Hz  1633 Hz
+    697 Hz      1        2        3        A
+    770 Hz      4        5        6        B
+    852 Hz      7        8        9        C
+    941 Hz      *        0        #        D
+
+The levels of all eight frequencies are measured with Goertzel filters.
+A key is only accepted if both tones are louder than 'thrs', if the
+level difference between the two tones (the 'twist') is within the
+usual limits, and if both tones clearly stand out of their group.
+A key has to be detected over a few successive measurements before
+it is accepted, the same holds for releasing it.
+
+The last key is emitted on 'key' as stepped value, in the order of the
+table above, row by row: '1' is 1/16, '2' is 2/16, ... and 'D' is 16/16.
+Each key press emits a trigger on 't', and 'gat' is high while the key
+is pressed. Use for instance a 'Map' or 'Mux9' node to act on the keys.
+
+The 'win' parameter sets the analysis window. The default of 30ms is
+suited for standard telephony signals. Longer windows reject noise
+better, but miss short key presses.
+"#;
+
+    fn detect_key(&self, thrs: f32) -> Option<u8> {
+        let (row, row_lvl) = max_band(&self.bank, 0);
+        let (col, col_lvl) = max_band(&self.bank, 4);
+
+        if row_lvl < thrs || col_lvl < thrs {
+            return None;
+        }
+
+        let twist_db = 20.0 * (col_lvl / row_lvl).log10();
+        if !(-MAX_REVERSE_TWIST_DB..=MAX_NORMAL_TWIST_DB).contains(&twist_db) {
+            return None;
+        }
+
+        for i in 0..4 {
+            if i != row && self.bank.level(i) > row_lvl * MAX_GROUP_NEIGHBOUR {
+                return None;
+            }
+            if i != col && self.bank.level(i + 4) > col_lvl * MAX_GROUP_NEIGHBOUR {
+                return None;
+            }
+        }
+
+        Some((row * 4 + col) as u8)
+    }
+
+    fn update_key(&mut self, detected: Option<u8>) {
+        if detected == self.candidate {
+            self.cand_count = self.cand_count.saturating_add(1);
+        } else {
+            self.candidate = detected;
+            self.cand_count = 1;
+        }
+
+        if self.cand_count < DEBOUNCE_HOPS || self.candidate == self.cur_key {
+            return;
+        }
+
+        self.cur_key = self.candidate;
+        if self.cur_key.is_some() {
+            self.last_key = self.cur_key;
+            self.trig_sig.trigger();
+        }
+    }
+
+    fn key_value(&self) -> f32 {
+        self.last_key.map(|k| (k as f32 + 1.0) / (DTMF_KEYS.len() as f32)).unwrap_or(0.0)
+    }
+}
+
+/// Returns index and level of the loudest of the four bands starting at `offs`.
+fn max_band(bank: &GoertzelBank, offs: usize) -> (usize, f32) {
+    let mut max = (0, bank.level(offs));
+    for i in 1..4 {
+        let lvl = bank.level(offs + i);
+        if lvl > max.1 {
+            max = (i, lvl);
+        }
+    }
+    max
+}
+
+impl DspNode for Dtmf {
+    fn outputs() -> usize {
+        3
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.bank.set_sample_rate(srate);
+        self.trig_sig.set_sample_rate(srate);
+    }
+
+    fn reset(&mut self) {
+        self.bank.reset();
+        self.trig_sig.reset();
+        self.candidate = None;
+        self.cand_count = 0;
+        self.cur_key = None;
+        self.last_key = None;
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        _ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        _atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        use crate::dsp::{denorm, inp, out};
+
+        let inp = inp::Dtmf::inp(inputs);
+        let thrs = inp::Dtmf::thrs(inputs);
+        let win = inp::Dtmf::win(inputs);
+
+        for frame in 0..ctx.nframes() {
+            if self.bank.is_hop_start() {
+                let win_len = ((denorm::Dtmf::win(win, frame) * self.srate) / 1000.0).round();
+                let win_len = win_len as usize;
+                self.bank.set_window(win_len, win_len / HOPS_PER_WIN);
+            }
+
+            if self.bank.tick(inp.read(frame)) {
+                let detected = self.detect_key(denorm::Dtmf::thrs(thrs, frame));
+                self.update_key(detected);
+            }
+
+            let key = out::Dtmf::key(outputs);
+            key.write(frame, self.key_value());
+            let t = out::Dtmf::t(outputs);
+            t.write(frame, self.trig_sig.next());
+            let gat = out::Dtmf::gat(outputs);
+            gat.write(frame, if self.cur_key.is_some() { 1.0 } else { 0.0 });
+        }
+
+        ctx_vals[0].set(if self.cur_key.is_some() { 1.0 } else { 0.0 });
+        ctx_vals[1].set(self.key_value());
+    }
+}
@@ -8,12 +8,12 @@ use crate::nodes::{NodeAudioContext, NodeExecContext};
 /// A simple amplifier
 #[derive(Debug, Clone)]
 pub struct FbWr {
-    fb_index: u8,
+    fb_index: usize,
 }
 
 impl FbWr {
     pub fn new(nid: &NodeId) -> Self {
-        Self { fb_index: nid.instance() as u8 }
+        Self { fb_index: nid.instance() }
     }
     pub const inp: &'static str = "FbWr inp\nSignal input\nRange: (-1..1)\n";
 
@@ -36,10 +36,24 @@ the signal multiple times by connecting the 'FbRd 0' 'sig' port to multiple
 inputs.
 
 The delay is always 3.14ms, regardless of the sampling rate the synthesizer
-is running at.
+is running at. Only if the synthesizer processes longer blocks than that,
+the delay is one block long.
 "#;
 }
 
+impl FbWr {
+    /// Writes `nframes` of silence into the feedback delay, so that a muted
+    /// or bypassed [FbWr] does not leave a stale signal in it.
+    #[inline]
+    pub fn write_silence(&self, ectx: &mut NodeExecContext, nframes: usize) {
+        if let Some(fb) = ectx.feedback_delay_buffers.get_mut(self.fb_index) {
+            for _ in 0..nframes {
+                fb.write(0.0);
+            }
+        }
+    }
+}
+
 impl DspNode for FbWr {
     fn outputs() -> usize {
         0
@@ -63,8 +77,12 @@ impl DspNode for FbWr {
 
         let inp = inp::FbWr::inp(inputs);
 
-        for frame in 0..ctx.nframes() {
-            ectx.feedback_delay_buffers[self.fb_index as usize].write(inp.read(frame));
+        // The feedback buffers are grown by the NodeConfigurator
+        // before the node is created:
+        if let Some(fb) = ectx.feedback_delay_buffers.get_mut(self.fb_index) {
+            for frame in 0..ctx.nframes() {
+                fb.write(inp.read(frame));
+            }
         }
 
         ctx_vals[0].set(inp.read(ctx.nframes() - 1));
@@ -74,12 +92,12 @@ impl DspNode for FbWr {
 /// A simple amplifier
 #[derive(Debug, Clone)]
 pub struct FbRd {
-    fb_index: u8,
+    fb_index: usize,
 }
 
 impl FbRd {
     pub fn new(nid: &NodeId) -> Self {
-        Self { fb_index: nid.instance() as u8 }
+        Self { fb_index: nid.instance() }
     }
     pub const atv: &'static str = "FbRd atv\nAttenuate or invert input.\n\
          Use this to adjust the feedback amount.\nRange: (-1..1)\n";
@@ -104,13 +122,27 @@ the signal multiple times by connecting the 'FbRd 0' 'sig' port to multiple
 inputs.
 
 The delay is always 3.14ms, regardless of the sampling rate the synthesizer
-is running at.
+is running at. Only if the synthesizer processes longer blocks than that,
+the delay is one block long.
 
 The 'atv' parameter is a convenience parameter to allow attenuating or
 even inverting the signal.
 "#;
 }
 
+impl FbRd {
+    /// Skips `nframes` of the feedback delay, so that a muted or bypassed
+    /// [FbRd] stays in sync with it's [FbWr].
+    #[inline]
+    pub fn skip(&self, ectx: &mut NodeExecContext, nframes: usize) {
+        if let Some(fb) = ectx.feedback_delay_buffers.get_mut(self.fb_index) {
+            for _ in 0..nframes {
+                fb.read();
+            }
+        }
+    }
+}
+
 impl DspNode for FbRd {
     fn outputs() -> usize {
         1
@@ -136,10 +168,14 @@ impl DspNode for FbRd {
         let sig = out::FbRd::sig(outputs);
 
         let mut last_val = 0.0;
-        for frame in 0..ctx.nframes() {
-            last_val = ectx.feedback_delay_buffers[self.fb_index as usize].read();
-            last_val *= denorm::FbRd::atv(atv, frame);
-            sig.write(frame, last_val);
+        if let Some(fb) = ectx.feedback_delay_buffers.get_mut(self.fb_index) {
+            for frame in 0..ctx.nframes() {
+                last_val = fb.read();
+                last_val *= denorm::FbRd::atv(atv, frame);
+                sig.write(frame, last_val);
+            }
+        } else {
+            sig.fill(0.0);
         }
 
         ctx_vals[0].set(last_val);
@@ -0,0 +1,199 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::biquad::{Biquad, BiquadCoefs};
+use crate::dsp::{DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+#[macro_export]
+macro_rules! fa_follow_mode {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let s = match ($v.round() as usize) {
+            0 => "Peak",
+            1 => "RMS",
+            2 => "LUFS",
+            _ => "?",
+        };
+        write!($formatter, "{}", s)
+    }};
+}
+
+/// Averaging time of the mean square in the RMS and LUFS modes.
+const RMS_AVG_MS: f32 = 20.0;
+
+/// Returns the coefficient of a one pole smoothing filter that
+/// reaches about 63% of a step within `ms` milliseconds.
+#[inline]
+fn smooth_coef(ms: f32, srate: f32) -> f32 {
+    (-1000.0 / (ms.max(0.01) * srate)).exp()
+}
+
+/// An envelope follower
+#[derive(Debug, Clone)]
+pub struct Follow {
+    /// The K-weighting filters of the LUFS mode: A high shelf
+    /// and a highpass (see ITU-R BS.1770).
+    kweight: Box<[Biquad; 2]>,
+    srate: f32,
+    /// The averaged mean square in the RMS and LUFS modes.
+    mean_sq: f32,
+    /// The level with an instant attack, decaying with the release time.
+    peak: f32,
+    env: f32,
+    gate: bool,
+}
+
+impl Follow {
+    pub fn new(_nid: &NodeId) -> Self {
+        let mut this = Self {
+            kweight: Box::new([Biquad::new(); 2]),
+            srate: 44100.0,
+            mean_sq: 0.0,
+            peak: 0.0,
+            env: 0.0,
+            gate: false,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+    pub const inp: &'static str = "Follow inp\nSignal input\nRange: (-1..1)\n";
+    pub const atk: &'static str =
+        "Follow atk\nAttack time, how fast the envelope rises.\nRange: (0..1)\n";
+    pub const rel: &'static str =
+        "Follow rel\nRelease time, how fast the envelope falls.\nRange: (0..1)\n";
+    pub const thrs: &'static str =
+        "Follow thrs\nThe 'gat' output opens once the envelope rises above \
+        this threshold.\nRange: (0..1)\n";
+    pub const hyst: &'static str =
+        "Follow hyst\nHysteresis of the gate. The 'gat' output closes once the \
+        envelope falls below 'thrs' reduced by this fraction of it. \
+        Prevents the gate from chattering around the threshold.\nRange: (0..1)\n";
+    pub const mode: &'static str =
+        "Follow mode\nHow the level is measured: 'Peak' follows the absolute \
+        amplitude, 'RMS' the root mean square and 'LUFS' the root mean square \
+        of the input weighted like the loudness meters in broadcasting do.\n";
+    pub const env: &'static str = "Follow env\nThe envelope of the input.\nRange: (0..1)\n";
+    pub const gat: &'static str =
+        "Follow gat\nThis gate is high while the envelope is above the threshold.\
+        \nRange: (0..1)\n";
+    pub const DESC: &'static str = r#"Envelope Follower
+
+Measures the amplitude of the input signal and emits it as smooth envelope, with adjustable attack and release times. A gate output with threshold is provided too.
+"#;
+    pub const HELP: &'static str = r#"Follow - Envelope Follower
+
+This node follows the amplitude of the input signal and emits it as
+envelope on 'env'. It's useful for audio reactive modulation, like
+opening a filter with the level of a drum loop, or for sidechain
+ducking: Map 'env' of the drums inversely to the 'gain' of an 'Amp'
+that processes a pad sound.
+
+'atk' sets how fast the envelope follows rising levels, 'rel' how fast
+it follows falling levels. Short attack and long release times are
+typical for ducking.
+
+There are three measurement modes ('mode'):
+
+    Peak   Follows the absolute amplitude, reacts quickly to transients.
+    RMS    The root mean square, which is closer to how loud the
+           signal is perceived. For a sine wave it's about 0.7
+           times the peak amplitude.
+    LUFS   Like RMS, but the input is weighted with the K-curve of
+           loudness meters (ITU-R BS.1770) first. Low frequencies
+           count less and high frequencies a bit more.
+
+The 'gat' output opens once the envelope is above 'thrs' and closes once
+it falls below 'thrs' minus the 'hyst' fraction of 'thrs'. The gate
+can trigger envelopes or sequencers on the beats of an input.
+"#;
+}
+
+impl DspNode for Follow {
+    fn outputs() -> usize {
+        2
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        // The K-weighting filter coefficients from ITU-R BS.1770:
+        self.kweight[0].set_coefs(BiquadCoefs::high_shelf(srate, 0.7072, 1681.97, 4.0));
+        self.kweight[1].set_coefs(BiquadCoefs::highpass(srate, 0.5003, 38.14));
+    }
+
+    fn reset(&mut self) {
+        for bq in self.kweight.iter_mut() {
+            bq.reset();
+        }
+        self.mean_sq = 0.0;
+        self.peak = 0.0;
+        self.env = 0.0;
+        self.gate = false;
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        _ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        use crate::dsp::{at, denorm, inp, out};
+
+        let inp = inp::Follow::inp(inputs);
+        let atk = inp::Follow::atk(inputs);
+        let rel = inp::Follow::rel(inputs);
+        let thrs = inp::Follow::thrs(inputs);
+        let hyst = inp::Follow::hyst(inputs);
+        let mode = at::Follow::mode(atoms).i();
+
+        let rms_coef = smooth_coef(RMS_AVG_MS, self.srate);
+
+        for frame in 0..ctx.nframes() {
+            let mut s = inp.read(frame);
+            if mode == 2 {
+                for bq in self.kweight.iter_mut() {
+                    s = bq.tick(s);
+                }
+            }
+
+            let x = if mode == 0 {
+                s.abs()
+            } else {
+                self.mean_sq = s * s + rms_coef * (self.mean_sq - s * s);
+                self.mean_sq.sqrt()
+            };
+
+            let rel_coef = smooth_coef(denorm::Follow::rel(rel, frame), self.srate);
+            self.peak = x.max(self.peak * rel_coef);
+
+            // The attack smoothes the rise, the fall already follows the release:
+            self.env = if self.peak > self.env {
+                let atk_coef = smooth_coef(denorm::Follow::atk(atk, frame), self.srate);
+                self.peak + atk_coef * (self.env - self.peak)
+            } else {
+                self.peak
+            };
+
+            let thrs = denorm::Follow::thrs(thrs, frame);
+            if self.gate {
+                let hyst = denorm::Follow::hyst(hyst, frame).clamp(0.0, 1.0);
+                self.gate = self.env >= thrs * (1.0 - hyst);
+            } else {
+                self.gate = self.env > thrs;
+            }
+
+            let env = out::Follow::env(outputs);
+            env.write(frame, self.env);
+            let gat = out::Follow::gat(outputs);
+            gat.write(frame, if self.gate { 1.0 } else { 0.0 });
+        }
+
+        ctx_vals[0].set(self.env);
+        ctx_vals[1].set(if self.gate { 1.0 } else { 0.0 });
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright (c) 2022 theloni-monk <theo.acooper@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::goertzel::{amp_to_db, GoertzelBank, GoertzelWindow, MAX_BANK_BANDS};
+use crate::dsp::{DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+const PI: f32 = std::f32::consts::PI;
+
+#[macro_export]
+macro_rules! fa_gzbank_bands {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        write!($formatter, "{}", ($v.round() as usize).clamp(1, 32))
+    }};
+}
+
+#[macro_export]
+macro_rules! fa_gzbank_scale {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let s = match ($v.round() as usize) {
+            0 => "Lin",
+            1 => "Oct",
+            2 => "Semi",
+            3 => "List",
+            _ => "?",
+        };
+        write!($formatter, "{}", s)
+    }};
+}
+
+#[macro_export]
+macro_rules! fa_gzbank_wfun {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let s = match ($v.round() as usize) {
+            0 => "Rect",
+            1 => "Hann",
+            2 => "Hamming",
+            3 => "BlackmanHarris",
+            _ => "?",
+        };
+        write!($formatter, "{}", s)
+    }};
+}
+
+#[macro_export]
+macro_rules! fa_gzbank_unit {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let s = match ($v.round() as usize) {
+            0 => "Lin",
+            1 => "dBFS",
+            _ => "?",
+        };
+        write!($formatter, "{}", s)
+    }};
+}
+
+#[macro_export]
+macro_rules! fa_gzbank_freqs {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        write!($formatter, "?")
+    }};
+}
+
+/// Calculates the center frequency of band `idx` for the given
+/// spacing scale (see `GzBank scale`).
+fn band_freq(scale: i64, idx: usize, freq: f32, spc: f32) -> f32 {
+    let i = idx as f32;
+    match scale {
+        1 => freq * (2.0_f32).powf(i * spc),
+        2 => freq * (2.0_f32).powf((i * spc) / 12.0),
+        _ => freq * (1.0 + i * spc),
+    }
+}
+
+/// A Goertzel filter bank with a variable number of bands
+#[derive(Debug, Clone)]
+pub struct GzBank {
+    bank: Box<GoertzelBank>,
+    srate: f32,
+}
+
+impl GzBank {
+    pub fn new(_nid: &NodeId) -> Self {
+        Self { bank: Box::new(GoertzelBank::new()), srate: 44100.0 }
+    }
+    pub const inp: &'static str = "GzBank inp\nSignal input\nRange: (-1..1)\n";
+    pub const freq: &'static str =
+        "GzBank freq\nCenter frequency of the first band. The other bands \
+        are placed above it according to 'scale' and 'spc'.\nRange: (-1..1)\n";
+    pub const spc: &'static str =
+        "GzBank spc\nBand spacing. For 'Lin' this is a multiple of 'freq', \
+        for 'Oct' in octaves and for 'Semi' in semitones.\nRange: (0..1)\n";
+    pub const win: &'static str =
+        "GzBank win\nAnalysis window length. Longer windows are more frequency \
+        selective, but react slower.\nRange: (0..1)\n";
+    pub const hop: &'static str =
+        "GzBank hop\nTime between the starts of two analysis windows. If it is \
+        shorter than 'win', the windows overlap and the outputs are updated \
+        more often. It can not be shorter than 1/8 of 'win'.\nRange: (0..1)\n";
+    pub const gain: &'static str = "GzBank gain\nOutput gain for all bands.\nRange: (0..1)\n";
+    pub const bands: &'static str = "GzBank bands\nNumber of analyzed bands.\n";
+    pub const scale: &'static str = "GzBank scale\nHow the band frequencies are spaced:\n\
+             - Lin: freq * (1 + n * spc)\n\
+             - Oct: freq * 2^(n * spc)\n\
+             - Semi: freq * 2^(n * spc / 12)\n\
+             - List: Frequencies (Hz) from 'freqs'\n";
+    pub const freqs: &'static str =
+        "GzBank freqs\nList of band center frequencies in Hz, used by the 'List' scale.\n";
+    pub const wfun: &'static str =
+        "GzBank wfun\nWindow function that is applied to each analysis window. \
+        'Rect' is no window at all. 'Hann', 'Hamming' and 'BlackmanHarris' \
+        reduce the leakage of neighbouring frequencies into a band.\n";
+    pub const unit: &'static str =
+        "GzBank unit\nUnit of the band outputs 'b1' to 'b32'. 'Lin' is the \
+        linear amplitude, 'dBFS' the level in decibels relative to full scale, \
+        where a sine with amplitude 1.0 reads 0.0 and silence reads -144.0.\n";
+    pub const b1: &'static str = "GzBank b1\nAmplitude of band 1.\nRange: (0..1)\n";
+    pub const b2: &'static str = "GzBank b2\nAmplitude of band 2.\nRange: (0..1)\n";
+    pub const b3: &'static str = "GzBank b3\nAmplitude of band 3.\nRange: (0..1)\n";
+    pub const b4: &'static str = "GzBank b4\nAmplitude of band 4.\nRange: (0..1)\n";
+    pub const b5: &'static str = "GzBank b5\nAmplitude of band 5.\nRange: (0..1)\n";
+    pub const b6: &'static str = "GzBank b6\nAmplitude of band 6.\nRange: (0..1)\n";
+    pub const b7: &'static str = "GzBank b7\nAmplitude of band 7.\nRange: (0..1)\n";
+    pub const b8: &'static str = "GzBank b8\nAmplitude of band 8.\nRange: (0..1)\n";
+    pub const b9: &'static str = "GzBank b9\nAmplitude of band 9.\nRange: (0..1)\n";
+    pub const b10: &'static str = "GzBank b10\nAmplitude of band 10.\nRange: (0..1)\n";
+    pub const b11: &'static str = "GzBank b11\nAmplitude of band 11.\nRange: (0..1)\n";
+    pub const b12: &'static str = "GzBank b12\nAmplitude of band 12.\nRange: (0..1)\n";
+    pub const b13: &'static str = "GzBank b13\nAmplitude of band 13.\nRange: (0..1)\n";
+    pub const b14: &'static str = "GzBank b14\nAmplitude of band 14.\nRange: (0..1)\n";
+    pub const b15: &'static str = "GzBank b15\nAmplitude of band 15.\nRange: (0..1)\n";
+    pub const b16: &'static str = "GzBank b16\nAmplitude of band 16.\nRange: (0..1)\n";
+    pub const b17: &'static str = "GzBank b17\nAmplitude of band 17.\nRange: (0..1)\n";
+    pub const b18: &'static str = "GzBank b18\nAmplitude of band 18.\nRange: (0..1)\n";
+    pub const b19: &'static str = "GzBank b19\nAmplitude of band 19.\nRange: (0..1)\n";
+    pub const b20: &'static str = "GzBank b20\nAmplitude of band 20.\nRange: (0..1)\n";
+    pub const b21: &'static str = "GzBank b21\nAmplitude of band 21.\nRange: (0..1)\n";
+    pub const b22: &'static str = "GzBank b22\nAmplitude of band 22.\nRange: (0..1)\n";
+    pub const b23: &'static str = "GzBank b23\nAmplitude of band 23.\nRange: (0..1)\n";
+    pub const b24: &'static str = "GzBank b24\nAmplitude of band 24.\nRange: (0..1)\n";
+    pub const b25: &'static str = "GzBank b25\nAmplitude of band 25.\nRange: (0..1)\n";
+    pub const b26: &'static str = "GzBank b26\nAmplitude of band 26.\nRange: (0..1)\n";
+    pub const b27: &'static str = "GzBank b27\nAmplitude of band 27.\nRange: (0..1)\n";
+    pub const b28: &'static str = "GzBank b28\nAmplitude of band 28.\nRange: (0..1)\n";
+    pub const b29: &'static str = "GzBank b29\nAmplitude of band 29.\nRange: (0..1)\n";
+    pub const b30: &'static str = "GzBank b30\nAmplitude of band 30.\nRange: (0..1)\n";
+    pub const b31: &'static str = "GzBank b31\nAmplitude of band 31.\nRange: (0..1)\n";
+    pub const b32: &'static str = "GzBank b32\nAmplitude of band 32.\nRange: (0..1)\n";
+    pub const pband: &'static str =
+        "GzBank pband\nThe band that is emitted on the 're', 'im' and 'ph' outputs.\n";
+    pub const re: &'static str =
+        "GzBank re\nReal part of the band selected with 'pband'.\nRange: (-1..1)\n";
+    pub const im: &'static str =
+        "GzBank im\nImaginary part of the band selected with 'pband'.\nRange: (-1..1)\n";
+    pub const ph: &'static str = "GzBank ph\nPhase of the band selected with 'pband', \
+        relative to the start of the analysis window. -1 is -PI and 1 is PI.\n\
+        Range: (-1..1)\n";
+    pub const DESC: &'static str = r#"Goertzel Filter Bank
+
+Measures the amplitude of up to 32 frequency bands of the input signal. Each band has it's own output.
+"#;
+    pub const HELP: &'static str = r#"GzBank - Goertzel Filter Bank
+
+This node runs a bank of Goertzel filters on the input signal. Each
+of them measures the amplitude of the signal at one center frequency,
+like a very narrow bandpass followed by an envelope follower. The
+amplitude of each band is emitted on it's own output, 'b1' to 'b32'.
+A sine wave with amplitude 1.0 at a band frequency results in roughly
+1.0 on the corresponding output.
+
+The number of bands is set with 'bands'. The frequency of the first
+band is 'freq', the other bands are placed above it, depending on
+the 'scale' setting:
+
+    Lin     freq * (1 + n * spc)     spc = 1.0 follows the harmonics
+    Oct     freq * 2^(n * spc)       spc = 1.0 is one band per octave
+    Semi    freq * 2^(n * spc / 12)  spc = 1.0 is one band per semitone
+    List    the frequencies (in Hz) listed in the 'freqs' atom
+
+The amplitudes are measured over windows of 'win' length. Longer
+windows separate close frequencies better, but react slower to changes.
+A window should at least hold a few periods of the lowest analyzed
+frequency. A new window is started every 'hop', and the outputs are
+updated each time a window is completed. Set 'hop' shorter than 'win'
+to let the windows overlap and get more frequent updates. The settings
+are applied at the start of each hop.
+
+With 'unit' set to 'dBFS' the band outputs emit the level in dBFS
+instead of the linear amplitude, after 'gain' has been applied. A sine
+with amplitude 1.0 reads 0.0, amplitude 0.5 reads about -6.0. The level
+is limited to -144.0, which is also what silence reads.
+
+Each band measures exactly at it's frequency, independent of the window
+length. Without a window function ('wfun' = 'Rect') strong signals leak
+into neighbouring bands. Select 'Hann' or 'Hamming' to reduce that
+leakage, or 'BlackmanHarris' to suppress it the most. Window functions
+make each band a bit wider, so they need longer windows to separate close
+frequencies.
+
+The band selected with 'pband' is additionally emitted as complex value
+on 're' (real part) and 'im' (imaginary part), and as phase on 'ph'.
+The phase is measured relative to the start of each analysis window, with
+-1 to 1 mapping to -PI to PI. A cosine that starts at the window start has
+a phase of 0. Comparing the phases of two GzBank nodes with the same
+settings tells the phase difference between two signals, and the phase
+change between two windows can be used to refine the frequency estimate.
+
+Use the outputs to watch the partials of an oscillator, to drive
+envelopes or filters from the spectrum of a live signal or to detect
+tones.
+"#;
+}
+
+impl DspNode for GzBank {
+    fn outputs() -> usize {
+        MAX_BANK_BANDS + 3
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.bank.set_sample_rate(srate);
+    }
+
+    fn reset(&mut self) {
+        self.bank.reset();
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        _ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        use crate::dsp::{at, denorm, inp, out};
+
+        let inp = inp::GzBank::inp(inputs);
+        let freq = inp::GzBank::freq(inputs);
+        let spc = inp::GzBank::spc(inputs);
+        let win = inp::GzBank::win(inputs);
+        let hop = inp::GzBank::hop(inputs);
+        let gain = inp::GzBank::gain(inputs);
+        let bands = at::GzBank::bands(atoms);
+        let scale = at::GzBank::scale(atoms);
+        let freqs = at::GzBank::freqs(atoms);
+        let wfun = at::GzBank::wfun(atoms);
+        let pband = at::GzBank::pband(atoms);
+        let unit = at::GzBank::unit(atoms);
+
+        let scale = scale.i();
+        let pband = (pband.i().clamp(1, MAX_BANK_BANDS as i64) - 1) as usize;
+        let db_unit = unit.i() == 1;
+        let list = freqs.v_ref().unwrap_or(&[]);
+        let nyquist = self.srate * 0.5;
+        let mut band_freqs = [0.0; MAX_BANK_BANDS];
+        let mut band_cnt = self.bank.band_count();
+
+        for frame in 0..ctx.nframes() {
+            // The settings are only applied at the start of a hop,
+            // so that the analysis does not depend on the block size:
+            if self.bank.is_hop_start() {
+                band_cnt = if scale == 3 {
+                    for (bf, f) in band_freqs.iter_mut().zip(list.iter()) {
+                        *bf = f.clamp(0.0, nyquist);
+                    }
+                    list.len().min(MAX_BANK_BANDS)
+                } else {
+                    let cnt = (bands.i().clamp(1, MAX_BANK_BANDS as i64)) as usize;
+                    let cfreq = denorm::GzBank::freq(freq, frame);
+                    let cspc = denorm::GzBank::spc(spc, frame);
+                    for (i, bf) in band_freqs.iter_mut().enumerate().take(cnt) {
+                        *bf = band_freq(scale, i, cfreq, cspc).clamp(0.0, nyquist);
+                    }
+                    cnt
+                };
+
+                let win_ms = denorm::GzBank::win(win, frame);
+                let hop_ms = denorm::GzBank::hop(hop, frame);
+                self.bank.set_window(
+                    ((win_ms * self.srate) / 1000.0).round() as usize,
+                    ((hop_ms * self.srate) / 1000.0).round() as usize,
+                );
+                self.bank.set_bands(&band_freqs[0..band_cnt]);
+                self.bank.set_window_func(GoertzelWindow::from_setting(wfun.i()));
+            }
+
+            self.bank.tick(inp.read(frame));
+
+            let gain = denorm::GzBank::gain(gain, frame);
+            for (i, out) in outputs.iter_mut().enumerate().take(MAX_BANK_BANDS) {
+                let level = if i < band_cnt { self.bank.level(i) * gain } else { 0.0 };
+                out.write(frame, if db_unit { amp_to_db(level) } else { level });
+            }
+
+            let (re, im, ph) = if pband < band_cnt {
+                let (re, im) = self.bank.complex(pband);
+                (re * gain, im * gain, self.bank.phase(pband) / PI)
+            } else {
+                (0.0, 0.0, 0.0)
+            };
+            let out_re = out::GzBank::re(outputs);
+            out_re.write(frame, re);
+            let out_im = out::GzBank::im(outputs);
+            out_im.write(frame, im);
+            let out_ph = out::GzBank::ph(outputs);
+            out_ph.write(frame, ph);
+        }
+
+        let mut max_level = 0.0;
+        let mut max_idx = 0;
+        for i in 0..band_cnt {
+            if self.bank.level(i) > max_level {
+                max_level = self.bank.level(i);
+                max_idx = i;
+            }
+        }
+
+        ctx_vals[0].set(max_level);
+        ctx_vals[1].set(if band_cnt > 0 {
+            (max_idx as f32 + 1.0) / (band_cnt as f32)
+        } else {
+            0.0
+        });
+    }
+}
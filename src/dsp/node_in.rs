@@ -0,0 +1,89 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::{
+    at, denorm, inp, out, DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom,
+};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+#[macro_export]
+macro_rules! fa_in_chpair {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let pair = $v.round() as usize;
+        write!($formatter, "{}+{}", pair * 2 + 1, pair * 2 + 2)
+    }};
+}
+
+/// The (stereo) input port of the plugin
+#[derive(Debug, Clone)]
+pub struct In {}
+
+impl In {
+    pub fn new(_nid: &NodeId) -> Self {
+        Self {}
+    }
+
+    pub const gain: &'static str =
+        "In gain\nThe gain of the audio input, applied to both channels. \
+        Please note that this is a linear control, to prevent inaccuracies for 1.0. \
+        \nRange: (0..1)";
+    pub const chp: &'static str =
+        "In chp\nThe pair of audio input channels to read from the audio device \
+        or host. Channels that are not provided are silent.\n";
+    pub const sig1: &'static str =
+        "In sig1\nThe first audio channel of the selected pair (left)\nRange: (-1..1)";
+    pub const sig2: &'static str =
+        "In sig2\nThe second audio channel of the selected pair (right)\nRange: (-1..1)";
+
+    pub const DESC: &'static str = "Audio Input Port\n\n\
+        This input port node allows you to receive audio signals \
+        from audio devices or tracks in your DAW.";
+    pub const HELP: &'static str = r#"Audio Input Port
+
+This input port node allows you to receive audio signals from audio
+devices or tracks in your DAW, for instance to use HexoDSP as effect
+processor. The input channels are grouped in pairs, which are selected
+with the 'chp' setting. If the audio device or host provides less
+channels, the missing channels are silent.
+"#;
+}
+
+impl DspNode for In {
+    fn outputs() -> usize {
+        2
+    }
+
+    fn set_sample_rate(&mut self, _srate: f32) {}
+    fn reset(&mut self) {}
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        _ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        let gain = inp::In::gain(inputs);
+        let ch = (at::In::chp(atoms).i() as usize) * 2;
+        let ch_count = ctx.input_channels();
+
+        for frame in 0..ctx.nframes() {
+            let gain = denorm::In::gain(gain, frame);
+            let s1 = if ch < ch_count { ctx.input(ch, frame) } else { 0.0 };
+            let s2 = if ch + 1 < ch_count { ctx.input(ch + 1, frame) } else { 0.0 };
+
+            let sig1 = out::In::sig1(outputs);
+            sig1.write(frame, gain * s1);
+            let sig2 = out::In::sig2(outputs);
+            sig2.write(frame, gain * s2);
+        }
+
+        let last_frame = ctx.nframes() - 1;
+        ctx_vals[0].set(out::In::sig1(outputs).read(last_frame));
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::helpers::SlewValue;
+use crate::dsp::{
+    at, denorm, inp, out, DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom,
+};
+use crate::nodes::{MidiMsg, NodeAudioContext, NodeExecContext};
+
+#[macro_export]
+macro_rules! fa_midip_chan {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let chan = $v.round() as usize;
+        if chan == 0 {
+            write!($formatter, "Omni")
+        } else {
+            write!($formatter, "{}", chan)
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! fa_midip_pbr {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        write!($formatter, "{} st", $v.round() as usize)
+    }};
+}
+
+/// The number of held notes the MidiP node remembers.
+const MAX_HELD_NOTES: usize = 16;
+
+/// MIDI CC number of the modulation wheel.
+const CC_MOD_WHEEL: u8 = 1;
+/// MIDI CC number of the "All Sound Off" message.
+const CC_ALL_SOUND_OFF: u8 = 120;
+/// MIDI CC number of the "All Notes Off" message.
+const CC_ALL_NOTES_OFF: u8 = 123;
+
+/// A monophonic MIDI to CV converter
+#[derive(Debug, Clone)]
+pub struct MidiP {
+    /// The held notes, the most recent one is the last.
+    held: Box<[u8; MAX_HELD_NOTES]>,
+    held_len: u8,
+    note: u8,
+    gate: bool,
+    vel: f32,
+    at: f32,
+    pb: f32,
+    mw: f32,
+    slew: SlewValue<f32>,
+}
+
+impl MidiP {
+    pub fn new(_nid: &NodeId) -> Self {
+        Self {
+            held: Box::new([0; MAX_HELD_NOTES]),
+            held_len: 0,
+            note: 69,
+            gate: false,
+            vel: 0.0,
+            at: 0.0,
+            pb: 0.0,
+            mw: 0.0,
+            slew: SlewValue::new(),
+        }
+    }
+
+    pub const glide: &'static str =
+        "MidiP glide\nThe time it takes the pitch to glide one octave to a new note. \
+        At 0.0 the pitch changes immediately.\nRange: (0..1)";
+    pub const chan: &'static str =
+        "MidiP chan\nThe MIDI channel to receive from, or 'Omni' for all channels.\n";
+    pub const pbr: &'static str =
+        "MidiP pbr\nThe pitch bend range in semitones, that is applied to 'pit'.\n";
+    pub const pit: &'static str = "MidiP pit\nThe pitch of the most recently played note, \
+        including pitch bend. Connect this directly to the 'freq' input of an \
+        oscillator.\nRange: (-1..1)";
+    pub const gate: &'static str = "MidiP gate\nIs 1.0 as long as a note is held. A new note \
+        while another note is held lets the gate go low for one sample to retrigger \
+        envelopes.\nRange: (0..1)";
+    pub const vel: &'static str = "MidiP vel\nThe velocity of the current note.\nRange: (0..1)";
+    pub const at: &'static str =
+        "MidiP at\nThe aftertouch (channel pressure or the pressure of the \
+        current note).\nRange: (0..1)";
+    pub const pb: &'static str = "MidiP pb\nThe pitch bend wheel.\nRange: (-1..1)";
+    pub const mw: &'static str = "MidiP mw\nThe modulation wheel (CC 1).\nRange: (0..1)";
+    pub const DESC: &'static str = r#"MIDI to CV
+
+Converts the incoming MIDI events into pitch, gate, velocity, aftertouch, pitch bend and mod wheel signals. It plays one note at a time.
+"#;
+    pub const HELP: &'static str = r#"MidiP - MIDI to CV
+
+This node converts the MIDI events that the host feeds into the
+DSP engine into signals. It's monophonic: If multiple notes are held,
+the most recent one is played. When it's released, the node returns to
+the previously held note.
+
+The 'pit' output can be connected directly to the 'freq' input of an
+oscillator. It includes the pitch bend, with the range set by 'pbr'.
+With 'glide' the pitch slides to new notes.
+
+The 'gate' output is high while a note is held and retriggers for new
+notes, use it to drive an envelope. 'vel', 'at' (aftertouch),
+'pb' (pitch bend) and 'mw' (mod wheel) can be used for modulation.
+
+With 'chan' the node only listens to a single MIDI channel.
+"#;
+
+    /// Handles a MIDI message. Returns true if the gate
+    /// needs to be retriggered.
+    fn handle(&mut self, msg: &MidiMsg) -> bool {
+        match *msg {
+            MidiMsg::NoteOn { note, vel, .. } => {
+                let retrig = self.gate;
+
+                self.release(note);
+                if self.held_len as usize == MAX_HELD_NOTES {
+                    self.held.copy_within(1.., 0);
+                    self.held_len -= 1;
+                }
+                self.held[self.held_len as usize] = note;
+                self.held_len += 1;
+
+                self.note = note;
+                self.vel = vel as f32 / 127.0;
+                self.gate = true;
+
+                return retrig;
+            }
+            MidiMsg::NoteOff { note, .. } => {
+                self.release(note);
+
+                if self.held_len > 0 {
+                    self.note = self.held[self.held_len as usize - 1];
+                } else {
+                    self.gate = false;
+                }
+            }
+            MidiMsg::PolyPressure { note, value, .. } => {
+                if note == self.note {
+                    self.at = value as f32 / 127.0;
+                }
+            }
+            MidiMsg::ChannelPressure { value, .. } => {
+                self.at = value as f32 / 127.0;
+            }
+            MidiMsg::PitchBend { value, .. } => {
+                self.pb = ((value as f32 - 8192.0) / 8191.0).clamp(-1.0, 1.0);
+            }
+            MidiMsg::ControlChange { cc, value, .. } => match cc {
+                CC_MOD_WHEEL => {
+                    self.mw = value as f32 / 127.0;
+                }
+                CC_ALL_SOUND_OFF | CC_ALL_NOTES_OFF => {
+                    self.held_len = 0;
+                    self.gate = false;
+                }
+                _ => (),
+            },
+        }
+
+        false
+    }
+
+    /// Removes the `note` from the held notes.
+    fn release(&mut self, note: u8) {
+        let len = self.held_len as usize;
+        if let Some(idx) = self.held[0..len].iter().position(|n| *n == note) {
+            self.held.copy_within((idx + 1)..len, idx);
+            self.held_len -= 1;
+        }
+    }
+}
+
+impl DspNode for MidiP {
+    fn outputs() -> usize {
+        6
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        self.slew.set_sample_rate(srate);
+    }
+
+    fn reset(&mut self) {
+        self.held_len = 0;
+        self.note = 69;
+        self.gate = false;
+        self.vel = 0.0;
+        self.at = 0.0;
+        self.pb = 0.0;
+        self.mw = 0.0;
+        self.slew.reset();
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        let glide = inp::MidiP::glide(inputs);
+        let chan = at::MidiP::chan(atoms).i();
+        let pbr = at::MidiP::pbr(atoms).i() as f32;
+
+        let events = ectx.midi.events();
+        let mut ev_idx = 0;
+
+        for frame in 0..ctx.nframes() {
+            let mut retrig = false;
+            while let Some(event) = events.get(ev_idx) {
+                if event.frame > frame {
+                    break;
+                }
+
+                if chan == 0 || event.msg.channel() as i64 == chan - 1 {
+                    retrig |= self.handle(&event.msg);
+                }
+                ev_idx += 1;
+            }
+
+            // The glide time is given per octave, which is 0.1:
+            let glide = denorm::MidiP::glide(glide, frame) * 10.0;
+            let pit = self.slew.next((self.note as f32 - 69.0) / 120.0, glide);
+
+            let pit_out = out::MidiP::pit(outputs);
+            pit_out.write(frame, pit + (self.pb * pbr) / 120.0);
+            let gate_out = out::MidiP::gate(outputs);
+            gate_out.write(frame, if self.gate && !retrig { 1.0 } else { 0.0 });
+            let vel_out = out::MidiP::vel(outputs);
+            vel_out.write(frame, self.vel);
+            let at_out = out::MidiP::at(outputs);
+            at_out.write(frame, self.at);
+            let pb_out = out::MidiP::pb(outputs);
+            pb_out.write(frame, self.pb);
+            let mw_out = out::MidiP::mw(outputs);
+            mw_out.write(frame, self.mw);
+        }
+
+        ctx_vals[0].set(if self.gate { 1.0 } else { 0.0 });
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::goertzel::{GoertzelBank, GoertzelWindow};
+use crate::dsp::helpers::TrigSignal;
+use crate::dsp::{DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+#[macro_export]
+macro_rules! fa_onset_mode {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        let s = match ($v.round() as usize) {
+            0 => "Flux",
+            1 => "HFC",
+            _ => "?",
+        };
+        write!($formatter, "{}", s)
+    }};
+}
+
+/// Number of analyzed frequency bands.
+const ONSET_BANDS: usize = 24;
+/// Center frequency of the lowest band.
+const ONSET_MIN_FREQ: f32 = 80.0;
+/// Center frequency of the highest band.
+const ONSET_MAX_FREQ: f32 = 12000.0;
+/// Length of the analysis window in milliseconds (512 samples at 44.1kHz).
+const ONSET_WIN_MS: f32 = 11.6;
+/// Number of hops per analysis window.
+const ONSET_HOPS_PER_WIN: usize = 4;
+/// Compression factor of the band levels: `ln(1 + ONSET_COMPRESS * level)`.
+const ONSET_COMPRESS: f32 = 100.0;
+/// The running average of the detection function is multiplied by this
+/// to make up the adaptive part of the threshold.
+const ONSET_ADAPT_MULT: f32 = 1.5;
+
+#[inline]
+fn compress(v: f32) -> f32 {
+    (1.0 + ONSET_COMPRESS * v).ln() / (1.0 + ONSET_COMPRESS).ln()
+}
+
+/// The analysis state of the [Onset] node, kept in a `Box`.
+#[derive(Debug, Clone)]
+struct OnsetAnalysis {
+    bank: GoertzelBank,
+    /// The compressed band levels (spectral flux) or the compressed
+    /// high frequency content (HFC) of the previous window.
+    prev: [f32; ONSET_BANDS],
+}
+
+/// An onset detector
+#[derive(Debug, Clone)]
+pub struct Onset {
+    ana: Box<OnsetAnalysis>,
+    trig_sig: TrigSignal,
+    srate: f32,
+    odf: f32,
+    avg: f32,
+    hold: u32,
+    hfc: bool,
+    /// Set if the next detection has to be skipped, because the
+    /// previous levels are not comparable.
+    skip: bool,
+}
+
+impl Onset {
+    pub fn new(_nid: &NodeId) -> Self {
+        let mut ana =
+            Box::new(OnsetAnalysis { bank: GoertzelBank::new(), prev: [0.0; ONSET_BANDS] });
+        ana.bank.set_window_func(GoertzelWindow::Hann);
+
+        let mut this = Self {
+            ana,
+            trig_sig: TrigSignal::new(),
+            srate: 0.0,
+            odf: 0.0,
+            avg: 0.0,
+            hold: 0,
+            hfc: false,
+            skip: false,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+    pub const inp: &'static str = "Onset inp\nSignal input\nRange: (-1..1)\n";
+    pub const thrs: &'static str =
+        "Onset thrs\nThe fixed part of the threshold. The detection function \
+        'odf' needs to rise above this plus the adaptive part to detect an \
+        onset. Raise this to ignore quiet or soft onsets.\nRange: (0..1)\n";
+    pub const avg: &'static str =
+        "Onset avg\nAveraging time of the adaptive part of the threshold. \
+        The threshold rises with the recent activity of the input, so \
+        that dense passages don't cause a flood of triggers.\nRange: (0..1)\n";
+    pub const hold: &'static str =
+        "Onset hold\nMinimum time between two onsets. Further onsets within \
+        this time are ignored.\nRange: (0..1)\n";
+    pub const mode: &'static str =
+        "Onset mode\nThe detection function: 'Flux' measures the rise of the \
+        level in all frequency bands, which works well for most material. \
+        'HFC' measures the rise of the high frequency content, which \
+        emphasizes percussive sounds.\n";
+    pub const t: &'static str =
+        "Onset t\nEmits a trigger each time an onset is detected.\nRange: (0..1)\n";
+    pub const odf: &'static str =
+        "Onset odf\nThe onset detection function, higher values mean a stronger \
+        onset. Useful to find a good 'thrs' setting.\nRange: (0..1)\n";
+    pub const DESC: &'static str = r#"Onset Detector
+
+Detects the onsets of notes and drum hits in the input signal and emits a trigger for each of them. Use it to clock sequencers or retrigger envelopes from a drum loop or a live input.
+"#;
+    pub const HELP: &'static str = r#"Onset - Onset / Transient Detector
+
+This node splits the input into 24 frequency bands with Goertzel
+filters and measures their levels about every 3ms. A sudden rise of
+the levels marks the onset of a note or a drum hit, and a trigger is
+emitted on 't'. Connect it to the 'trig' inputs of for instance
+'TSeq', 'Ad' or 'RndWk' to make them follow the input.
+
+The strength of the rise is emitted on 'odf', the onset detection
+function. How it's calculated is selected with 'mode':
+
+    Flux   Spectral flux, the sum of the level rises of all bands.
+           Works for most material, including pitched notes.
+    HFC    The rise of the high frequency content, where the levels
+           of the higher bands count more. Good for drums and other
+           percussive sounds.
+
+An onset is detected if 'odf' rises above the threshold. The threshold
+is made of a fixed part 'thrs' and an adaptive part, that follows the
+average of 'odf' over the time 'avg'. This suppresses false triggers in
+busy or noisy passages. After an onset, no further onsets are detected
+for the 'hold' time.
+"#;
+
+    fn update_bands(&mut self) {
+        let mut freqs = [0.0; ONSET_BANDS];
+        let ratio = (ONSET_MAX_FREQ / ONSET_MIN_FREQ).powf(1.0 / ((ONSET_BANDS - 1) as f32));
+        let nyquist = self.srate * 0.45;
+        for (i, f) in freqs.iter_mut().enumerate() {
+            *f = (ONSET_MIN_FREQ * ratio.powf(i as f32)).min(nyquist);
+        }
+        self.ana.bank.set_bands(&freqs[..]);
+
+        let win_len = ((ONSET_WIN_MS * self.srate) / 1000.0).round() as usize;
+        self.ana.bank.set_window(win_len, win_len / ONSET_HOPS_PER_WIN);
+    }
+
+    /// Calculates the onset detection function from the latest band levels.
+    fn detection_function(&mut self, hfc: bool) -> f32 {
+        if hfc {
+            let mut sum = 0.0;
+            let mut weights = 0.0;
+            for i in 0..ONSET_BANDS {
+                let weight = (i + 1) as f32;
+                sum += weight * self.ana.bank.level(i);
+                weights += weight;
+            }
+
+            let hfc = compress(sum / weights);
+            let rise = (hfc - self.ana.prev[0]).max(0.0);
+            self.ana.prev[0] = hfc;
+            rise
+        } else {
+            let mut flux = 0.0;
+            for (i, prev) in self.ana.prev.iter_mut().enumerate() {
+                let lvl = compress(self.ana.bank.level(i));
+                flux += (lvl - *prev).max(0.0);
+                *prev = lvl;
+            }
+
+            flux / (ONSET_BANDS as f32)
+        }
+    }
+}
+
+impl DspNode for Onset {
+    fn outputs() -> usize {
+        2
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        if srate != self.srate {
+            self.srate = srate;
+            self.ana.bank.set_sample_rate(srate);
+            self.update_bands();
+            self.ana.prev = [0.0; ONSET_BANDS];
+        }
+        self.trig_sig.set_sample_rate(srate);
+    }
+
+    fn reset(&mut self) {
+        self.ana.bank.reset();
+        self.trig_sig.reset();
+        self.ana.prev = [0.0; ONSET_BANDS];
+        self.odf = 0.0;
+        self.avg = 0.0;
+        self.hold = 0;
+        self.skip = false;
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        _ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        use crate::dsp::{at, denorm, inp, out};
+
+        let inp = inp::Onset::inp(inputs);
+        let thrs = inp::Onset::thrs(inputs);
+        let avg = inp::Onset::avg(inputs);
+        let hold = inp::Onset::hold(inputs);
+        let hfc = at::Onset::mode(atoms).i() == 1;
+        if hfc != self.hfc {
+            self.hfc = hfc;
+            self.skip = true;
+        }
+
+        let hop_ms = (self.ana.bank.hop() as f32 * 1000.0) / self.srate;
+
+        let mut trig = 0.0;
+        for frame in 0..ctx.nframes() {
+            self.hold = self.hold.saturating_sub(1);
+
+            if self.ana.bank.tick(inp.read(frame)) {
+                let odf = self.detection_function(hfc);
+
+                if !self.skip {
+                    self.odf = odf;
+                    let thrs = denorm::Onset::thrs(thrs, frame) + ONSET_ADAPT_MULT * self.avg;
+                    if self.odf > thrs && self.hold == 0 {
+                        self.trig_sig.trigger();
+                        let hold_ms = denorm::Onset::hold(hold, frame);
+                        self.hold = ((hold_ms * self.srate) / 1000.0) as u32;
+                    }
+
+                    let avg_ms = denorm::Onset::avg(avg, frame);
+                    let coef = (-hop_ms / avg_ms).exp();
+                    self.avg = self.odf + coef * (self.avg - self.odf);
+                }
+                self.skip = false;
+            }
+
+            trig = self.trig_sig.next();
+            let t = out::Onset::t(outputs);
+            t.write(frame, trig);
+            let odf = out::Onset::odf(outputs);
+            odf.write(frame, self.odf);
+        }
+
+        ctx_vals[0].set(trig);
+        ctx_vals[1].set(self.odf);
+    }
+}
@@ -2,7 +2,10 @@
 // This file is a part of HexoDSP. Released under GPL-3.0-or-later.
 // See README.md and COPYING for details.
 
-use crate::dsp::{at, denorm, inp, DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::dsp::{
+    at, denorm, inp, DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom,
+    MAX_OUTPUT_CHANNELS,
+};
 use crate::nodes::{NodeAudioContext, NodeExecContext};
 
 #[macro_export]
@@ -17,44 +20,37 @@ macro_rules! fa_out_mono {
     }};
 }
 
-/// The (stereo) output port of the plugin
+/// The (multichannel) output port of the plugin
 #[derive(Debug, Clone)]
-pub struct Out {
-    /// - 0: signal channel 1
-    /// - 1: signal channel 2
-    #[allow(dead_code)]
-    input: [f32; 2],
-}
+pub struct Out {}
 
 impl Out {
     pub fn new(_nid: &NodeId) -> Self {
-        Self { input: [0.0; 2] }
+        Self {}
     }
 
     pub const mono: &'static str =
-        "Out mono\nIf set to 'Mono', ch1 will be sent to both output channels.\n(UI only)";
+        "Out mono\nIf set to 'Mono', ch1 will be sent to both output channels 1 and 2.\n(UI only)";
     pub const gain: &'static str =
         "Out gain\nThe main gain of the synthesizer output, applied to all channels. \
         Please note that this is a linear control, to prevent inaccuracies for 1.0. \
         \nRange: (0..1)";
     pub const ch1: &'static str = "Out ch1\nAudio channel 1 (left)\nRange: (-1..1)";
     pub const ch2: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-
-    pub const ch3: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch4: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch5: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch6: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch7: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch8: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch9: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch10: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch11: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch12: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch13: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch14: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch15: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch16: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
-    pub const ch17: &'static str = "Out ch2\nAudio channel 2 (right)\nRange: (-1..1)";
+    pub const ch3: &'static str = "Out ch3\nAudio channel 3\nRange: (-1..1)";
+    pub const ch4: &'static str = "Out ch4\nAudio channel 4\nRange: (-1..1)";
+    pub const ch5: &'static str = "Out ch5\nAudio channel 5\nRange: (-1..1)";
+    pub const ch6: &'static str = "Out ch6\nAudio channel 6\nRange: (-1..1)";
+    pub const ch7: &'static str = "Out ch7\nAudio channel 7\nRange: (-1..1)";
+    pub const ch8: &'static str = "Out ch8\nAudio channel 8\nRange: (-1..1)";
+    pub const ch9: &'static str = "Out ch9\nAudio channel 9\nRange: (-1..1)";
+    pub const ch10: &'static str = "Out ch10\nAudio channel 10\nRange: (-1..1)";
+    pub const ch11: &'static str = "Out ch11\nAudio channel 11\nRange: (-1..1)";
+    pub const ch12: &'static str = "Out ch12\nAudio channel 12\nRange: (-1..1)";
+    pub const ch13: &'static str = "Out ch13\nAudio channel 13\nRange: (-1..1)";
+    pub const ch14: &'static str = "Out ch14\nAudio channel 14\nRange: (-1..1)";
+    pub const ch15: &'static str = "Out ch15\nAudio channel 15\nRange: (-1..1)";
+    pub const ch16: &'static str = "Out ch16\nAudio channel 16\nRange: (-1..1)";
 
     pub const DESC: &'static str = "Audio Output Port\n\n\
         This output port node allows you to send audio signals \
@@ -65,6 +61,10 @@ This output port node allows you to send audio signals to audio devices
 or tracks in your DAW. If you need a stereo output but only have a mono
 signal you can use the 'mono' setting to duplicate the signal on the 'ch1'
 input to the second channel 'ch2'.
+
+The inputs 'ch1' to 'ch16' are routed to the audio channels 1 to 16,
+for instance for quadrophonic or ambisonic setups. Channels that the
+audio device or host does not provide are ignored.
 "#;
 }
 
@@ -90,19 +90,32 @@ impl DspNode for Out {
         let in1 = inp::Out::ch1(inputs);
         let gain = inp::Out::gain(inputs);
 
-        if at::Out::mono(atoms).i() > 0 {
-            for frame in 0..ctx.nframes() {
-                let gain = denorm::Out::gain(gain, frame);
-                ctx.output(0, frame, gain * in1.read(frame));
-                ctx.output(1, frame, gain * in1.read(frame));
-            }
-        } else {
-            let in2 = inp::Out::ch2(inputs);
+        let channels = [
+            in1,
+            inp::Out::ch2(inputs),
+            inp::Out::ch3(inputs),
+            inp::Out::ch4(inputs),
+            inp::Out::ch5(inputs),
+            inp::Out::ch6(inputs),
+            inp::Out::ch7(inputs),
+            inp::Out::ch8(inputs),
+            inp::Out::ch9(inputs),
+            inp::Out::ch10(inputs),
+            inp::Out::ch11(inputs),
+            inp::Out::ch12(inputs),
+            inp::Out::ch13(inputs),
+            inp::Out::ch14(inputs),
+            inp::Out::ch15(inputs),
+            inp::Out::ch16(inputs),
+        ];
+        let ch_count = ctx.output_channels().min(MAX_OUTPUT_CHANNELS);
+
+        for (ch, buf) in channels.iter().enumerate().take(ch_count) {
+            let buf = if ch == 1 && at::Out::mono(atoms).i() > 0 { &in1 } else { buf };
 
             for frame in 0..ctx.nframes() {
                 let gain = denorm::Out::gain(gain, frame);
-                ctx.output(0, frame, gain * in1.read(frame));
-                ctx.output(1, frame, gain * in2.read(frame));
+                ctx.output(ch, frame, gain * buf.read(frame));
             }
         }
 
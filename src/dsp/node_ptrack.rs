@@ -0,0 +1,122 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::yin::Yin;
+use crate::dsp::{DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+/// A monophonic pitch tracker
+#[derive(Debug, Clone)]
+pub struct PTrack {
+    yin: Box<Yin>,
+    pitch: f32,
+    gate: bool,
+}
+
+impl PTrack {
+    pub fn new(_nid: &NodeId) -> Self {
+        Self { yin: Box::new(Yin::new()), pitch: 0.0, gate: false }
+    }
+    pub const inp: &'static str = "PTrack inp\nSignal input\nRange: (-1..1)\n";
+    pub const fmin: &'static str =
+        "PTrack fmin\nLowest frequency that is detected. Keep this close to the \
+        lowest note of the input, lower values need more CPU time.\nRange: (-1..1)\n";
+    pub const fmax: &'static str =
+        "PTrack fmax\nHighest frequency that is detected.\nRange: (-1..1)\n";
+    pub const thrs: &'static str = "PTrack thrs\nThe confidence 'conf' needs to be above this \
+        to open the 'gat' output.\nRange: (0..1)\n";
+    pub const freq: &'static str =
+        "PTrack freq\nThe detected pitch, in the same format as the 'freq' inputs \
+        of oscillators and filters. The last pitch is held while no pitch is \
+        detected.\nRange: (-1..1)\n";
+    pub const conf: &'static str =
+        "PTrack conf\nConfidence of the detected pitch, from 0.0 (no pitch) \
+        to 1.0 (perfectly periodic signal).\nRange: (0..1)\n";
+    pub const gat: &'static str =
+        "PTrack gat\nThis gate is high while a pitch is detected with enough \
+        confidence.\nRange: (0..1)\n";
+    pub const DESC: &'static str = r#"Pitch Tracker
+
+Detects the fundamental frequency of a monophonic input signal and emits it as pitch signal, suitable for the 'freq' input of oscillators and filters.
+"#;
+    pub const HELP: &'static str = r#"PTrack - Monophonic Pitch Tracker
+
+This node detects the pitch of the input signal with the YIN
+algorithm. It works best on monophonic signals like a single voice,
+a guitar string or a bass line.
+
+The detected pitch is emitted on 'freq' in the same format that the 'freq'
+inputs of nodes like 'Sin', 'BOsc' or 'SFilter' expect. So connect it
+to an oscillator to follow a played melody, or to a 'Quant' to snap it
+to a scale. The pitch is updated roughly every 12ms, about 12ms after
+the end of the analyzed part of the signal.
+
+How periodic the input is, is emitted on 'conf'. Noise and silence result
+in low values, a clean tone in values near 1.0. The 'gat' output is high
+as long as 'conf' is above 'thrs', it can be used to trigger envelopes.
+While no pitch is detected, the last pitch is held on 'freq'.
+
+Set 'fmin' and 'fmax' to the range of the played notes. This avoids
+octave errors and saves CPU time, which grows with lower 'fmin' values.
+"#;
+}
+
+impl DspNode for PTrack {
+    fn outputs() -> usize {
+        3
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        self.yin.set_sample_rate(srate);
+    }
+
+    fn reset(&mut self) {
+        self.yin.reset();
+        self.pitch = 0.0;
+        self.gate = false;
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        _ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        _atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        use crate::dsp::{denorm, inp, out};
+
+        let inp = inp::PTrack::inp(inputs);
+        let fmin = inp::PTrack::fmin(inputs);
+        let fmax = inp::PTrack::fmax(inputs);
+        let thrs = inp::PTrack::thrs(inputs);
+
+        for frame in 0..ctx.nframes() {
+            self.yin
+                .set_range(denorm::PTrack::fmin(fmin, frame), denorm::PTrack::fmax(fmax, frame));
+
+            if self.yin.tick(inp.read(frame)) {
+                self.gate = self.yin.confidence() >= denorm::PTrack::thrs(thrs, frame);
+
+                let freq = self.yin.freq();
+                if self.gate && freq > 0.0 {
+                    self.pitch = crate::n_pit!(freq);
+                }
+            }
+
+            let freq = out::PTrack::freq(outputs);
+            freq.write(frame, self.pitch);
+            let conf = out::PTrack::conf(outputs);
+            conf.write(frame, self.yin.confidence());
+            let gat = out::PTrack::gat(outputs);
+            gat.write(frame, if self.gate { 1.0 } else { 0.0 });
+        }
+
+        ctx_vals[0].set(if self.gate { 1.0 } else { 0.0 });
+        ctx_vals[1].set(self.yin.confidence());
+    }
+}
@@ -22,6 +22,14 @@ macro_rules! fa_tseq_cmode {
     }};
 }
 
+/// Writes the `slice` to `buf`, starting at frame `offs`.
+#[inline]
+fn write_at(buf: &mut ProcBuf, offs: usize, slice: &[f32]) {
+    for (i, v) in slice.iter().enumerate() {
+        buf.write(offs + i, *v);
+    }
+}
+
 #[derive(Debug)]
 pub struct TSeqTime {
     clock: TriggerPhaseClock,
@@ -201,77 +209,88 @@ impl DspNode for TSeq {
         backend.check_updates();
 
         let mut phase_out: [f32; MAX_BLOCK_SIZE] = [0.0; MAX_BLOCK_SIZE];
+        let mut col_out: [f32; MAX_BLOCK_SIZE] = [0.0; MAX_BLOCK_SIZE];
+        let mut col_out_gate: [f32; MAX_BLOCK_SIZE] = [0.0; MAX_BLOCK_SIZE];
 
         let cmode = cmode.i();
         let plen = backend.pattern_len().max(1) as f64;
 
         let time = &mut self.time;
 
-        for frame in 0..ctx.nframes() {
-            if time.trigger.check_trigger(denorm::TSeq::trig(trig, frame)) {
-                time.clock.sync();
-                println!("CLOCK SYNC");
+        // The block can be longer than the temporary buffers,
+        // if the engine was created with a larger maximum block size:
+        let nframes = ctx.nframes();
+        let mut offs = 0;
+        while offs < nframes {
+            let len = (nframes - offs).min(MAX_BLOCK_SIZE);
+
+            for (i, phase_out) in phase_out.iter_mut().enumerate().take(len) {
+                let frame = offs + i;
+                if time.trigger.check_trigger(denorm::TSeq::trig(trig, frame)) {
+                    time.clock.sync();
+                    println!("CLOCK SYNC");
+                }
+
+                let phase = match cmode {
+                    0 => time.clock.next_phase(plen, clock.read(frame)) / plen,
+                    1 => time.clock.next_phase(1.0, clock.read(frame)),
+                    2 | _ => (clock.read(frame).abs() as f64).fract(),
+                };
+
+                *phase_out = phase as f32;
             }
 
-            let phase = match cmode {
-                0 => time.clock.next_phase(plen, clock.read(frame)) / plen,
-                1 => time.clock.next_phase(1.0, clock.read(frame)),
-                2 | _ => (clock.read(frame).abs() as f64).fract(),
-            };
+            let col_out_slice = &mut col_out[0..len];
+            let col_out_gate_slice = &mut col_out_gate[0..len];
+            let phase_out_slice = &phase_out[0..len];
 
-            phase_out[frame] = phase as f32;
-        }
+            let out_t1 = out::TSeq::trk1(outputs);
+            backend.get_col_at_phase(0, phase_out_slice, col_out_slice, col_out_gate_slice);
+            write_at(out_t1, offs, col_out_slice);
 
-        let mut col_out: [f32; MAX_BLOCK_SIZE] = [0.0; MAX_BLOCK_SIZE];
-        let mut col_out_gate: [f32; MAX_BLOCK_SIZE] = [0.0; MAX_BLOCK_SIZE];
-        let col_out_slice = &mut col_out[0..ctx.nframes()];
-        let col_out_gate_slice = &mut col_out_gate[0..ctx.nframes()];
-        let phase_out_slice = &phase_out[0..ctx.nframes()];
+            let out_g1 = out::TSeq::gat1(outputs);
+            write_at(out_g1, offs, col_out_gate_slice);
 
-        let out_t1 = out::TSeq::trk1(outputs);
-        backend.get_col_at_phase(0, phase_out_slice, col_out_slice, col_out_gate_slice);
-        out_t1.write_from(col_out_slice);
+            ctx_vals[0].set(col_out_slice[col_out_slice.len() - 1]);
 
-        let out_g1 = out::TSeq::gat1(outputs);
-        out_g1.write_from(col_out_gate_slice);
+            let out_t2 = out::TSeq::trk2(outputs);
+            backend.get_col_at_phase(1, phase_out_slice, col_out_slice, col_out_gate_slice);
+            write_at(out_t2, offs, col_out_slice);
 
-        ctx_vals[0].set(col_out_slice[col_out_slice.len() - 1]);
+            let out_g2 = out::TSeq::gat2(outputs);
+            write_at(out_g2, offs, col_out_gate_slice);
 
-        let out_t2 = out::TSeq::trk2(outputs);
-        backend.get_col_at_phase(1, phase_out_slice, col_out_slice, col_out_gate_slice);
-        out_t2.write_from(col_out_slice);
+            let out_t3 = out::TSeq::trk3(outputs);
+            backend.get_col_at_phase(2, phase_out_slice, col_out_slice, col_out_gate_slice);
+            write_at(out_t3, offs, col_out_slice);
 
-        let out_g2 = out::TSeq::gat2(outputs);
-        out_g2.write_from(col_out_gate_slice);
+            let out_g3 = out::TSeq::gat3(outputs);
+            write_at(out_g3, offs, col_out_gate_slice);
 
-        let out_t3 = out::TSeq::trk3(outputs);
-        backend.get_col_at_phase(2, phase_out_slice, col_out_slice, col_out_gate_slice);
-        out_t3.write_from(col_out_slice);
+            let out_t4 = out::TSeq::trk4(outputs);
+            backend.get_col_at_phase(3, phase_out_slice, col_out_slice, col_out_gate_slice);
+            write_at(out_t4, offs, col_out_slice);
 
-        let out_g3 = out::TSeq::gat3(outputs);
-        out_g3.write_from(col_out_gate_slice);
+            let out_g4 = out::TSeq::gat4(outputs);
+            write_at(out_g4, offs, col_out_gate_slice);
 
-        let out_t4 = out::TSeq::trk4(outputs);
-        backend.get_col_at_phase(3, phase_out_slice, col_out_slice, col_out_gate_slice);
-        out_t4.write_from(col_out_slice);
+            let out_t5 = out::TSeq::trk5(outputs);
+            backend.get_col_at_phase(4, phase_out_slice, col_out_slice, col_out_gate_slice);
+            write_at(out_t5, offs, col_out_slice);
 
-        let out_g4 = out::TSeq::gat4(outputs);
-        out_g4.write_from(col_out_gate_slice);
+            let out_g5 = out::TSeq::gat5(outputs);
+            write_at(out_g5, offs, col_out_gate_slice);
 
-        let out_t5 = out::TSeq::trk5(outputs);
-        backend.get_col_at_phase(4, phase_out_slice, col_out_slice, col_out_gate_slice);
-        out_t5.write_from(col_out_slice);
+            let out_t6 = out::TSeq::trk6(outputs);
+            backend.get_col_at_phase(5, phase_out_slice, col_out_slice, col_out_gate_slice);
+            write_at(out_t6, offs, col_out_slice);
 
-        let out_g5 = out::TSeq::gat5(outputs);
-        out_g5.write_from(col_out_gate_slice);
+            let out_g6 = out::TSeq::gat6(outputs);
+            write_at(out_g6, offs, col_out_gate_slice);
 
-        let out_t6 = out::TSeq::trk6(outputs);
-        backend.get_col_at_phase(5, phase_out_slice, col_out_slice, col_out_gate_slice);
-        out_t6.write_from(col_out_slice);
+            ctx_vals[1].set(phase_out_slice[phase_out_slice.len() - 1]);
 
-        let out_g6 = out::TSeq::gat6(outputs);
-        out_g6.write_from(col_out_gate_slice);
-
-        ctx_vals[1].set(phase_out_slice[phase_out_slice.len() - 1]);
+            offs += len;
+        }
     }
 }
@@ -0,0 +1,234 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::biquad::{Biquad, BiquadCoefs};
+use crate::dsp::{DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+#[macro_export]
+macro_rules! fa_vocoder_bands {
+    ($formatter: expr, $v: expr, $denorm_v: expr) => {{
+        write!($formatter, "{}", ($v.round() as usize).clamp(4, 32))
+    }};
+}
+
+/// The maximum number of vocoder bands.
+const MAX_VOCODER_BANDS: usize = 32;
+/// Center frequency of the lowest band.
+const VOCODER_MIN_FREQ: f32 = 100.0;
+/// Center frequency of the highest band.
+const VOCODER_MAX_FREQ: f32 = 8000.0;
+
+/// One band of the vocoder: A bandpass for the modulator, the envelope
+/// follower and a bandpass for the carrier. Each bandpass is
+/// made of two resonators in series for steeper slopes.
+#[derive(Debug, Clone, Copy, Default)]
+struct VocoderBand {
+    analysis: [Biquad; 2],
+    synthesis: [Biquad; 2],
+    /// Gain that brings the peak of each resonator to 1.0.
+    ana_norm: f32,
+    syn_norm: f32,
+    env: f32,
+}
+
+impl VocoderBand {
+    fn reset(&mut self) {
+        for bq in self.analysis.iter_mut().chain(self.synthesis.iter_mut()) {
+            bq.reset();
+        }
+        self.env = 0.0;
+    }
+}
+
+/// Returns the resonator coefficients and the gain that normalizes
+/// the peak of the resonator to 1.0.
+fn resonator(srate: f32, center: f32, bandwidth: f32) -> (BiquadCoefs, f32) {
+    let coefs = BiquadCoefs::resonator(srate, center, bandwidth);
+    // The peak gain of the resonator is 1 / sqrt(1 - r^2), with a2 = r^2:
+    let norm = (1.0 - coefs.a2).max(0.0).sqrt();
+    (coefs, norm)
+}
+
+/// A channel vocoder
+#[derive(Debug, Clone)]
+pub struct Vocoder {
+    bands: Box<[VocoderBand; MAX_VOCODER_BANDS]>,
+    srate: f32,
+    band_cnt: usize,
+    fshft: f32,
+}
+
+impl Vocoder {
+    pub fn new(_nid: &NodeId) -> Self {
+        Self {
+            bands: Box::new([VocoderBand::default(); MAX_VOCODER_BANDS]),
+            srate: 44100.0,
+            band_cnt: 0,
+            fshft: 0.0,
+        }
+    }
+    pub const modu: &'static str =
+        "Vocoder modu\nModulator input, usually a voice or drums. It's spectrum \
+        is imprinted onto the carrier.\nRange: (-1..1)\n";
+    pub const car: &'static str =
+        "Vocoder car\nCarrier input, usually a harmonically rich signal like \
+        a saw wave, a chord or noise.\nRange: (-1..1)\n";
+    pub const atk: &'static str =
+        "Vocoder atk\nAttack time of the band envelope followers.\nRange: (0..1)\n";
+    pub const rel: &'static str =
+        "Vocoder rel\nRelease time of the band envelope followers.\nRange: (0..1)\n";
+    pub const fshft: &'static str =
+        "Vocoder fshft\nFormant shift in semitones. Shifts the frequencies of the \
+        carrier bands against the modulator bands.\nRange: (-1..1)\n";
+    pub const gain: &'static str = "Vocoder gain\nOutput gain.\nRange: (0..1)\n";
+    pub const bands: &'static str = "Vocoder bands\nNumber of frequency bands.\n";
+    pub const sig: &'static str = "Vocoder sig\nVocoder output.\nRange: (-1..1)\n";
+    pub const DESC: &'static str = r#"Channel Vocoder
+
+Imprints the spectrum of the modulator signal onto the carrier signal, with up to 32 bands. The classic 'talking synthesizer' effect.
+"#;
+    pub const HELP: &'static str = r#"Vocoder - Channel Vocoder
+
+A vocoder splits the modulator signal ('modu') into frequency bands and
+measures the level of each band with an envelope follower. The carrier
+signal ('car') is split into the same bands, and each carrier band
+is amplified by the level of the corresponding modulator band.
+The result is the carrier with the spectral shape of the modulator.
+
+Feed a voice into 'modu' and a saw wave or a chord into 'car' to make the
+synthesizer talk. Drums on 'modu' give a rhythmic filter effect.
+
+The number of bands is set with 'bands', they are spread logarithmically
+from 100Hz to 8kHz. More bands make the speech more intelligible.
+
+'atk' and 'rel' set the attack and release time of the envelope
+followers. Short times follow the modulator closely, longer times
+smear the sound.
+
+'fshft' shifts the carrier bands up or down by a number of semitones,
+which moves the formants of the modulator, for instance to make a voice
+sound higher or deeper without changing the pitch of the carrier.
+"#;
+
+    fn update_bands(&mut self, band_cnt: usize, fshft: f32) {
+        self.band_cnt = band_cnt;
+        self.fshft = fshft;
+
+        let nyquist = self.srate * 0.45;
+        let ratio = (VOCODER_MAX_FREQ / VOCODER_MIN_FREQ).powf(1.0 / ((band_cnt - 1) as f32));
+        // The bandwidth reaches from the center to the neighbour bands' center:
+        let bw_factor = ratio.sqrt() - 1.0 / ratio.sqrt();
+        let shift = (2.0_f32).powf(fshft / 12.0);
+
+        for (i, band) in self.bands.iter_mut().enumerate().take(band_cnt) {
+            let freq = VOCODER_MIN_FREQ * ratio.powf(i as f32);
+
+            let center = freq.min(nyquist);
+            let (coefs, norm) = resonator(self.srate, center, center * bw_factor);
+            band.ana_norm = norm;
+            for bq in band.analysis.iter_mut() {
+                bq.set_coefs(coefs);
+            }
+
+            let center = (freq * shift).min(nyquist);
+            let (coefs, norm) = resonator(self.srate, center, center * bw_factor);
+            band.syn_norm = norm;
+            for bq in band.synthesis.iter_mut() {
+                bq.set_coefs(coefs);
+            }
+        }
+    }
+}
+
+impl DspNode for Vocoder {
+    fn outputs() -> usize {
+        1
+    }
+
+    fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        if self.band_cnt > 0 {
+            self.update_bands(self.band_cnt, self.fshft);
+        }
+    }
+
+    fn reset(&mut self) {
+        for band in self.bands.iter_mut() {
+            band.reset();
+        }
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        _ectx: &mut NodeExecContext,
+        _nctx: &NodeContext,
+        atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        use crate::dsp::{at, denorm, inp, out};
+
+        let modu = inp::Vocoder::modu(inputs);
+        let car = inp::Vocoder::car(inputs);
+        let atk = inp::Vocoder::atk(inputs);
+        let rel = inp::Vocoder::rel(inputs);
+        let fshft = inp::Vocoder::fshft(inputs);
+        let gain = inp::Vocoder::gain(inputs);
+        let bands = at::Vocoder::bands(atoms);
+        let out = out::Vocoder::sig(outputs);
+
+        let band_cnt = (bands.i().clamp(4, MAX_VOCODER_BANDS as i64)) as usize;
+        let shift = denorm::Vocoder::fshft(fshft, 0);
+        if band_cnt != self.band_cnt || (shift - self.fshft).abs() > 0.001 {
+            if band_cnt != self.band_cnt {
+                self.reset();
+            }
+            self.update_bands(band_cnt, shift);
+        }
+
+        let mut max_env: f32 = 0.0;
+        let mut last_out = 0.0;
+        for frame in 0..ctx.nframes() {
+            let atk_ms = denorm::Vocoder::atk(atk, frame).max(0.01);
+            let rel_ms = denorm::Vocoder::rel(rel, frame).max(0.01);
+            let atk_coef = (-1000.0 / (atk_ms * self.srate)).exp();
+            let rel_coef = (-1000.0 / (rel_ms * self.srate)).exp();
+
+            let m = modu.read(frame);
+            let c = car.read(frame);
+
+            let mut sum = 0.0;
+            for band in self.bands.iter_mut().take(band_cnt) {
+                let mut a = m;
+                for bq in band.analysis.iter_mut() {
+                    a = bq.tick(a) * band.ana_norm;
+                }
+                let mut s = c;
+                for bq in band.synthesis.iter_mut() {
+                    s = bq.tick(s) * band.syn_norm;
+                }
+
+                let level = a.abs();
+                let coef = if level > band.env { atk_coef } else { rel_coef };
+                band.env = level + coef * (band.env - level);
+
+                sum += s * band.env;
+            }
+
+            last_out = sum * denorm::Vocoder::gain(gain, frame);
+            out.write(frame, last_out);
+        }
+
+        for band in self.bands.iter().take(band_cnt) {
+            max_env = max_env.max(band.env);
+        }
+
+        ctx_vals[0].set(last_out);
+        ctx_vals[1].set(max_env);
+    }
+}
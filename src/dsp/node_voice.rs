@@ -0,0 +1,100 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::{denorm, inp, out, DspNode, LedPhaseVals, NodeContext, NodeId, ProcBuf, SAtom};
+use crate::nodes::{NodeAudioContext, NodeExecContext};
+
+/// Provides the pitch, gate and velocity of a voice of the polyphonic
+/// voice region.
+#[derive(Debug, Clone)]
+pub struct Voice {
+    trig_count: u32,
+    gate: bool,
+}
+
+impl Voice {
+    pub fn new(_nid: &NodeId) -> Self {
+        Self { trig_count: 0, gate: false }
+    }
+
+    pub const vsens: &'static str = "Voice vsens\nThe velocity sensitivity. At 0.0 the \
+        'vel' output is always 1.0, at 1.0 it is the velocity of the note.\nRange: (0..1)";
+    pub const pit: &'static str = "Voice pit\nThe pitch of the note that is played by this \
+        voice. Connect this directly to the 'freq' input of an oscillator.\nRange: (-1..1)";
+    pub const gate: &'static str = "Voice gate\nIs 1.0 as long as the note is held. If the \
+        voice is taken for a new note while it is still playing, the gate goes \
+        low for one sample to retrigger envelopes.\nRange: (0..1)";
+    pub const vel: &'static str =
+        "Voice vel\nThe velocity of the note that is played by this voice.\nRange: (0..1)";
+    pub const DESC: &'static str = r#"Polyphonic Voice
+
+Provides the pitch, gate and velocity of the notes that are assigned to this voice. Put this node into the voice region of the graph to play it polyphonically.
+"#;
+    pub const HELP: &'static str = r#"Voice - Polyphonic Voice
+
+This node provides the pitch, gate and velocity of the notes that are
+played with 'note_on' and 'note_off' on the Matrix.
+
+The voice region is a set of nodes that is duplicated for each voice,
+see 'Matrix::set_voice_region'. Each copy of this node in the region
+outputs the note of it's own voice. Outputs of the voice region that are
+connected to nodes outside of it carry the sum of all voices.
+
+If this node is not part of the voice region, it plays the first voice,
+which is handy for monophonic patches.
+
+The 'pit' output can be connected directly to the 'freq' input of the
+oscillators. The 'gate' output is meant to drive an envelope. With
+'vsens' you can reduce how much the velocity affects the 'vel' output.
+"#;
+}
+
+impl DspNode for Voice {
+    fn outputs() -> usize {
+        3
+    }
+
+    fn set_sample_rate(&mut self, _srate: f32) {}
+    fn reset(&mut self) {
+        self.trig_count = 0;
+        self.gate = false;
+    }
+
+    #[inline]
+    fn process<T: NodeAudioContext>(
+        &mut self,
+        ctx: &mut T,
+        ectx: &mut NodeExecContext,
+        nctx: &NodeContext,
+        _atoms: &[SAtom],
+        inputs: &[ProcBuf],
+        outputs: &mut [ProcBuf],
+        ctx_vals: LedPhaseVals,
+    ) {
+        let voice = *ectx.voices.voice(nctx.voice);
+
+        // A new note on a still playing voice needs a falling edge
+        // for retriggering:
+        let retrig = self.gate && voice.gate && voice.trig_count != self.trig_count;
+        self.trig_count = voice.trig_count;
+        self.gate = voice.gate;
+
+        let gate_v = if voice.gate { 1.0 } else { 0.0 };
+        let pit = voice.pitch();
+        let vsens = inp::Voice::vsens(inputs);
+
+        for frame in 0..ctx.nframes() {
+            let vsens = denorm::Voice::vsens(vsens, frame);
+
+            let pit_out = out::Voice::pit(outputs);
+            pit_out.write(frame, pit);
+            let gate_out = out::Voice::gate(outputs);
+            gate_out.write(frame, if retrig && frame == 0 { 0.0 } else { gate_v });
+            let vel_out = out::Voice::vel(outputs);
+            vel_out.write(frame, 1.0 - vsens * (1.0 - voice.vel));
+        }
+
+        ctx_vals[0].set(gate_v);
+    }
+}
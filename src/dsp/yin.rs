@@ -0,0 +1,287 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+//! A monophonic pitch detector based on the YIN algorithm.
+//!
+//! See: A. de Cheveigné and H. Kawahara, "YIN, a fundamental frequency
+//! estimator for speech and music", JASA 2002.
+
+use crate::dsp::helpers::process_1pole_lowpass;
+
+/// The lowest frequency the [Yin] detector can track.
+pub const YIN_MIN_FREQ: f32 = 20.0;
+
+/// The input is decimated to a rate of at least this.
+const YIN_INTERNAL_RATE: f32 = 11025.0;
+
+/// The internal rate can be up to twice [YIN_INTERNAL_RATE],
+/// so this is the longest period in internal samples.
+const YIN_MAX_TAU: usize = ((2.0 * YIN_INTERNAL_RATE) / YIN_MIN_FREQ) as usize + 1;
+
+/// Number of internal samples between two analyses.
+const YIN_HOP: usize = 128;
+
+/// Dips of the normalized difference function below this are
+/// taken as period candidates.
+const YIN_THRESHOLD: f32 = 0.15;
+
+/// Windows with an RMS below this (-60dB) are treated as silence.
+const YIN_SILENCE_RMS: f32 = 0.001;
+
+/// A pitch detector using the YIN algorithm.
+///
+/// The input is low pass filtered and decimated to roughly 11kHz, then every
+/// 128 decimated samples the period is searched in the range between
+/// the minimum and maximum frequency set with [Yin::set_range].
+/// The search is spread over the following 128 decimated samples,
+/// to keep the CPU load per sample even. The result is available with
+/// [Yin::freq] and [Yin::confidence].
+///
+/// The buffers are allocated up front, so that this can run in the
+/// audio thread without allocations. This struct is quite big, you
+/// want to put it into a `Box`.
+#[derive(Debug, Clone)]
+pub struct Yin {
+    buf: [f32; 2 * YIN_MAX_TAU],
+    /// Copy of the samples the running analysis works on.
+    frame: [f32; 2 * YIN_MAX_TAU],
+    diff: [f32; YIN_MAX_TAU + 1],
+    cmnd: [f32; YIN_MAX_TAU + 1],
+    wr: usize,
+    filled: usize,
+    hop_cnt: usize,
+
+    /// The next lag the running analysis computes, 0 if none is running.
+    tau: usize,
+    tau_min: usize,
+    tau_max: usize,
+    running_sum: f32,
+
+    decim: usize,
+    decim_cnt: usize,
+    lp_z: [f32; 2],
+    srate: f32,
+
+    min_freq: f32,
+    max_freq: f32,
+
+    freq: f32,
+    conf: f32,
+}
+
+impl Yin {
+    pub fn new() -> Self {
+        let mut this = Self {
+            buf: [0.0; 2 * YIN_MAX_TAU],
+            frame: [0.0; 2 * YIN_MAX_TAU],
+            diff: [0.0; YIN_MAX_TAU + 1],
+            cmnd: [0.0; YIN_MAX_TAU + 1],
+            wr: 0,
+            filled: 0,
+            hop_cnt: 0,
+            tau: 0,
+            tau_min: 0,
+            tau_max: 0,
+            running_sum: 0.0,
+            decim: 4,
+            decim_cnt: 0,
+            lp_z: [0.0; 2],
+            srate: 44100.0,
+            min_freq: 50.0,
+            max_freq: 2000.0,
+            freq: 0.0,
+            conf: 0.0,
+        };
+        this.set_sample_rate(44100.0);
+        this
+    }
+
+    pub fn set_sample_rate(&mut self, srate: f32) {
+        self.srate = srate;
+        self.decim = ((srate / YIN_INTERNAL_RATE).floor() as usize).max(1);
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.buf = [0.0; 2 * YIN_MAX_TAU];
+        self.wr = 0;
+        self.filled = 0;
+        self.hop_cnt = 0;
+        self.tau = 0;
+        self.decim_cnt = 0;
+        self.lp_z = [0.0; 2];
+        self.freq = 0.0;
+        self.conf = 0.0;
+    }
+
+    /// The sample rate the analysis runs at.
+    #[inline]
+    fn internal_rate(&self) -> f32 {
+        self.srate / (self.decim as f32)
+    }
+
+    /// Sets the range of frequencies (in Hz) that are searched.
+    /// The minimum is limited to [YIN_MIN_FREQ]. Lower minimum
+    /// frequencies need more CPU time.
+    pub fn set_range(&mut self, min_freq: f32, max_freq: f32) {
+        let nyquist = self.internal_rate() * 0.5;
+        self.min_freq = min_freq.clamp(YIN_MIN_FREQ, nyquist);
+        self.max_freq = max_freq.clamp(self.min_freq, nyquist);
+    }
+
+    /// The frequency in Hz of the last detected pitch.
+    /// It is held if the latest analysis did not find any pitch.
+    #[inline]
+    pub fn freq(&self) -> f32 {
+        self.freq
+    }
+
+    /// The confidence of the latest analysis, from 0.0 (no pitch)
+    /// to 1.0 (perfectly periodic signal).
+    #[inline]
+    pub fn confidence(&self) -> f32 {
+        self.conf
+    }
+
+    /// Feeds one sample into the detector. Returns true if a new
+    /// analysis was done.
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> bool {
+        let israte = 1.0 / self.srate;
+        let lp_freq = self.internal_rate() * 0.25;
+        let v = process_1pole_lowpass(input, lp_freq, israte, &mut self.lp_z[0]);
+        let v = process_1pole_lowpass(v, lp_freq, israte, &mut self.lp_z[1]);
+
+        self.decim_cnt += 1;
+        if self.decim_cnt < self.decim {
+            return false;
+        }
+        self.decim_cnt = 0;
+
+        self.buf[self.wr] = v;
+        self.wr = (self.wr + 1) % self.buf.len();
+        self.filled = (self.filled + 1).min(self.buf.len());
+
+        let done = self.tau > 0 && self.analyze_step();
+
+        self.hop_cnt += 1;
+        if self.hop_cnt >= YIN_HOP {
+            self.hop_cnt = 0;
+            self.start_analysis();
+        }
+
+        done
+    }
+
+    /// Takes a copy of the most recent samples and starts a new analysis,
+    /// that is continued by [Yin::analyze_step].
+    fn start_analysis(&mut self) {
+        let rate = self.internal_rate();
+        let tau_min = ((rate / self.max_freq).floor() as usize).max(2);
+        let tau_max = ((rate / self.min_freq).ceil() as usize).min(YIN_MAX_TAU - 1);
+        let win = tau_max;
+        let len = win + tau_max;
+
+        if self.filled < len || tau_min + 1 >= tau_max {
+            self.conf = 0.0;
+            return;
+        }
+
+        let n = self.buf.len();
+        let start = self.wr + n - len;
+        let mut energy = 0.0;
+        for (j, s) in self.frame[0..len].iter_mut().enumerate() {
+            *s = self.buf[(start + j) % n];
+            if j < win {
+                energy += *s * *s;
+            }
+        }
+        if (energy / (win as f32)).sqrt() < YIN_SILENCE_RMS {
+            self.conf = 0.0;
+            return;
+        }
+
+        self.tau_min = tau_min;
+        self.tau_max = tau_max;
+        self.diff[0] = 0.0;
+        self.cmnd[0] = 1.0;
+        self.running_sum = 0.0;
+        self.tau = 1;
+    }
+
+    /// Computes the next slice of the difference function. The slices are
+    /// sized so that the analysis is done before the next one starts.
+    /// Returns true if the analysis is finished.
+    fn analyze_step(&mut self) -> bool {
+        let win = self.tau_max;
+        let last = (self.tau + self.tau_max.div_ceil(YIN_HOP) - 1).min(self.tau_max);
+
+        // Difference function and it's cumulative mean normalized form:
+        for tau in self.tau..=last {
+            let mut d = 0.0;
+            for (a, b) in self.frame[0..win].iter().zip(self.frame[tau..(tau + win)].iter()) {
+                let delta = a - b;
+                d += delta * delta;
+            }
+            self.running_sum += d;
+            self.diff[tau] = d;
+            self.cmnd[tau] =
+                if self.running_sum > 0.0 { d * (tau as f32) / self.running_sum } else { 1.0 };
+        }
+
+        if last < self.tau_max {
+            self.tau = last + 1;
+            return false;
+        }
+        self.tau = 0;
+
+        self.pick_period();
+        true
+    }
+
+    /// Searches the period in the finished difference function.
+    fn pick_period(&mut self) {
+        let rate = self.internal_rate();
+        let (tau_min, tau_max) = (self.tau_min, self.tau_max);
+
+        // First dip below the threshold, or the global minimum:
+        let mut best = None;
+        let mut tau = tau_min;
+        while tau < tau_max {
+            if self.cmnd[tau] < YIN_THRESHOLD {
+                while tau + 1 < tau_max && self.cmnd[tau + 1] < self.cmnd[tau] {
+                    tau += 1;
+                }
+                best = Some(tau);
+                break;
+            }
+            tau += 1;
+        }
+        let tau = best.unwrap_or_else(|| {
+            let mut min_tau = tau_min;
+            for tau in tau_min..tau_max {
+                if self.cmnd[tau] < self.cmnd[min_tau] {
+                    min_tau = tau;
+                }
+            }
+            min_tau
+        });
+
+        // Parabolic interpolation around the minimum of the raw difference:
+        let (a, b, c) = (self.diff[tau - 1], self.diff[tau], self.diff[tau + 1]);
+        let denom = a - 2.0 * b + c;
+        let offs = if denom.abs() > 1e-9 { (0.5 * (a - c) / denom).clamp(-0.5, 0.5) } else { 0.0 };
+
+        self.conf = (1.0 - self.cmnd[tau]).clamp(0.0, 1.0);
+        if best.is_some() {
+            self.freq = rate / (tau as f32 + offs);
+        }
+    }
+}
+
+impl Default for Yin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
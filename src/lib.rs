@@ -30,7 +30,8 @@ And following DSP nodes:
 
 | Category | Name | Function |
 |-|-|-|
-| IO Util | Out         | Audio output (to DAW or Jack) |
+| IO Util | Out         | Audio output (to DAW or Jack) with up to 16 channels |
+| IO Util | In          | Audio input (from DAW or Jack) with selectable channel pairs |
 | Osc     | Sampl       | Sample player |
 | Osc     | Sin         | Sine oscillator |
 | Osc     | BOsc        | Basic bandlimited waveform oscillator (waveforms: Sin, Tri, Saw, Pulse/Square) |
@@ -42,6 +43,12 @@ And following DSP nodes:
 | Signal  | PVerb       | Reverb node, based on Dattorros plate reverb algorithm |
 | Signal  | AllP        | All-Pass filter based on internal delay line feedback |
 | Signal  | Comb        | Comb filter |
+| Signal  | GzBank      | Goertzel filter bank, measures the level of up to 32 frequency bands |
+| Signal  | Dtmf        | DTMF (touch tone) decoder |
+| Signal  | Chroma      | Chromagram based chord detector, emits the root pitch |
+| Signal  | PTrack      | Monophonic pitch tracker, emits the detected pitch |
+| Signal  | Vocoder     | Channel vocoder with up to 32 bands and formant shift |
+| Signal  | Onset       | Onset/transient detector, emits a trigger for each detected onset |
 | N-\>M   | Mix3        | 3 channel mixer |
 | N-\>M   | Mux9        | 9 channel to 1 output multiplexer/switch |
 | Ctrl    | SMap        | Simple control signal mapper |
@@ -52,6 +59,10 @@ And following DSP nodes:
 | Mod     | Ad          | Attack-Decay envelope |
 | Mod     | TsLFO       | Tri/Saw waveform low frequency oscillator (LFO) |
 | Mod     | RndWk       | Random walker, a Sample & Hold noise generator |
+| Mod     | Follow      | Envelope follower with peak, RMS and LUFS modes and a gate output |
+| Mod     | Voice       | Pitch, gate and velocity of a polyphonic voice |
+| Mod     | MidiP       | MIDI to CV converter with pitch, gate, velocity, aftertouch, pitch bend and mod wheel |
+| Mod     | Clock       | Transport synced clock with note divisions, swing, beat, bar and reset outputs |
 | IO Util | FbWr / FbRd | Utility modules for feedback in patches |
 
 ## API Examples
@@ -155,6 +166,88 @@ let (out_l, out_r) = node_exec.test_run(0.11, true);
 // samples now.
 ```
 
+### Offline Rendering
+
+To render a patch at any sample rate and block size into memory or
+a WAV file, use the [crate::render::OfflineRenderer]:
+
+```rust
+use hexodsp::*;
+
+let (node_conf, mut node_exec) = new_node_engine();
+let mut matrix = Matrix::new(node_conf, 3, 3);
+
+let mut chain = MatrixCellChain::new(CellDir::B);
+chain.node_out("sin", "sig").node_inp("out", "ch1");
+chain.place(&mut matrix, 0, 0).unwrap();
+matrix.sync().unwrap();
+
+let audio = OfflineRenderer::new(48000.0)
+    .pre_roll(0.1)
+    .tail(0.0001, 5.0)
+    .render(&mut node_exec, 2.0);
+// audio.write_wav("patch.wav").unwrap();
+```
+
+### MIDI Input
+
+MIDI events are fed to the DSP thread with a frame offset into the next
+processed block. The MidiP node converts them into pitch, gate and other
+control signals:
+
+```rust
+use hexodsp::*;
+use hexodsp::nodes::MidiEvent;
+
+let (node_conf, mut node_exec) = new_node_engine();
+let mut matrix = Matrix::new(node_conf, 3, 3);
+
+let mut chain = MatrixCellChain::new(CellDir::B);
+chain.node_out("midip", "pit").node_io("sin", "freq", "sig").node_inp("out", "ch1");
+chain.place(&mut matrix, 0, 0).unwrap();
+matrix.sync().unwrap();
+
+// Note on with a note of 60 at frame 10, from the audio thread:
+node_exec.feed_midi_event(MidiEvent::from_bytes(10, &[0x90, 60, 127]).unwrap());
+// Or from another thread, like a MIDI driver:
+let mut midi_in = node_exec.take_midi_input().unwrap();
+midi_in.send(MidiEvent::from_bytes(2000, &[0x80, 60, 0]).unwrap());
+
+let (out_l, out_r) = node_exec.test_run(0.1, false);
+```
+
+MIDI controllers are mapped to parameters by the [Matrix], see the
+[cc_map] module.
+
+### Transport
+
+The host passes its tempo, time signature, play state and song position
+to the DSP thread with the `Transport` struct, either from the audio thread
+or with [Matrix::set_transport]. The Clock node generates gates,
+beat and bar triggers from it:
+
+```rust
+use hexodsp::*;
+use hexodsp::nodes::Transport;
+
+let (node_conf, mut node_exec) = new_node_engine();
+let mut matrix = Matrix::new(node_conf, 3, 3);
+
+let mut chain = MatrixCellChain::new(CellDir::B);
+chain.node_out("clock", "clk").node_inp("out", "ch1");
+chain.place(&mut matrix, 0, 0).unwrap();
+matrix.sync().unwrap();
+
+// Before each period, from the audio thread:
+let mut transport = Transport::new();
+transport.bpm = 98.0;
+transport.playing = true;
+transport.set_position(16.0);
+node_exec.set_transport(transport);
+
+let (out_l, out_r) = node_exec.test_run(0.1, false);
+```
+
 ## State of Development
 
 As of 2021-05-18: The architecture and it's functionality have been mostly
@@ -300,6 +393,7 @@ projects and authors, I can't relicense it.
 
 */
 
+pub mod cc_map;
 pub mod cell_dir;
 pub mod chain_builder;
 #[allow(unused_macros, non_snake_case)]
@@ -309,6 +403,7 @@ pub mod matrix;
 pub mod matrix_repr;
 pub mod monitor;
 pub mod nodes;
+pub mod render;
 pub mod sample_lib;
 mod util;
 
@@ -319,7 +414,11 @@ pub use log::log;
 pub use matrix::{Cell, Matrix};
 pub use matrix_repr::load_patch_from_file;
 pub use matrix_repr::save_patch_to_file;
-pub use nodes::{new_node_engine, NodeConfigurator, NodeExecutor};
+pub use nodes::{
+    new_node_engine, new_node_engine_with_block_size, new_node_engine_with_capacity,
+    NodeConfigurator, NodeExecutor,
+};
+pub use render::{OfflineRenderer, RenderedAudio};
 pub use sample_lib::{SampleLibrary, SampleLoadError};
 
 pub struct Context<'a, 'b, 'c, 'd> {
@@ -336,12 +435,25 @@ impl<'a, 'b, 'c, 'd> nodes::NodeAudioContext for Context<'a, 'b, 'c, 'd> {
 
     #[inline]
     fn output(&mut self, channel: usize, frame: usize, v: f32) {
-        self.output[channel][frame] = v;
+        if let Some(ch) = self.output.get_mut(channel) {
+            ch[frame] = v;
+        }
     }
 
     #[inline]
     fn input(&mut self, channel: usize, frame: usize) -> f32 {
-        self.input[channel][frame]
+        // Channels the driver does not provide are silent:
+        self.input.get(channel).map(|ch| ch[frame]).unwrap_or(0.0)
+    }
+
+    #[inline]
+    fn output_channels(&self) -> usize {
+        self.output.len()
+    }
+
+    #[inline]
+    fn input_channels(&self) -> usize {
+        self.input.len()
     }
 }
 
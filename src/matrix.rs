@@ -2,12 +2,16 @@
 // This file is a part of HexoDSP. Released under GPL-3.0-or-later.
 // See README.md and COPYING for details.
 
+use crate::cc_map::{CcMap, CcMapping};
 use crate::dsp::tracker::PatternData;
 use crate::dsp::{NodeId, NodeInfo, ParamId, SAtom};
 use crate::matrix_repr::*;
 pub use crate::monitor::MON_SIG_CNT;
 pub use crate::nodes::MinMaxMonitorSamples;
-use crate::nodes::{NodeConfigurator, NodeGraphOrdering, NodeProg, MAX_ALLOCATED_NODES};
+use crate::nodes::{
+    DspLoad, NodeConfigurator, NodeGraphOrdering, NodeProg, NodeState, SignalFault, Transport,
+    VoiceSteal, MAX_ALLOCATED_NODES,
+};
 pub use crate::CellDir;
 
 use std::collections::{HashMap, HashSet};
@@ -465,6 +469,9 @@ pub trait MatrixObserver {
     /// The called then needs up update all it's internal state it knows
     /// about [Matrix].
     fn update_all(&self);
+    /// Called for each [SignalFault] that is received
+    /// by [Matrix::update_signal_faults].
+    fn update_signal_fault(&self, _fault: &SignalFault) {}
 }
 
 pub struct Matrix {
@@ -499,6 +506,10 @@ pub struct Matrix {
     /// using [Matrix::set_prop] and [Matrix::get_prop].
     properties: HashMap<String, SAtom>,
 
+    /// Maps MIDI controllers to parameters, see [Matrix::handle_cc].
+    /// The mappings are saved with the [MatrixRepr].
+    cc_map: CcMap,
+
     /// Stores the [crate::dsp::ParamId] of the inputs that have an output
     /// assigned to them. It's updates when [Matrix::edges] is updated and used
     /// by [Matrix::param_input_is_used] to return whether a parameter is
@@ -532,6 +543,7 @@ impl Matrix {
             edges: Vec::with_capacity(MAX_ALLOCATED_NODES * 2),
             assigned_inputs: HashSet::new(),
             properties: HashMap::new(),
+            cc_map: CcMap::new(),
             observer: None,
             config,
             w,
@@ -745,6 +757,7 @@ impl Matrix {
         self.assigned_inputs.clear();
         self.saved_matrix = None;
         self.properties.clear();
+        self.cc_map.clear();
 
         self.config.delete_nodes();
         self.monitor_cell(Cell::empty(NodeId::Nop));
@@ -823,7 +836,11 @@ impl Matrix {
 
         let properties = self.properties.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
 
-        MatrixRepr { cells, params, atoms, patterns, properties, version: 2 }
+        let cc_map = self.cc_map.mappings().to_vec();
+
+        let node_states = self.config.node_states();
+
+        MatrixRepr { cells, params, atoms, patterns, properties, cc_map, node_states, version: 2 }
     }
 
     /// Loads the matrix from a previously my [Matrix::to_repr]
@@ -842,6 +859,14 @@ impl Matrix {
             self.properties.insert(key.to_string(), val.clone());
         }
 
+        for mapping in repr.cc_map.iter() {
+            self.cc_map.add(*mapping);
+        }
+
+        for (node_id, state) in repr.node_states.iter() {
+            self.config.set_node_state(*node_id, *state);
+        }
+
         for cell_repr in repr.cells.iter() {
             let cell = Cell::from_repr(cell_repr);
             self.place(cell.x as usize, cell.y as usize, cell);
@@ -947,6 +972,24 @@ impl Matrix {
         self.config.pop_error()
     }
 
+    /// Receives the NaN, infinite or denormal outputs, that the DSP thread
+    /// detected and cleared. Call this every UI frame. The [MatrixObserver]
+    /// is notified about each fault and an error message is
+    /// available via [Matrix::pop_error].
+    ///
+    /// See also [NodeConfigurator::update_signal_faults].
+    pub fn update_signal_faults(&mut self) -> Vec<SignalFault> {
+        let faults = self.config.update_signal_faults();
+
+        if let Some(obs) = &self.observer {
+            for fault in faults.iter() {
+                obs.update_signal_fault(fault);
+            }
+        }
+
+        faults
+    }
+
     /// Retrieve [SAtom] values for input parameters and atoms.
     pub fn get_param(&self, param: &ParamId) -> Option<SAtom> {
         self.config.get_param(param)
@@ -961,6 +1004,74 @@ impl Matrix {
         }
     }
 
+    /// Assigns the [SAtom] value of an input parameter or atom at the
+    /// `frame` offset into the next processed block, for sample accurate
+    /// automation. See also [NodeConfigurator::set_param_at].
+    pub fn set_param_at(&mut self, param: ParamId, at: SAtom, frame: usize) {
+        self.config.set_param_at(param, at, frame);
+        self.gen_counter += 1;
+        if let Some(obs) = &self.observer {
+            obs.update_param(&param);
+        }
+    }
+
+    /// Passes a MIDI control change message with the `channel` (0 to 15),
+    /// the controller number `cc` and it's `value` to the parameters that
+    /// are mapped to it. If [Matrix::learn_cc] was called before, the
+    /// controller is bound to the parameter that is learned.
+    ///
+    /// Returns true if the controller is mapped.
+    /// See also [crate::cc_map] for an example.
+    pub fn handle_cc(&mut self, channel: u8, cc: u8, value: u8) -> bool {
+        let learning = self.cc_map.learn_param().is_some();
+
+        let config = &self.config;
+        let mut updates = vec![];
+        let handled =
+            self.cc_map.handle_cc(channel, cc, value, |p| config.get_param(p), &mut updates);
+
+        if learning {
+            self.gen_counter += 1;
+        }
+
+        for (param, at) in updates {
+            self.set_param(param, at);
+        }
+
+        handled
+    }
+
+    /// Binds the next controller that is passed to [Matrix::handle_cc]
+    /// to the parameter `param`. Previous mappings of `param` are removed.
+    /// `None` stops learning.
+    pub fn learn_cc(&mut self, param: Option<ParamId>) {
+        self.cc_map.learn(param);
+    }
+
+    /// Returns the parameter that waits for [Matrix::handle_cc]
+    /// to learn it's controller.
+    pub fn cc_learn_param(&self) -> Option<ParamId> {
+        self.cc_map.learn_param()
+    }
+
+    /// Adds a mapping of a MIDI controller to a parameter. A previous
+    /// mapping of the same controller to the same parameter is replaced.
+    pub fn add_cc_mapping(&mut self, mapping: CcMapping) {
+        self.cc_map.add(mapping);
+        self.gen_counter += 1;
+    }
+
+    /// Removes all controller mappings of the parameter `param`.
+    pub fn remove_cc_mappings(&mut self, param: &ParamId) {
+        self.cc_map.remove_param(param);
+        self.gen_counter += 1;
+    }
+
+    /// Returns all controller mappings.
+    pub fn cc_mappings(&self) -> &[CcMapping] {
+        self.cc_map.mappings()
+    }
+
     /// Retrieve the modulation amount of the input parameter.
     pub fn get_param_modamt(&self, param: &ParamId) -> Option<f32> {
         self.config.get_param_modamt(param)
@@ -990,6 +1101,42 @@ impl Matrix {
         }
     }
 
+    /// Marks the `nodes` that are duplicated for each of the `voices`.
+    /// Call [Matrix::sync] afterwards to apply the change.
+    ///
+    /// See also [NodeConfigurator::set_voice_region].
+    pub fn set_voice_region(&mut self, nodes: &[NodeId], voices: usize) {
+        self.config.set_voice_region(nodes, voices);
+    }
+
+    /// Returns the nodes of the voice region and the number of voices.
+    pub fn voice_region(&self) -> (&[NodeId], usize) {
+        self.config.voice_region()
+    }
+
+    /// Sets the policy which voice is taken for a new note
+    /// if all voices are playing.
+    pub fn set_voice_steal(&mut self, steal: VoiceSteal) {
+        self.config.set_voice_steal(steal);
+    }
+
+    /// Starts playing the MIDI `note` with the velocity `vel` (0..1)
+    /// on one of the voices. See also [Matrix::set_voice_region].
+    pub fn note_on(&mut self, note: u8, vel: f32) {
+        self.config.note_on(note, vel);
+    }
+
+    /// Releases the voice that plays the MIDI `note`.
+    pub fn note_off(&mut self, note: u8) {
+        self.config.note_off(note);
+    }
+
+    /// Sets the tempo and song position for the `Clock` node.
+    /// See also [NodeConfigurator::set_transport].
+    pub fn set_transport(&mut self, transport: Transport) {
+        self.config.set_transport(transport);
+    }
+
     pub fn get_adjacent_output(&self, x: usize, y: usize, dir: CellDir) -> Option<(NodeId, u8)> {
         if dir.is_output() {
             return None;
@@ -1414,6 +1561,95 @@ impl Matrix {
     pub fn update_output_feedback(&mut self) {
         self.config.update_output_feedback();
     }
+
+    /// Sets the number of worker threads for the parallel execution of the nodes.
+    /// See also [NodeConfigurator::set_worker_threads].
+    pub fn set_worker_threads(&mut self, count: usize) {
+        self.config.set_worker_threads(count);
+    }
+
+    /// Bypasses or mutes the node `node_id`, see [NodeState].
+    /// This takes effect right away, without a [Matrix::sync],
+    /// and is saved in the [MatrixRepr].
+    /// See also [NodeConfigurator::set_node_state].
+    pub fn set_node_state(&mut self, node_id: NodeId, state: NodeState) {
+        self.gen_counter += 1;
+        self.config.set_node_state(node_id, state);
+    }
+
+    /// Returns the [NodeState] of the node `node_id`.
+    pub fn node_state(&self, node_id: &NodeId) -> NodeState {
+        self.config.node_state(node_id)
+    }
+
+    /// Sets the length of the crossfade on [Matrix::sync] in milliseconds.
+    /// See also [NodeConfigurator::set_prog_crossfade].
+    pub fn set_prog_crossfade(&mut self, ms: f32) {
+        self.config.set_prog_crossfade(ms);
+    }
+
+    /// Enables or disables the measuring of the DSP load of the nodes.
+    /// See also [NodeConfigurator::set_dsp_profiling].
+    pub fn set_dsp_profiling(&mut self, enabled: bool) {
+        self.config.set_dsp_profiling(enabled);
+    }
+
+    /// Fetches the most recent DSP load measurements. Call this
+    /// every UI frame, before [Matrix::node_dsp_load], [Matrix::dsp_load]
+    /// or [Matrix::dsp_load_by_node].
+    ///
+    /// See also [NodeConfigurator::update_dsp_load].
+    pub fn update_dsp_load(&mut self) {
+        self.config.update_dsp_load();
+    }
+
+    /// Returns the DSP load of the node `node_id`.
+    pub fn node_dsp_load(&self, node_id: &NodeId) -> Option<DspLoad> {
+        self.config.node_dsp_load(node_id)
+    }
+
+    /// Returns the DSP load of the whole DSP graph.
+    pub fn dsp_load(&self) -> DspLoad {
+        self.config.dsp_load()
+    }
+
+    /// Returns the DSP load of all nodes in the matrix,
+    /// with the most expensive ones first.
+    ///
+    ///```
+    /// use hexodsp::*;
+    ///
+    /// let (node_conf, mut node_exec) = new_node_engine();
+    /// let mut matrix = Matrix::new(node_conf, 3, 3);
+    ///
+    /// let mut chain = MatrixCellChain::new(CellDir::B);
+    /// chain.node_out("sin", "sig").node_inp("out", "ch1");
+    /// chain.place(&mut matrix, 0, 0).unwrap();
+    /// matrix.sync().unwrap();
+    ///
+    /// matrix.set_dsp_profiling(true);
+    /// node_exec.test_run(0.5, false);
+    ///
+    /// matrix.update_dsp_load();
+    /// for (node_id, load) in matrix.dsp_load_by_node() {
+    ///     println!("{}: {:.1}us per block, {:.2}%", node_id, load.avg_us, load.load * 100.0);
+    /// }
+    /// assert!(matrix.dsp_load().avg_us > 0.0);
+    ///```
+    pub fn dsp_load_by_node(&self) -> Vec<(NodeId, DspLoad)> {
+        let mut loads: Vec<(NodeId, DspLoad)> = vec![];
+        self.for_each(|_x, _y, cell| {
+            let node_id = cell.node_id();
+            if node_id != NodeId::Nop && !loads.iter().any(|(nid, _)| *nid == node_id) {
+                if let Some(load) = self.node_dsp_load(&node_id) {
+                    loads.push((node_id, load));
+                }
+            }
+        });
+
+        loads.sort_by(|a, b| b.1.load.partial_cmp(&a.1.load).unwrap_or(std::cmp::Ordering::Equal));
+        loads
+    }
 }
 
 #[cfg(test)]
@@ -1570,7 +1806,7 @@ mod tests {
         assert_eq!(prog.prog[0].to_string(), "Op(i=0 out=(0-1|1) in=(0-2|0) at=(0-0) mod=(0-0))");
         assert_eq!(
             prog.prog[1].to_string(),
-            "Op(i=1 out=(1-1|0) in=(2-5|1) at=(0-1) mod=(0-0) cpy=(o0 => i2))"
+            "Op(i=1 out=(1-1|0) in=(2-19|1) at=(0-1) mod=(0-0) cpy=(o0 => i2))"
         );
     }
 
@@ -1603,7 +1839,7 @@ mod tests {
         assert_eq!(prog.prog[0].to_string(), "Op(i=2 out=(2-3|1) in=(4-6|0) at=(0-0) mod=(0-0))");
         assert_eq!(
             prog.prog[1].to_string(),
-            "Op(i=3 out=(3-3|0) in=(6-9|1) at=(0-1) mod=(0-0) cpy=(o2 => i6))"
+            "Op(i=3 out=(3-3|0) in=(6-23|1) at=(0-1) mod=(0-0) cpy=(o2 => i6))"
         );
     }
 
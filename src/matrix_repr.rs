@@ -2,8 +2,11 @@
 // This file is a part of HexoDSP. Released under GPL-3.0-or-later.
 // See README.md and COPYING for details.
 
+use crate::cc_map::CcMapping;
 use crate::dsp::{NodeId, ParamId, SAtom};
+use crate::nodes::NodeState;
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Copy)]
 pub struct CellRepr {
@@ -23,6 +26,96 @@ fn deserialize_node_id(v: &Value, i1: usize, i2: usize) -> Result<NodeId, Matrix
     Ok(nid.to_instance(v[i2].as_i64().unwrap_or(0) as usize))
 }
 
+/// Patches saved before the Gz3Filt node was replaced by the GzBank node
+/// refer to it as "goertzel". It's loaded as a GzBank with the 3 frequencies
+/// in a custom list, the latency becomes the window length.
+fn migrate_gz3filt(v: &mut Value) {
+    let is_gz3filt = |v: &Value| v[0].as_str() == Some("goertzel");
+    // Since version 2 the parameters are saved denormalized:
+    let denormalized = v["VERSION"].as_i64().unwrap_or(0) > 1;
+    let denorm = |v: f32| if denormalized { v } else { crate::d_pit!(v) };
+
+    // The frequencies and latency (in samples) of each instance:
+    let mut instances: BTreeMap<i64, ([f32; 3], f32)> = BTreeMap::new();
+
+    if let Some(cells) = v["cells"].as_array_mut() {
+        for c in cells.iter_mut().filter(|c| is_gz3filt(c)) {
+            instances.entry(c[1].as_i64().unwrap_or(0)).or_insert(([220.0, 330.0, 440.0], 2048.0));
+            c[0] = json!("gzbank");
+
+            for inp in c[4].as_array_mut().into_iter().flatten() {
+                *inp = match (inp.as_str(), inp.as_i64()) {
+                    (Some("inp"), _) | (_, Some(0)) => json!("inp"),
+                    (Some("gain"), _) | (_, Some(5)) => json!("gain"),
+                    _ => json!(-1),
+                };
+            }
+
+            for out in c[5].as_array_mut().into_iter().flatten() {
+                *out = match (out.as_str(), out.as_i64()) {
+                    (Some("sigf1"), _) | (_, Some(0)) => json!("b1"),
+                    (Some("sigf2"), _) | (_, Some(1)) => json!("b2"),
+                    (Some("sigf3"), _) | (_, Some(2)) => json!("b3"),
+                    _ => json!(-1),
+                };
+            }
+        }
+    }
+
+    if let Some(params) = v["params"].as_array_mut() {
+        for p in params.iter_mut().filter(|p| is_gz3filt(p)) {
+            let inst = instances
+                .entry(p[1].as_i64().unwrap_or(0))
+                .or_insert(([220.0, 330.0, 440.0], 2048.0));
+            let value = p[3].as_f64().unwrap_or(0.0) as f32;
+
+            match p[2].as_str().unwrap_or("") {
+                "freq1" => inst.0[0] = denorm(value),
+                "freq2" => inst.0[1] = denorm(value),
+                "freq3" => inst.0[2] = denorm(value),
+                "latency" => inst.1 = denorm(value),
+                _ => (),
+            }
+        }
+
+        params.retain(|p| !is_gz3filt(p) || p[2].as_str() == Some("gain"));
+        for p in params.iter_mut().filter(|p| is_gz3filt(p)) {
+            p[0] = json!("gzbank");
+        }
+
+        for (inst, (_, latency)) in instances.iter() {
+            let win_ms = (latency * 1000.0) / 44100.0;
+            if let Some(win) = NodeId::GzBank(0).inp_param("win") {
+                let win_ms = if denormalized { win_ms } else { win.norm(win_ms) };
+                params.push(json!(["gzbank", inst, "win", win_ms, null]));
+            }
+        }
+    }
+
+    if !v["atoms"].is_array() {
+        v["atoms"] = json!([]);
+    }
+    if let Some(atoms) = v["atoms"].as_array_mut() {
+        for (inst, (freqs, _)) in instances.iter() {
+            atoms.push(json!(["gzbank", inst, "scale", ["i", 3]]));
+            atoms.push(json!(["gzbank", inst, "freqs", ["ms", freqs[0], freqs[1], freqs[2]]]));
+        }
+    }
+
+    if let Some(cc_map) = v["ccmap"].as_array_mut() {
+        cc_map.retain(|c| !is_gz3filt(c) || c[2].as_str() == Some("gain"));
+        for c in cc_map.iter_mut().filter(|c| is_gz3filt(c)) {
+            c[0] = json!("gzbank");
+        }
+    }
+
+    if let Some(states) = v["states"].as_array_mut() {
+        for st in states.iter_mut().filter(|st| is_gz3filt(st)) {
+            st[0] = json!("gzbank");
+        }
+    }
+}
+
 fn inp2idx(node_id: NodeId, v: &Value) -> i16 {
     let inp = v.as_i64().unwrap_or(-2) as i16;
 
@@ -187,6 +280,9 @@ pub struct MatrixRepr {
     pub atoms: Vec<(ParamId, SAtom)>,
     pub patterns: Vec<Option<PatternRepr>>,
     pub properties: Vec<(String, SAtom)>,
+    pub cc_map: Vec<CcMapping>,
+    /// The nodes that are bypassed or muted.
+    pub node_states: Vec<(NodeId, NodeState)>,
     pub version: i64,
 }
 
@@ -198,6 +294,7 @@ pub enum MatrixDeserError {
     Deserialization(String),
     IO(String),
     InvalidAtom(String),
+    UnknownNodeState(String),
     MatrixError(crate::matrix::MatrixError),
 }
 
@@ -256,13 +353,15 @@ fn deserialize_atom(v: &Value) -> Result<SAtom, MatrixDeserError> {
             }
         }
         "ms" => {
-            let mut buf: [f32; 8] = [0.0; 8];
-
-            for i in 0..8 {
-                if let Some(v) = v[i + 1].as_f64() {
-                    buf[i] = v as f32;
-                } else {
-                    return Err(MatrixDeserError::InvalidAtom(v.to_string()));
+            let mut buf: Vec<f32> = vec![];
+
+            if let Some(arr) = v.as_array() {
+                for s in arr.iter().skip(1) {
+                    if let Some(s) = s.as_f64() {
+                        buf.push(s as f32);
+                    } else {
+                        return Err(MatrixDeserError::InvalidAtom(v.to_string()));
+                    }
                 }
             }
 
@@ -274,7 +373,11 @@ fn deserialize_atom(v: &Value) -> Result<SAtom, MatrixDeserError> {
 
 fn serialize_atom(atom: &SAtom) -> Value {
     match atom {
-        SAtom::MicroSample(s) => json!(["ms", s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7],]),
+        SAtom::MicroSample(s) => {
+            let mut v = vec![json!("ms")];
+            v.extend(s.iter().map(|s| json!(s)));
+            Value::Array(v)
+        }
         SAtom::Str(s) => json!(["s", s]),
         SAtom::AudioSample((s, _)) => json!(["as", s]),
         SAtom::Setting(i) => json!(["i", i]),
@@ -289,8 +392,10 @@ impl MatrixRepr {
         let atoms = vec![];
         let patterns = vec![];
         let properties = vec![];
+        let cc_map = vec![];
+        let node_states = vec![];
 
-        Self { cells, params, atoms, patterns, properties, version: 2 }
+        Self { cells, params, atoms, patterns, properties, cc_map, node_states, version: 2 }
     }
 
     pub fn write_to_file(&mut self, filepath: &str) -> std::io::Result<()> {
@@ -325,7 +430,8 @@ impl MatrixRepr {
     }
 
     pub fn deserialize(s: &str) -> Result<MatrixRepr, MatrixDeserError> {
-        let v: Value = serde_json::from_str(s)?;
+        let mut v: Value = serde_json::from_str(s)?;
+        migrate_gz3filt(&mut v);
 
         let mut m = MatrixRepr::empty();
 
@@ -387,6 +493,45 @@ impl MatrixRepr {
             }
         }
 
+        let cc_map = &v["ccmap"];
+        if let Value::Array(cc_map) = cc_map {
+            for v in cc_map.iter() {
+                let node_id = deserialize_node_id(v, 0, 1)?;
+                let param_id = node_id.inp_param(v[2].as_str().unwrap_or(""));
+
+                if let Some(param_id) = param_id {
+                    let channel = v[3].as_i64().unwrap_or(-1);
+                    let channel = if channel < 0 { None } else { Some(channel as u8) };
+                    let cc = v[4].as_i64().unwrap_or(0) as u8;
+
+                    m.cc_map.push(
+                        CcMapping::new(channel, cc, param_id)
+                            .range(
+                                v[5].as_f64().unwrap_or(0.0) as f32,
+                                v[6].as_f64().unwrap_or(1.0) as f32,
+                            )
+                            .invert(v[7].as_bool().unwrap_or(false))
+                            .soft_takeover(v[8].as_bool().unwrap_or(false)),
+                    );
+                } else {
+                    return Err(MatrixDeserError::UnknownParamId(v.to_string()));
+                }
+            }
+        }
+
+        let states = &v["states"];
+        if let Value::Array(states) = states {
+            for v in states.iter() {
+                let node_id = deserialize_node_id(v, 0, 1)?;
+
+                if let Some(state) = NodeState::from_name(v[2].as_str().unwrap_or("")) {
+                    m.node_states.push((node_id, state));
+                } else {
+                    return Err(MatrixDeserError::UnknownNodeState(v.to_string()));
+                }
+            }
+        }
+
         let patterns = &v["patterns"];
         if let Value::Array(patterns) = patterns {
             for p in patterns.iter() {
@@ -450,6 +595,40 @@ impl MatrixRepr {
 
         v["props"] = props;
 
+        // Only written if present, patches without controller
+        // mappings stay the same:
+        if !self.cc_map.is_empty() {
+            let mut cc_map = json!([]);
+            if let Value::Array(cc_map) = &mut cc_map {
+                for m in self.cc_map.iter() {
+                    cc_map.push(json!([
+                        m.param.node_id().name(),
+                        m.param.node_id().instance(),
+                        m.param.name(),
+                        m.channel.map(|c| c as i64).unwrap_or(-1),
+                        m.cc,
+                        m.min,
+                        m.max,
+                        m.invert,
+                        m.soft_takeover,
+                    ]));
+                }
+            }
+
+            v["ccmap"] = cc_map;
+        }
+
+        if !self.node_states.is_empty() {
+            let mut states = json!([]);
+            if let Value::Array(states) = &mut states {
+                for (node_id, state) in self.node_states.iter() {
+                    states.push(json!([node_id.name(), node_id.instance(), state.name()]));
+                }
+            }
+
+            v["states"] = states;
+        }
+
         let mut cells = json!([]);
         if let Value::Array(cells) = &mut cells {
             for cell in self.cells.iter() {
@@ -533,7 +712,7 @@ mod tests {
         let s = mr.serialize();
 
         assert_eq!(s,
-            "{\"VERSION\":2,\"atoms\":[[\"out\",0,\"mono\",[\"i\",0]]],\"cells\":[[\"sin\",2,0,0,[-1,-1,-1],[-1,\"sig\",-1]],[\"out\",0,1,0,[-1,\"ch1\",-1],[-1,-1,-1]]],\"params\":[[\"out\",0,\"ch1\",0.0],[\"out\",0,\"ch2\",0.0],[\"sin\",0,\"det\",0.0],[\"sin\",1,\"det\",0.0],[\"sin\",2,\"det\",0.0],[\"sin\",0,\"freq\",440.0],[\"sin\",1,\"freq\",440.0],[\"sin\",2,\"freq\",220.0],[\"out\",0,\"gain\",1.0]],\"patterns\":[null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null],\"props\":[]}");
+            "{\"VERSION\":2,\"atoms\":[[\"out\",0,\"mono\",[\"i\",0]]],\"cells\":[[\"sin\",2,0,0,[-1,-1,-1],[-1,\"sig\",-1]],[\"out\",0,1,0,[-1,\"ch1\",-1],[-1,-1,-1]]],\"params\":[[\"out\",0,\"ch1\",0.0],[\"out\",0,\"ch10\",0.0],[\"out\",0,\"ch11\",0.0],[\"out\",0,\"ch12\",0.0],[\"out\",0,\"ch13\",0.0],[\"out\",0,\"ch14\",0.0],[\"out\",0,\"ch15\",0.0],[\"out\",0,\"ch16\",0.0],[\"out\",0,\"ch2\",0.0],[\"out\",0,\"ch3\",0.0],[\"out\",0,\"ch4\",0.0],[\"out\",0,\"ch5\",0.0],[\"out\",0,\"ch6\",0.0],[\"out\",0,\"ch7\",0.0],[\"out\",0,\"ch8\",0.0],[\"out\",0,\"ch9\",0.0],[\"sin\",0,\"det\",0.0],[\"sin\",1,\"det\",0.0],[\"sin\",2,\"det\",0.0],[\"sin\",0,\"freq\",440.0],[\"sin\",1,\"freq\",440.0],[\"sin\",2,\"freq\",220.0],[\"out\",0,\"gain\",1.0]],\"patterns\":[null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null,null],\"props\":[]}");
         let mut mr2 = MatrixRepr::deserialize(&s).unwrap();
 
         let s2 = mr2.serialize();
@@ -563,6 +742,11 @@ mod tests {
         let s = serialize_atom(&deserialize_atom(&v).unwrap()).to_string();
         assert_eq!(s, v.to_string());
 
+        let v = serialize_atom(&SAtom::micro(&[110.0, 220.0, 440.0]));
+        assert_eq!(v.to_string(), "[\"ms\",110.0,220.0,440.0]");
+        let s = serialize_atom(&deserialize_atom(&v).unwrap()).to_string();
+        assert_eq!(s, v.to_string());
+
         let v = serialize_atom(&SAtom::audio(
             "lol.wav",
             std::sync::Arc::new(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]),
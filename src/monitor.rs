@@ -42,10 +42,12 @@ const MONITOR_INPUT_LEN_PER_SAMPLE: usize =
 /// Maximum number of monitor buffers to hold in the backend.
 /// Typically there are only 16-32ms of monitor content floating
 /// around, as the monitor processing thread regularily
-/// processes the monitor.
-const MONITOR_BUF_COUNT: usize =
+/// processes the monitor. Longer blocks than [MAX_BLOCK_SIZE]
+/// need less buffers.
+fn monitor_buf_count(max_block_size: usize) -> usize {
     //  2 for safety margin
-    2 * (IMAGINARY_MAX_SAMPLE_RATE / MAX_BLOCK_SIZE);
+    2 * (IMAGINARY_MAX_SAMPLE_RATE / max_block_size.max(MAX_BLOCK_SIZE))
+}
 
 pub struct MonitorBackend {
     rb_mon_prod: Producer<MonitorBufPtr>,
@@ -359,17 +361,19 @@ impl MonitorProcessor {
 
 /// Creates a pair of interconnected MonitorBackend and MonitorProcessor
 /// instances, to be sent to different threads.
-pub fn new_monitor_processor() -> (MonitorBackend, Monitor) {
-    let rb_monitor = RingBuffer::new(MONITOR_BUF_COUNT);
-    let rb_recycle = RingBuffer::new(MONITOR_BUF_COUNT);
+/// The [MonitorBuf] instances hold blocks of up to `max_block_size` samples.
+pub fn new_monitor_processor(max_block_size: usize) -> (MonitorBackend, Monitor) {
+    let buf_count = monitor_buf_count(max_block_size);
+    let rb_monitor = RingBuffer::new(buf_count);
+    let rb_recycle = RingBuffer::new(buf_count);
 
     let (rb_mon_prod, rb_mon_con) = rb_monitor.split();
     let (rb_recycle_prod, rb_recycle_con) = rb_recycle.split();
 
-    let mut unused_monitor_buffers = Vec::with_capacity(MONITOR_BUF_COUNT);
+    let mut unused_monitor_buffers = Vec::with_capacity(buf_count);
 
-    for _ in 0..MONITOR_BUF_COUNT {
-        unused_monitor_buffers.push(MonitorBuf::alloc());
+    for _ in 0..buf_count {
+        unused_monitor_buffers.push(MonitorBuf::alloc(max_block_size));
     }
 
     let backend = MonitorBackend { rb_mon_prod, rb_recycle_con, unused_monitor_buffers };
@@ -385,7 +389,10 @@ pub struct MonitorBuf {
     /// Holds the data of the signals. Each signal has it's
     /// own length. The lengths of the individual elements is
     /// reflected in the `len` attribute.
-    sig_blocks: [f32; MON_SIG_CNT * MAX_BLOCK_SIZE],
+    sig_blocks: Vec<f32>,
+
+    /// The maximum length of each signal data block in `sig_blocks`.
+    block_size: usize,
 
     /// Holds the lengths of the individual signal data blocks in `sig_blocks`.
     len: [usize; MON_SIG_CNT],
@@ -395,7 +402,7 @@ pub struct MonitorBuf {
 }
 
 /// A trait that represents any kind of monitorable sources
-/// that provides at least MAX_BLOCK_SIZE samples.
+/// that provides at least the maximum block size of samples.
 pub trait MonitorSource {
     fn copy_to(&self, len: usize, slice: &mut [f32]);
 }
@@ -407,10 +414,12 @@ impl MonitorSource for &[f32] {
 }
 
 impl MonitorBuf {
-    /// Allocates a monitor buffer that holds up to 6 signals.
-    pub fn alloc() -> MonitorBufPtr {
+    /// Allocates a monitor buffer that holds up to 6 signals
+    /// of up to `block_size` samples each.
+    pub fn alloc(block_size: usize) -> MonitorBufPtr {
         Box::new(Self {
-            sig_blocks: [0.0; MON_SIG_CNT * MAX_BLOCK_SIZE],
+            sig_blocks: vec![0.0; MON_SIG_CNT * block_size],
+            block_size,
             len: [0; MON_SIG_CNT],
             read_idx: [0; MON_SIG_CNT],
         })
@@ -429,7 +438,7 @@ impl MonitorBuf {
         }
 
         self.read_idx[idx] = rd_idx + 1;
-        let sb_idx = idx * MAX_BLOCK_SIZE;
+        let sb_idx = idx * self.block_size;
 
         Some(self.sig_blocks[sb_idx + rd_idx])
     }
@@ -438,7 +447,7 @@ impl MonitorBuf {
     where
         T: MonitorSource,
     {
-        let sb_idx = idx * MAX_BLOCK_SIZE;
+        let sb_idx = idx * self.block_size;
         data.copy_to(len, &mut self.sig_blocks[sb_idx..(sb_idx + len)]);
 
         self.len[idx] = len;
@@ -477,7 +486,7 @@ mod tests {
 
     #[test]
     fn check_monitor_proc() {
-        let (mut backend, mut frontend) = new_monitor_processor();
+        let (mut backend, mut frontend) = new_monitor_processor(MAX_BLOCK_SIZE);
 
         let count1 = (MONITOR_INPUT_LEN_PER_SAMPLE / MAX_BLOCK_SIZE) + 1;
         let count2 = 2 * ((MONITOR_INPUT_LEN_PER_SAMPLE / MAX_BLOCK_SIZE) + 1);
@@ -494,16 +503,19 @@ mod tests {
         assert_eq!(sl[MONITOR_MINMAX_SAMPLES - 2], (-0.7, 0.8));
         assert_eq!(sl[MONITOR_MINMAX_SAMPLES - 3], (-0.9, 0.8));
 
-        assert_eq!(backend.count_unused_mon_bufs(), MONITOR_BUF_COUNT - count1 - count2);
+        assert_eq!(
+            backend.count_unused_mon_bufs(),
+            monitor_buf_count(MAX_BLOCK_SIZE) - count1 - count2
+        );
 
         backend.check_recycle();
 
-        assert_eq!(backend.count_unused_mon_bufs(), MONITOR_BUF_COUNT);
+        assert_eq!(backend.count_unused_mon_bufs(), monitor_buf_count(MAX_BLOCK_SIZE));
     }
 
     #[test]
     fn check_monitor_partial() {
-        let (mut backend, mut frontend) = new_monitor_processor();
+        let (mut backend, mut frontend) = new_monitor_processor(MAX_BLOCK_SIZE);
 
         let count1 = MONITOR_INPUT_LEN_PER_SAMPLE / MAX_BLOCK_SIZE;
 
@@ -524,7 +536,7 @@ mod tests {
 
     #[test]
     fn check_monitor_fragment() {
-        let (mut backend, mut frontend) = new_monitor_processor();
+        let (mut backend, mut frontend) = new_monitor_processor(MAX_BLOCK_SIZE);
 
         let count1 = MONITOR_INPUT_LEN_PER_SAMPLE / MAX_BLOCK_SIZE;
 
@@ -567,7 +579,7 @@ mod tests {
 
     #[test]
     fn check_monitor_wrap_buf() {
-        let (mut backend, mut frontend) = new_monitor_processor();
+        let (mut backend, mut frontend) = new_monitor_processor(MAX_BLOCK_SIZE);
 
         let count1 = (MONITOR_INPUT_LEN_PER_SAMPLE / MAX_BLOCK_SIZE) + 1;
 
@@ -586,6 +598,6 @@ mod tests {
 
         assert_eq!((sl[MONITOR_MINMAX_SAMPLES - 1].1 * 10000.0).floor() as u32, 9937);
 
-        assert_eq!(backend.count_unused_mon_bufs(), MONITOR_BUF_COUNT);
+        assert_eq!(backend.count_unused_mon_bufs(), monitor_buf_count(MAX_BLOCK_SIZE));
     }
 }
@@ -0,0 +1,232 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use super::{DropMsg, NodeAudioContext, NodeOp, NodeProg};
+use crate::dsp::{Node, NodeId, MAX_OUTPUT_CHANNELS};
+
+use ringbuf::Producer;
+
+/// How the previous [NodeProg] executes one of it's ops
+/// during a [ProgCrossfade].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FadeOp {
+    /// The node is not part of the new program, or it writes to the
+    /// audio driver. It's executed with the inputs of the previous program.
+    Process,
+    /// The node was already executed by the new program with the same
+    /// inputs. The `len` outputs are copied from the output vector of
+    /// the new program, starting at `src`.
+    Copy { src: usize, len: usize },
+    /// The inputs of the node were rerouted in the new program. It's
+    /// executed with the inputs of the previous program, on the clone
+    /// of the node in [ProgCrossfade::nodes] at `slot`.
+    Clone { slot: usize },
+}
+
+/// Crossfades the audio driver outputs of the previous [NodeProg] with
+/// the ones of the new program, after a new program was uploaded.
+///
+/// Both programs share the node instances. The previous program executes
+/// the nodes that were removed from the new program and the `Out` node.
+/// The nodes of both programs, whose inputs (or the inputs of any node
+/// before them) were rerouted, are cloned when the crossfade starts and
+/// the previous program executes the clones. The outputs of all other
+/// nodes are copied from the new program, which is executed first.
+/// This fades out the whole previous program, without executing any
+/// node instance twice.
+///
+/// The `FbWr` and `FbRd` nodes are never cloned, because the clone would
+/// move the shared feedback delay along a second time.
+///
+/// All buffers are allocated up front. Only cloning the rerouted nodes
+/// allocates on the audio thread, for nodes that have buffers of their own.
+pub(crate) struct ProgCrossfade {
+    /// The previous program, while a crossfade is running.
+    pub(crate) prev: Option<NodeProg>,
+    /// How each op of the previous program is executed.
+    pub(crate) ops: Vec<FadeOp>,
+    /// The position of the op of each node index in the new program.
+    new_ops: Vec<Option<usize>>,
+    /// The clones of the rerouted nodes, see [FadeOp::Clone].
+    pub(crate) nodes: Vec<Node>,
+    /// The audio driver outputs of the new program in the current block.
+    pub(crate) new_bufs: Vec<Vec<f32>>,
+    /// The audio driver outputs of the previous program in the current block.
+    pub(crate) prev_bufs: Vec<Vec<f32>>,
+    /// The number of faded frames.
+    pos: usize,
+    /// The length of the crossfade in frames.
+    len: usize,
+}
+
+impl ProgCrossfade {
+    /// Creates a crossfade for programs with up to `capacity` nodes,
+    /// that process blocks of up to `max_block_size` frames.
+    pub(crate) fn new(capacity: usize, max_block_size: usize) -> Self {
+        Self {
+            prev: None,
+            ops: Vec::with_capacity(capacity),
+            new_ops: vec![None; capacity],
+            nodes: vec![],
+            new_bufs: vec![vec![0.0; max_block_size]; MAX_OUTPUT_CHANNELS],
+            prev_bufs: vec![vec![0.0; max_block_size]; MAX_OUTPUT_CHANNELS],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_active(&self) -> bool {
+        self.prev.is_some()
+    }
+
+    /// Returns true if the previous program executes the node `index`.
+    pub(crate) fn uses_node(&self, index: usize) -> bool {
+        self.prev.as_ref().map(|prev| prev.prog.iter().any(|op| op.idx == index)).unwrap_or(false)
+    }
+
+    /// Starts a crossfade of `len` frames from the `prev` program to `prog`.
+    /// The buffers of `prev` must be assigned, see [NodeProg::assign_outputs].
+    /// The previous crossfade must be finished, see [ProgCrossfade::finish].
+    pub(crate) fn start(&mut self, prev: NodeProg, prog: &NodeProg, nodes: &[Node], len: usize) {
+        self.new_ops.fill(None);
+        for (pos, op) in prog.prog.iter().enumerate() {
+            self.new_ops[op.idx] = Some(pos);
+        }
+
+        self.ops.clear();
+        for (pos, op) in prev.prog.iter().enumerate() {
+            let node = &nodes[op.idx];
+            let is_out = matches!(node.to_id(0), NodeId::Out(_));
+            let shares_fb = matches!(node, Node::FbWr { .. } | Node::FbRd { .. });
+
+            let fade_op = match self.new_ops[op.idx] {
+                Some(new_pos) if !is_out => {
+                    let new_op = &prog.prog[new_pos];
+
+                    if !shares_fb && self.is_rerouted(&prev.prog[..pos], op, prog, new_op) {
+                        self.nodes.push(node.clone());
+                        FadeOp::Clone { slot: self.nodes.len() - 1 }
+                    } else {
+                        let (start, end) = new_op.out_idxlen;
+                        FadeOp::Copy {
+                            src: start,
+                            len: (end - start).min(op.out_idxlen.1 - op.out_idxlen.0),
+                        }
+                    }
+                }
+                _ => FadeOp::Process,
+            };
+            self.ops.push(fade_op);
+        }
+
+        self.prev = Some(prev);
+        self.pos = 0;
+        self.len = len.max(1);
+    }
+
+    /// Returns true if the node of `op` in the previous program
+    /// gets different inputs than the node of `new_op` in the new
+    /// program `prog`. The `prev_ops` are the ops before `op`, which
+    /// have their [FadeOp] already.
+    fn is_rerouted(
+        &self,
+        prev_ops: &[NodeOp],
+        op: &NodeOp,
+        prog: &NodeProg,
+        new_op: &NodeOp,
+    ) -> bool {
+        if op.state != new_op.state || op.inputs.len() != new_op.inputs.len() {
+            return true;
+        }
+
+        op.inputs.iter().any(|(out_idx, in_idx, _)| {
+            let src_pos = prev_ops
+                .iter()
+                .position(|src| *out_idx >= src.out_idxlen.0 && *out_idx < src.out_idxlen.1);
+
+            // The source output has to be the same as in the new program:
+            let new_out_idx = match src_pos.map(|pos| (&prev_ops[pos], self.ops[pos])) {
+                Some((src, FadeOp::Copy { .. })) => self.new_ops[src.idx]
+                    .map(|new_pos| prog.prog[new_pos].out_idxlen.0 + out_idx - src.out_idxlen.0),
+                _ => None,
+            };
+
+            !new_op
+                .inputs
+                .iter()
+                .any(|(new_out, new_in, _)| Some(*new_out) == new_out_idx && new_in == in_idx)
+        })
+    }
+
+    /// Stops the crossfade and sends the previous program and
+    /// the cloned nodes to the drop thread.
+    pub(crate) fn finish(&mut self, drop_prod: &mut Producer<DropMsg>) {
+        self.ops.clear();
+
+        if let Some(prog) = self.prev.take() {
+            let _ = drop_prod.push(DropMsg::Prog { prog });
+        }
+        for node in self.nodes.drain(..) {
+            let _ = drop_prod.push(DropMsg::Node { node });
+        }
+    }
+
+    /// Clears the output buffers of both programs for the next block.
+    pub(crate) fn clear_buffers(&mut self, nframes: usize) {
+        for buf in self.new_bufs.iter_mut().chain(self.prev_bufs.iter_mut()) {
+            buf[0..nframes].fill(0.0);
+        }
+    }
+
+    /// Writes the crossfaded outputs of both programs to `ctx`.
+    /// Returns true if the crossfade is done.
+    pub(crate) fn mix<T: NodeAudioContext>(&mut self, ctx: &mut T) -> bool {
+        let nframes = ctx.nframes();
+
+        for ch in 0..ctx.output_channels().min(MAX_OUTPUT_CHANNELS) {
+            let (new_buf, prev_buf) = (&self.new_bufs[ch], &self.prev_bufs[ch]);
+
+            for frame in 0..nframes {
+                let gain = ((self.pos + frame) as f32 / self.len as f32).min(1.0);
+                ctx.output(ch, frame, new_buf[frame] * gain + prev_buf[frame] * (1.0 - gain));
+            }
+        }
+
+        self.pos += nframes;
+        self.pos >= self.len
+    }
+}
+
+/// Captures the audio driver outputs of a program in the buffers of
+/// the [ProgCrossfade]. The inputs are read from the audio driver.
+pub(crate) struct CaptureContext<'a, T: NodeAudioContext> {
+    pub(crate) ctx: &'a mut T,
+    pub(crate) bufs: &'a mut [Vec<f32>],
+}
+
+impl<'a, T: NodeAudioContext> NodeAudioContext for CaptureContext<'a, T> {
+    #[inline]
+    fn nframes(&self) -> usize {
+        self.ctx.nframes()
+    }
+
+    #[inline]
+    fn output(&mut self, channel: usize, frame: usize, v: f32) {
+        self.bufs[channel][frame] = v;
+    }
+
+    #[inline]
+    fn input(&mut self, channel: usize, frame: usize) -> f32 {
+        self.ctx.input(channel, frame)
+    }
+
+    fn output_channels(&self) -> usize {
+        self.ctx.output_channels().min(self.bufs.len())
+    }
+
+    fn input_channels(&self) -> usize {
+        self.ctx.input_channels()
+    }
+}
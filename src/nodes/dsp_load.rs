@@ -0,0 +1,271 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use triple_buffer::{Input, Output, TripleBuffer};
+
+use std::time::Instant;
+
+/// The length of audio over which the DSP load is aggregated,
+/// before it is published to the frontend.
+pub const DSP_LOAD_WINDOW_MS: f32 = 250.0;
+
+/// The DSP load of a node or of the whole DSP graph, as returned by
+/// [crate::nodes::NodeConfigurator::node_dsp_load] and
+/// [crate::nodes::NodeConfigurator::dsp_load].
+///
+/// The values are measured over a window of [DSP_LOAD_WINDOW_MS].
+/// The load is the processing time relative to the duration of the
+/// processed audio, at 1.0 the processing does not keep up with
+/// the audio anymore.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DspLoad {
+    /// The average processing time per block in microseconds.
+    pub avg_us: f32,
+    /// The maximum processing time of a single block in microseconds.
+    pub peak_us: f32,
+    /// The average load.
+    pub load: f32,
+    /// The maximum load of a single block.
+    pub peak_load: f32,
+}
+
+/// The aggregated processing times of a window of blocks.
+#[derive(Debug, Clone)]
+pub(crate) struct LoadWindow {
+    blocks: usize,
+    /// The duration of the processed audio in nanoseconds.
+    audio_ns: f64,
+    block_sum_ns: u64,
+    block_peak_ns: u64,
+    block_peak_load: f32,
+    node_sum_ns: Vec<u64>,
+    node_peak_ns: Vec<u64>,
+    node_peak_load: Vec<f32>,
+}
+
+impl LoadWindow {
+    /// A window for `capacity` nodes.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            blocks: 0,
+            audio_ns: 0.0,
+            block_sum_ns: 0,
+            block_peak_ns: 0,
+            block_peak_load: 0.0,
+            node_sum_ns: vec![0; capacity],
+            node_peak_ns: vec![0; capacity],
+            node_peak_load: vec![0.0; capacity],
+        }
+    }
+
+    fn reset(&mut self) {
+        self.blocks = 0;
+        self.audio_ns = 0.0;
+        self.block_sum_ns = 0;
+        self.block_peak_ns = 0;
+        self.block_peak_load = 0.0;
+        self.node_sum_ns.fill(0);
+        self.node_peak_ns.fill(0);
+        self.node_peak_load.fill(0.0);
+    }
+
+    /// Copies the `other` window, without reallocating.
+    /// Both windows must have the same capacity.
+    pub(crate) fn copy_from(&mut self, other: &LoadWindow) {
+        self.blocks = other.blocks;
+        self.audio_ns = other.audio_ns;
+        self.block_sum_ns = other.block_sum_ns;
+        self.block_peak_ns = other.block_peak_ns;
+        self.block_peak_load = other.block_peak_load;
+        self.node_sum_ns.copy_from_slice(&other.node_sum_ns[..]);
+        self.node_peak_ns.copy_from_slice(&other.node_peak_ns[..]);
+        self.node_peak_load.copy_from_slice(&other.node_peak_load[..]);
+    }
+
+    fn load(&self, sum_ns: u64, peak_ns: u64, peak_load: f32) -> DspLoad {
+        if self.blocks == 0 {
+            return DspLoad::default();
+        }
+
+        DspLoad {
+            avg_us: (sum_ns as f64 / (self.blocks as f64 * 1000.0)) as f32,
+            peak_us: peak_ns as f32 / 1000.0,
+            load: (sum_ns as f64 / self.audio_ns) as f32,
+            peak_load,
+        }
+    }
+
+    /// Returns the load of the whole DSP graph.
+    pub(crate) fn total(&self) -> DspLoad {
+        self.load(self.block_sum_ns, self.block_peak_ns, self.block_peak_load)
+    }
+
+    /// Returns the load of the node at `idx` in the executor.
+    pub(crate) fn node(&self, idx: usize) -> DspLoad {
+        if idx >= self.node_sum_ns.len() {
+            return DspLoad::default();
+        }
+
+        self.load(self.node_sum_ns[idx], self.node_peak_ns[idx], self.node_peak_load[idx])
+    }
+
+    /// Adds the load of the node at `idx` to `load`, used
+    /// to sum up the voices of a node.
+    pub(crate) fn add_node(&self, load: &mut DspLoad, idx: usize) {
+        let node = self.node(idx);
+        load.avg_us += node.avg_us;
+        load.peak_us += node.peak_us;
+        load.load += node.load;
+        load.peak_load += node.peak_load;
+    }
+}
+
+/// Measures the processing time of single nodes for the [LoadMeter].
+/// The timer is shared with the worker threads, which each measure
+/// different nodes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NodeTimer {
+    /// Points to [LoadMeter::block_ns], or is null if the meter is disabled.
+    block_ns: *mut u64,
+    /// The length of [LoadMeter::block_ns].
+    len: usize,
+}
+
+unsafe impl Send for NodeTimer {}
+unsafe impl Sync for NodeTimer {}
+
+impl NodeTimer {
+    /// Returns the start time of a node, if the meter is enabled.
+    #[inline]
+    pub(crate) fn start(&self) -> Option<Instant> {
+        if self.block_ns.is_null() {
+            None
+        } else {
+            Some(Instant::now())
+        }
+    }
+
+    /// Adds the time since `start` to the node at `idx`. The caller must
+    /// make sure that no other thread measures the same node at the same time.
+    #[inline]
+    pub(crate) fn end(&self, idx: usize, start: Option<Instant>) {
+        if let Some(start) = start {
+            if idx < self.len {
+                unsafe { *self.block_ns.add(idx) += start.elapsed().as_nanos() as u64 };
+            }
+        }
+    }
+}
+
+/// Measures the processing time of the nodes in the
+/// [crate::nodes::NodeExecutor], if enabled with
+/// [crate::nodes::NodeConfigurator::set_dsp_profiling].
+pub(crate) struct LoadMeter {
+    enabled: bool,
+    window: LoadWindow,
+    /// The processing times of the nodes in the current block.
+    block_ns: Vec<u64>,
+    block_start: Option<Instant>,
+    window_ns: f64,
+    sample_rate: f32,
+    /// Publishes the completed windows to the frontend.
+    input: Input<LoadWindow>,
+}
+
+impl LoadMeter {
+    /// Creates a meter for `capacity` nodes, which publishes the windows
+    /// to the returned [Output] for the frontend.
+    pub(crate) fn new(capacity: usize) -> (Self, Output<LoadWindow>) {
+        let (input, output) = TripleBuffer::new(LoadWindow::new(capacity)).split();
+        (Self::with_input(input, capacity), output)
+    }
+
+    fn with_input(input: Input<LoadWindow>, capacity: usize) -> Self {
+        Self {
+            enabled: false,
+            window: LoadWindow::new(capacity),
+            block_ns: vec![0; capacity],
+            block_start: None,
+            window_ns: DSP_LOAD_WINDOW_MS as f64 * 1000000.0,
+            sample_rate: 44100.0,
+            input,
+        }
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.window.reset();
+        self.block_ns.fill(0);
+        self.block_start = None;
+
+        if !enabled {
+            self.input.input_buffer().copy_from(&self.window);
+            self.input.publish();
+        }
+    }
+
+    pub(crate) fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Takes over the settings of the `prev` meter, when the node
+    /// capacity grows. The current window is started anew.
+    pub(crate) fn take_settings(&mut self, prev: &LoadMeter) {
+        self.enabled = prev.enabled;
+        self.sample_rate = prev.sample_rate;
+    }
+
+    #[inline]
+    pub(crate) fn start_block(&mut self) {
+        if self.enabled {
+            self.block_start = Some(Instant::now());
+        }
+    }
+
+    /// Returns the timer for the nodes of the current block.
+    #[inline]
+    pub(crate) fn node_timer(&mut self) -> NodeTimer {
+        NodeTimer {
+            block_ns: if self.enabled { self.block_ns.as_mut_ptr() } else { std::ptr::null_mut() },
+            len: self.block_ns.len(),
+        }
+    }
+
+    /// Accumulates the times of the processed block of `nframes`
+    /// and publishes the window when it is complete.
+    pub(crate) fn end_block(&mut self, nframes: usize) {
+        let start = if let Some(start) = self.block_start.take() {
+            start
+        } else {
+            return;
+        };
+
+        let block_ns = start.elapsed().as_nanos() as u64;
+        let audio_ns = (nframes as f64 * 1e9) / self.sample_rate as f64;
+        let w = &mut self.window;
+
+        w.blocks += 1;
+        w.audio_ns += audio_ns;
+        w.block_sum_ns += block_ns;
+        w.block_peak_ns = w.block_peak_ns.max(block_ns);
+        w.block_peak_load = w.block_peak_load.max((block_ns as f64 / audio_ns) as f32);
+
+        for (idx, ns) in self.block_ns.iter_mut().enumerate() {
+            if *ns == 0 {
+                continue;
+            }
+
+            w.node_sum_ns[idx] += *ns;
+            w.node_peak_ns[idx] = w.node_peak_ns[idx].max(*ns);
+            w.node_peak_load[idx] = w.node_peak_load[idx].max((*ns as f64 / audio_ns) as f32);
+            *ns = 0;
+        }
+
+        if w.audio_ns >= self.window_ns {
+            self.input.input_buffer().copy_from(w);
+            self.input.publish();
+            w.reset();
+        }
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use ringbuf::Producer;
+
+/// The maximum number of MIDI events that are queued for the DSP thread.
+/// Further events are dropped.
+pub const MAX_MIDI_EVENTS: usize = 1024;
+
+/// A channel message of the MIDI protocol. The channels are numbered
+/// from 0 to 15.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMsg {
+    NoteOn {
+        channel: u8,
+        note: u8,
+        vel: u8,
+    },
+    NoteOff {
+        channel: u8,
+        note: u8,
+        vel: u8,
+    },
+    PolyPressure {
+        channel: u8,
+        note: u8,
+        value: u8,
+    },
+    ControlChange {
+        channel: u8,
+        cc: u8,
+        value: u8,
+    },
+    ChannelPressure {
+        channel: u8,
+        value: u8,
+    },
+    /// The 14 bit pitch bend value, 8192 is the center.
+    PitchBend {
+        channel: u8,
+        value: u16,
+    },
+}
+
+impl MidiMsg {
+    /// Parses a MIDI channel message. A note on with velocity 0 is
+    /// returned as [MidiMsg::NoteOff]. Returns `None` for system messages
+    /// or messages that are too short.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let status = *bytes.first()?;
+        let channel = status & 0x0F;
+        let d1 = bytes.get(1).copied().unwrap_or(0) & 0x7F;
+        let d2 = || Some(*bytes.get(2)? & 0x7F);
+
+        match status & 0xF0 {
+            0x80 => Some(MidiMsg::NoteOff { channel, note: d1, vel: d2()? }),
+            0x90 => {
+                let vel = d2()?;
+                if vel == 0 {
+                    Some(MidiMsg::NoteOff { channel, note: d1, vel })
+                } else {
+                    Some(MidiMsg::NoteOn { channel, note: d1, vel })
+                }
+            }
+            0xA0 => Some(MidiMsg::PolyPressure { channel, note: d1, value: d2()? }),
+            0xB0 => Some(MidiMsg::ControlChange { channel, cc: d1, value: d2()? }),
+            0xD0 if bytes.len() > 1 => Some(MidiMsg::ChannelPressure { channel, value: d1 }),
+            0xE0 => Some(MidiMsg::PitchBend { channel, value: ((d2()? as u16) << 7) | d1 as u16 }),
+            _ => None,
+        }
+    }
+
+    pub fn channel(&self) -> u8 {
+        match self {
+            MidiMsg::NoteOn { channel, .. } => *channel,
+            MidiMsg::NoteOff { channel, .. } => *channel,
+            MidiMsg::PolyPressure { channel, .. } => *channel,
+            MidiMsg::ControlChange { channel, .. } => *channel,
+            MidiMsg::ChannelPressure { channel, .. } => *channel,
+            MidiMsg::PitchBend { channel, .. } => *channel,
+        }
+    }
+}
+
+/// A [MidiMsg] with a time stamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiEvent {
+    /// The frame offset of the event, relative to the start of the next
+    /// block that is processed by the [crate::nodes::NodeExecutor].
+    /// Events past the end of that block are delayed into the following blocks.
+    pub frame: usize,
+    pub msg: MidiMsg,
+}
+
+impl MidiEvent {
+    pub fn new(frame: usize, msg: MidiMsg) -> Self {
+        Self { frame, msg }
+    }
+
+    /// Parses a MIDI channel message, see also [MidiMsg::from_bytes].
+    pub fn from_bytes(frame: usize, bytes: &[u8]) -> Option<Self> {
+        Some(Self { frame, msg: MidiMsg::from_bytes(bytes)? })
+    }
+}
+
+/// Holds the MIDI events for the DSP nodes, sorted by their time stamp.
+/// It is accessible via [crate::nodes::NodeExecContext::midi].
+///
+/// Nodes get the events of the current block with [MidiEventQueue::events].
+pub struct MidiEventQueue {
+    events: Vec<MidiEvent>,
+    block_len: usize,
+}
+
+impl MidiEventQueue {
+    pub fn new() -> Self {
+        Self { events: Vec::with_capacity(MAX_MIDI_EVENTS), block_len: 0 }
+    }
+
+    /// Inserts an event after all events with the same or an earlier
+    /// time stamp. Returns `false` if the queue is full and the
+    /// event was dropped.
+    pub fn push(&mut self, event: MidiEvent) -> bool {
+        if self.events.len() >= MAX_MIDI_EVENTS {
+            return false;
+        }
+
+        let idx = self.events.iter().rposition(|e| e.frame <= event.frame).map(|i| i + 1);
+        self.events.insert(idx.unwrap_or(0), event);
+        true
+    }
+
+    /// Returns the events of the currently processed block,
+    /// ordered by their time stamp.
+    #[inline]
+    pub fn events(&self) -> &[MidiEvent] {
+        &self.events[0..self.block_len]
+    }
+
+    /// Selects the events for the next block of `nframes`.
+    pub(crate) fn start_block(&mut self, nframes: usize) {
+        self.block_len = self.events.iter().take_while(|e| e.frame < nframes).count();
+    }
+
+    /// Removes the events of the processed block and moves the
+    /// remaining events `nframes` closer.
+    pub(crate) fn end_block(&mut self, nframes: usize) {
+        self.events.drain(0..self.block_len);
+        self.block_len = 0;
+
+        for event in self.events.iter_mut() {
+            event.frame -= nframes;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.block_len = 0;
+    }
+}
+
+impl Default for MidiEventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sends MIDI events to the [crate::nodes::NodeExecutor] from another
+/// thread, for instance from a MIDI input driver.
+/// Retrieve it with [crate::nodes::NodeExecutor::take_midi_input].
+///
+/// The events are received at the start of the next processed block.
+pub struct MidiInput {
+    pub(crate) prod: Producer<MidiEvent>,
+}
+
+impl MidiInput {
+    /// Sends the `event`. Returns `false` if the queue is full.
+    pub fn send(&mut self, event: MidiEvent) -> bool {
+        self.prod.push(event).is_ok()
+    }
+}
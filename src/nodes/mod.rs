@@ -2,42 +2,72 @@
 // This file is a part of HexoDSP. Released under GPL-3.0-or-later.
 // See README.md and COPYING for details.
 
+/// The initial node capacity of [new_node_engine]. The capacity grows
+/// when more nodes are created, see [new_node_engine_with_capacity].
 pub const MAX_ALLOCATED_NODES: usize = 256;
-pub const MAX_INPUTS: usize = 32;
 pub const MAX_SMOOTHERS: usize = 36 + 4; // 6 * 6 modulator inputs + 4 UI Knobs
+pub const MAX_TIMED_UPDATES: usize = 512;
 pub const MAX_AVAIL_TRACKERS: usize = 128;
-pub const MAX_FB_DELAYS: usize = 256; // 256 feedback delays, thats roughly 1.2MB RAM
+/// The initial number of feedback delays, it grows with the instances of
+/// the `FbWr` and `FbRd` nodes. 256 feedback delays are roughly 1.2MB RAM.
+pub const MAX_FB_DELAYS: usize = 256;
 pub const FB_DELAY_TIME_US: usize = 3140; // 3.14ms (should be enough for MAX_BLOCK_SIZE)
                                           // This means, until 384000 sample rate the times are accurate.
 pub const MAX_FB_DELAY_SRATE: usize = 48000 * 8;
 pub const MAX_FB_DELAY_SIZE: usize = (MAX_FB_DELAY_SRATE * FB_DELAY_TIME_US) / 1000000;
 
+mod crossfade;
 mod drop_thread;
+mod dsp_load;
 mod feedback_filter;
+mod midi;
 mod node_conf;
 mod node_exec;
 mod node_graph_ordering;
 mod node_prog;
+mod signal_guard;
+mod transport;
 pub mod visual_sampling_filter;
+mod voice;
+mod worker_pool;
 
 pub(crate) use visual_sampling_filter::*;
 
+pub use dsp_load::DspLoad;
+pub use dsp_load::DSP_LOAD_WINDOW_MS;
 pub use feedback_filter::*;
+pub use midi::*;
 pub use node_conf::*;
 pub use node_exec::*;
 pub use node_graph_ordering::NodeGraphOrdering;
 pub use node_prog::*;
+pub(crate) use signal_guard::{flush_denormals, FaultReport};
+pub use signal_guard::{SignalFault, SignalFaultKind, MAX_SIGNAL_FAULTS, SIGNAL_GUARD_FRAMES};
+pub use transport::Transport;
+pub use voice::*;
+pub use worker_pool::WorkerPool;
 
 use crate::dsp::{Node, SAtom};
 pub use crate::monitor::MinMaxMonitorSamples;
 use crate::monitor::MON_SIG_CNT;
 
 #[derive(Debug)]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::large_enum_variant)]
 pub(crate) enum DropMsg {
     Node { node: Node },
     Prog { prog: NodeProg },
     Atom { atom: SAtom },
+    WorkerPool { pool: Box<WorkerPool> },
+    NodeSlots { slots: Box<NodeSlots> },
+}
+
+/// A [GraphMessage] that the [NodeExecutor] rejected, because the node
+/// index is outside of it's node storage. It's sent back to the
+/// [NodeConfigurator], see [NodeConfigurator::pop_error].
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum GraphReject {
+    Node { index: usize },
+    Prog,
 }
 
 /// Messages for updating the NodeExecutor thread.
@@ -46,9 +76,15 @@ pub(crate) enum DropMsg {
 #[derive(Debug)]
 pub enum GraphMessage {
     NewNode {
-        index: u8,
+        index: usize,
         node: Node,
     },
+    /// Hands over the larger storage for the nodes, when the node capacity
+    /// grows. The storage is allocated by the [NodeConfigurator] and
+    /// the previous one is freed on the drop thread.
+    GrowNodes {
+        slots: Box<NodeSlots>,
+    },
     NewProg {
         prog: NodeProg,
         copy_old_out: bool,
@@ -67,6 +103,20 @@ pub enum GraphMessage {
         input_idx: usize,
         value: f32,
     },
+    /// An [GraphMessage::AtomUpdate] that is applied at the `frame` offset
+    /// into the next processed block.
+    AtomUpdateAt {
+        at_idx: usize,
+        value: SAtom,
+        frame: usize,
+    },
+    /// Sets the parameter at the `frame` offset into the next processed
+    /// block. Unlike [GraphMessage::ParamUpdate] the value is not smoothed.
+    ParamUpdateAt {
+        input_idx: usize,
+        value: f32,
+        frame: usize,
+    },
     ModamtUpdate {
         mod_idx: usize,
         modamt: f32,
@@ -75,6 +125,43 @@ pub enum GraphMessage {
     SetMonitor {
         bufs: [usize; MON_SIG_CNT],
     },
+    /// Starts a note on the [VoiceAllocator].
+    NoteOn {
+        note: u8,
+        vel: f32,
+    },
+    /// Releases a note on the [VoiceAllocator].
+    NoteOff {
+        note: u8,
+    },
+    /// Sets the voice count and stealing policy of the [VoiceAllocator].
+    SetVoices {
+        count: usize,
+        steal: VoiceSteal,
+    },
+    /// Enables or disables the measuring of the DSP load.
+    SetDspProfiling {
+        enabled: bool,
+    },
+    /// Sets the [NodeState] of the [NodeOp]s of the node `index`.
+    SetNodeState {
+        index: usize,
+        state: NodeState,
+    },
+    /// Sets the length of the crossfade after a new [NodeProg]
+    /// was uploaded, 0.0 disables it.
+    SetProgCrossfade {
+        ms: f32,
+    },
+    /// Sets the [Transport] of the [NodeExecContext].
+    SetTransport {
+        transport: Transport,
+    },
+    /// Replaces the [WorkerPool] for the parallel execution,
+    /// `None` executes all nodes on the audio thread.
+    SetWorkerPool {
+        pool: Option<Box<WorkerPool>>,
+    },
 }
 
 pub const UNUSED_MONITOR_IDX: usize = 99999;
@@ -82,8 +169,41 @@ pub const UNUSED_MONITOR_IDX: usize = 99999;
 /// Creates a NodeConfigurator and a NodeExecutor which are interconnected
 /// by ring buffers.
 pub fn new_node_engine() -> (NodeConfigurator, NodeExecutor) {
-    let (nc, shared_exec) = NodeConfigurator::new();
-    let ne = NodeExecutor::new(shared_exec);
+    new_node_engine_with_capacity(MAX_ALLOCATED_NODES)
+}
+
+/// Like [new_node_engine], but the nodes process blocks of up to
+/// `max_block_size` frames instead of [crate::dsp::MAX_BLOCK_SIZE].
+/// The buffers of the nodes, the signal monitors and the program
+/// crossfade are allocated for this size. Longer audio periods passed
+/// to [NodeExecutor::process] are split, see also
+/// [NodeExecutor::set_max_block_size].
+///
+/// The feedback delay of the `FbWr` and `FbRd` nodes is 3.14ms, or one
+/// block if the block is longer than that.
+pub fn new_node_engine_with_block_size(max_block_size: usize) -> (NodeConfigurator, NodeExecutor) {
+    new_engine(MAX_ALLOCATED_NODES, max_block_size)
+}
+
+/// Like [new_node_engine], but with space for `node_capacity` nodes
+/// up front, including the voice copies of the voice region.
+///
+/// The capacity grows when more nodes are created, see also
+/// [NodeConfigurator::node_capacity]. The larger storage is allocated by
+/// the [NodeConfigurator] and handed over to the [NodeExecutor], so growing
+/// does not allocate on the audio thread. Patches with many nodes should
+/// still reserve enough capacity here, because all nodes of a patch are sent
+/// at once to the [NodeExecutor] through a queue with twice the initial
+/// capacity (but at least twice [MAX_ALLOCATED_NODES]).
+pub fn new_node_engine_with_capacity(node_capacity: usize) -> (NodeConfigurator, NodeExecutor) {
+    new_engine(node_capacity, crate::dsp::MAX_BLOCK_SIZE)
+}
+
+fn new_engine(node_capacity: usize, max_block_size: usize) -> (NodeConfigurator, NodeExecutor) {
+    let node_capacity = node_capacity.max(1);
+    let max_block_size = max_block_size.max(1);
+    let (nc, shared_exec) = NodeConfigurator::new(node_capacity, max_block_size);
+    let ne = NodeExecutor::new(shared_exec, node_capacity, max_block_size);
 
     // XXX: This is one of the earliest and most consistent points
     //      in runtime to do this kind of initialization:
@@ -3,25 +3,27 @@
 // See README.md and COPYING for details.
 
 use super::{
-    FeedbackFilter, GraphMessage, NodeOp, NodeProg, MAX_ALLOCATED_NODES, MAX_AVAIL_TRACKERS,
-    MAX_INPUTS, UNUSED_MONITOR_IDX,
+    FaultReport, FeedbackFilter, GraphMessage, GraphReject, NodeOp, NodeProg, NodeSlots, NodeState,
+    SignalFault, Transport, VoiceSteal, WorkerPool, MAX_ALLOCATED_NODES, MAX_AVAIL_TRACKERS,
+    MAX_FB_DELAYS, MAX_SIGNAL_FAULTS, MAX_VOICES, UNUSED_MONITOR_IDX,
 };
 use crate::dsp::tracker::{PatternData, Tracker};
 use crate::dsp::{node_factory, Node, NodeId, NodeInfo, ParamId, SAtom};
 use crate::monitor::{new_monitor_processor, MinMaxMonitorSamples, Monitor, MON_SIG_CNT};
 use crate::nodes::drop_thread::DropThread;
+use crate::nodes::dsp_load::{DspLoad, LoadMeter, LoadWindow};
 use crate::util::AtomicFloat;
 use crate::SampleLibrary;
 
-use ringbuf::{Producer, RingBuffer};
-use std::collections::HashMap;
+use ringbuf::{Consumer, Producer, RingBuffer};
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
 use triple_buffer::Output;
 
 /// A NodeInstance describes the input/output/atom ports of a Node
 /// and holds other important house keeping information for the [NodeConfigurator].
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct NodeInstance {
     id: NodeId,
     in_use: bool,
@@ -34,13 +36,21 @@ pub struct NodeInstance {
     at_end: usize,
     mod_start: usize,
     mod_end: usize,
+    /// Start of the parameter inputs, which differs from `in_start`
+    /// only for the copies of the voice region.
+    param_start: usize,
+    /// The voice this node instance plays.
+    voice: u8,
+    /// Whether the node is processed, bypassed or muted.
+    state: NodeState,
     /// A mapping array, to map from input index of the node
     /// to the modulator index. Because not every input has an
     /// associated modulator.
     /// This is used later to send [GraphMessage::ModamtUpdate].
     /// The input index into this array is the index returned from
-    /// routines like [NodeId::inp_param].
-    in2mod_map: [Option<usize>; MAX_INPUTS],
+    /// routines like [NodeId::inp_param]. It only extends up to
+    /// the last input with a modulator.
+    in2mod_map: Vec<Option<usize>>,
 }
 
 impl NodeInstance {
@@ -57,7 +67,10 @@ impl NodeInstance {
             at_end: 0,
             mod_start: 0,
             mod_end: 0,
-            in2mod_map: [None; MAX_INPUTS],
+            param_start: 0,
+            voice: 0,
+            state: NodeState::Active,
+            in2mod_map: vec![],
         }
     }
 
@@ -70,7 +83,7 @@ impl NodeInstance {
 
     pub fn as_op(&self) -> NodeOp {
         NodeOp {
-            idx: self.prog_idx as u8,
+            idx: self.prog_idx,
             out_idxlen: (self.out_start, self.out_end),
             in_idxlen: (self.in_start, self.in_end),
             at_idxlen: (self.at_start, self.at_end),
@@ -78,14 +91,16 @@ impl NodeInstance {
             out_connected: 0x0,
             in_connected: 0x0,
             inputs: vec![],
+            param_idx: self.param_start,
+            voice: self.voice,
+            voice_sums: vec![],
+            context_bound: self.id.is_context_bound(),
+            state: self.state,
         }
     }
 
     pub fn mod_in_local2global(&self, idx: u8) -> Option<usize> {
-        if (idx as usize) > self.in2mod_map.len() {
-            return None;
-        }
-        self.in2mod_map[idx as usize]
+        self.in2mod_map.get(idx as usize).copied().flatten()
     }
 
     pub fn in_local2global(&self, idx: u8) -> Option<usize> {
@@ -120,9 +135,28 @@ impl NodeInstance {
     pub fn set_input(&mut self, s: usize, e: usize) -> &mut Self {
         self.in_start = s;
         self.in_end = e;
+        self.param_start = s;
+        self
+    }
+
+    /// Makes this instance play the `voice`, with the parameters
+    /// of the node instance it is a copy of, starting at `param_start`.
+    /// Call this after [NodeInstance::set_input].
+    pub fn set_voice(&mut self, voice: usize, param_start: usize) -> &mut Self {
+        self.voice = voice as u8;
+        self.param_start = param_start;
+        self
+    }
+
+    pub fn set_state(&mut self, state: NodeState) -> &mut Self {
+        self.state = state;
         self
     }
 
+    pub fn state(&self) -> NodeState {
+        self.state
+    }
+
     pub fn set_mod(&mut self, s: usize, e: usize) -> &mut Self {
         self.mod_start = s;
         self.mod_end = e;
@@ -134,6 +168,9 @@ impl NodeInstance {
     /// and `i` is the absolute index of the modulator that belongs
     /// to this parameter.
     pub fn set_mod_idx(&mut self, idx: usize, i: usize) -> &mut Self {
+        if idx >= self.in2mod_map.len() {
+            self.in2mod_map.resize(idx + 1, None);
+        }
         self.in2mod_map[idx] = Some(i);
         self
     }
@@ -189,6 +226,9 @@ pub struct NodeConfigurator {
 
     /// Error messages:
     errors: Vec<String>,
+    /// The graph updates that did not fit into the graph update queue.
+    /// They are sent again, before the next ones.
+    pending_graph_updates: VecDeque<GraphMessage>,
 
     /// Contains (automateable) parameters
     params: std::collections::HashMap<ParamId, NodeInputParam>,
@@ -196,6 +236,8 @@ pub struct NodeConfigurator {
     param_values: std::collections::HashMap<ParamId, f32>,
     /// Stores the modulation amount of a parameter
     param_modamt: std::collections::HashMap<ParamId, Option<f32>>,
+    /// Stores the nodes that are not [NodeState::Active]
+    node_states: HashMap<NodeId, NodeState>,
     /// Contains non automateable atom data for the nodes
     atoms: std::collections::HashMap<ParamId, NodeInputAtom>,
     /// Stores the most recently set atoms
@@ -208,6 +250,38 @@ pub struct NodeConfigurator {
     /// Holds the channel to the backend that sends output port feedback.
     /// This is queried by [NodeConfigurator::update_output_feedback].
     output_fb_cons: Option<Output<Vec<f32>>>,
+
+    /// Holds a copy of the most recent DSP load measurements.
+    /// Update this by calling [NodeConfigurator::update_dsp_load].
+    dsp_load: LoadWindow,
+
+    /// The maximum number of frames the nodes process at once,
+    /// which is the size of the buffers of the [NodeProg].
+    max_block_size: usize,
+    /// The number of feedback delays of the [crate::nodes::NodeExecutor],
+    /// one for each instance of the `FbWr` and `FbRd` nodes.
+    feedback_capacity: usize,
+
+    /// The nodes that are duplicated for each voice,
+    /// see [NodeConfigurator::set_voice_region].
+    voice_region: Vec<NodeId>,
+    /// The number of voices of the voice region.
+    voice_count: usize,
+    /// The voice stealing policy.
+    voice_steal: VoiceSteal,
+    /// The node that is currently allocated in the executor for each
+    /// node index. Only the indices of the voice copies are used, which
+    /// are allocated from the end.
+    voice_slots: Vec<NodeId>,
+    /// The node instances of the voice copies (voice 1 and up)
+    /// of each node in the voice region.
+    voice_copies: HashMap<NodeId, Vec<NodeInstance>>,
+    /// The start of the outputs that hold the sum of all voices of
+    /// each node in the voice region.
+    voice_bus: HashMap<NodeId, usize>,
+    /// Maps the modulator index of a node in the voice region to the
+    /// modulator indices of it's voice copies.
+    voice_mods: HashMap<usize, Vec<usize>>,
 }
 
 pub(crate) struct SharedNodeConf {
@@ -220,65 +294,97 @@ pub(crate) struct SharedNodeConf {
     /// Handles deallocation of dead nodes from the backend.
     #[allow(dead_code)]
     pub(crate) drop_thread: DropThread,
+    /// For receiving the DSP load measurements from the backend thread.
+    pub(crate) dsp_load_cons: Output<LoadWindow>,
+    /// For receiving the signal faults of the nodes from the backend thread.
+    pub(crate) signal_fault_cons: Consumer<FaultReport>,
+    /// For receiving the rejected graph updates from the backend thread.
+    pub(crate) graph_reject_cons: Consumer<GraphReject>,
 }
 
 use super::node_exec::SharedNodeExec;
 
 impl SharedNodeConf {
-    pub(crate) fn new() -> (Self, SharedNodeExec) {
-        let rb_graph = RingBuffer::new(MAX_ALLOCATED_NODES * 2);
-        let rb_drop = RingBuffer::new(MAX_ALLOCATED_NODES * 2);
+    pub(crate) fn new(capacity: usize, max_block_size: usize) -> (Self, SharedNodeExec) {
+        let rb_graph = RingBuffer::new(capacity.max(MAX_ALLOCATED_NODES) * 2);
+        let rb_drop = RingBuffer::new(capacity.max(MAX_ALLOCATED_NODES) * 2);
+        let rb_reject = RingBuffer::new(capacity.max(MAX_ALLOCATED_NODES) * 2);
 
         let (rb_graph_prod, rb_graph_con) = rb_graph.split();
         let (rb_drop_prod, rb_drop_con) = rb_drop.split();
+        let (rb_reject_prod, rb_reject_con) = rb_reject.split();
 
         let drop_thread = DropThread::new(rb_drop_con);
 
-        let (monitor_backend, monitor) = new_monitor_processor();
+        let (monitor_backend, monitor) = new_monitor_processor(max_block_size);
+
+        let (load_meter, dsp_load_cons) = LoadMeter::new(capacity);
+
+        let (signal_fault_prod, signal_fault_cons) = RingBuffer::new(MAX_SIGNAL_FAULTS).split();
 
         let mut node_ctx_values = Vec::new();
-        node_ctx_values.resize_with(2 * MAX_ALLOCATED_NODES, || Arc::new(AtomicFloat::new(0.0)));
+        node_ctx_values.resize_with(2 * capacity, || Arc::new(AtomicFloat::new(0.0)));
 
-        let mut exec_node_ctx_vals = Vec::new();
-        for ctx_val in node_ctx_values.iter() {
-            exec_node_ctx_vals.push(ctx_val.clone());
-        }
+        let exec_node_ctx_vals = node_ctx_values.clone();
 
         (
-            Self { node_ctx_values, graph_update_prod: rb_graph_prod, monitor, drop_thread },
+            Self {
+                node_ctx_values,
+                graph_update_prod: rb_graph_prod,
+                monitor,
+                drop_thread,
+                dsp_load_cons,
+                signal_fault_cons,
+                graph_reject_cons: rb_reject_con,
+            },
             SharedNodeExec {
                 node_ctx_values: exec_node_ctx_vals,
                 graph_update_con: rb_graph_con,
                 graph_drop_prod: rb_drop_prod,
                 monitor_backend,
+                load_meter,
+                signal_fault_prod,
+                graph_reject_prod: rb_reject_prod,
             },
         )
     }
 }
 
 impl NodeConfigurator {
-    pub(crate) fn new() -> (Self, SharedNodeExec) {
+    pub(crate) fn new(capacity: usize, max_block_size: usize) -> (Self, SharedNodeExec) {
         let mut nodes = Vec::new();
-        nodes.resize_with(MAX_ALLOCATED_NODES, || (NodeInfo::from_node_id(NodeId::Nop), None));
+        nodes.resize_with(capacity, || (NodeInfo::from_node_id(NodeId::Nop), None));
 
-        let (shared, shared_exec) = SharedNodeConf::new();
+        let (shared, shared_exec) = SharedNodeConf::new(capacity, max_block_size);
 
         (
             NodeConfigurator {
                 nodes,
                 shared,
                 errors: vec![],
+                pending_graph_updates: VecDeque::new(),
                 sample_lib: SampleLibrary::new(),
                 feedback_filter: FeedbackFilter::new(),
                 output_fb_values: vec![],
                 output_fb_cons: None,
+                dsp_load: LoadWindow::new(capacity),
+                max_block_size,
+                feedback_capacity: MAX_FB_DELAYS,
                 params: std::collections::HashMap::new(),
                 param_values: std::collections::HashMap::new(),
                 param_modamt: std::collections::HashMap::new(),
+                node_states: HashMap::new(),
                 atoms: std::collections::HashMap::new(),
                 atom_values: std::collections::HashMap::new(),
                 node2idx: HashMap::new(),
                 trackers: vec![Tracker::new(); MAX_AVAIL_TRACKERS],
+                voice_region: vec![],
+                voice_count: 1,
+                voice_steal: VoiceSteal::Oldest,
+                voice_slots: vec![NodeId::Nop; capacity],
+                voice_copies: HashMap::new(),
+                voice_bus: HashMap::new(),
+                voice_mods: HashMap::new(),
             },
             shared_exec,
         )
@@ -309,18 +415,96 @@ impl NodeConfigurator {
     pub fn for_each<F: FnMut(&NodeInfo, NodeId, usize)>(&self, mut f: F) {
         for (i, n) in self.nodes.iter().enumerate() {
             let nid = n.0.to_id();
+            // The slots of the voice copies are empty:
             if NodeId::Nop == nid {
-                break;
+                continue;
             }
 
             f(&n.0, nid, i);
         }
     }
 
+    /// Returns the next error message, for instance about a sample that
+    /// could not be loaded, a [SignalFault] or a node or program that the
+    /// [crate::nodes::NodeExecutor] rejected.
     pub fn pop_error(&mut self) -> Option<String> {
+        while let Some(reject) = self.shared.graph_reject_cons.pop() {
+            match reject {
+                GraphReject::Node { index } => {
+                    let node_id = self.nodes.get(index).map(|n| n.0.to_id()).unwrap_or(NodeId::Nop);
+                    self.errors.push(format!(
+                        "Graph Error\n\
+                        Node {} was rejected by the DSP thread, it's node storage is too small.",
+                        node_id
+                    ));
+                }
+                GraphReject::Prog => {
+                    self.errors.push(
+                        "Graph Error\n\
+                        The program was rejected by the DSP thread, it's node storage is too small."
+                            .to_string(),
+                    );
+                }
+            }
+        }
+
         self.errors.pop()
     }
 
+    /// Sends a [GraphMessage] to the [crate::nodes::NodeExecutor]. If the
+    /// graph update queue is full, the message is kept and sent again
+    /// before the next one. So no update is lost or overtaken by a later
+    /// one, for instance a parameter update can't reach the executor
+    /// before the program it refers to.
+    fn push_graph_update(&mut self, msg: GraphMessage) {
+        self.pending_graph_updates.push_back(msg);
+        self.flush_graph_updates();
+    }
+
+    /// Sends the graph updates that did not fit into the graph update
+    /// queue before.
+    fn flush_graph_updates(&mut self) {
+        while let Some(msg) = self.pending_graph_updates.pop_front() {
+            if let Err(msg) = self.shared.graph_update_prod.push(msg) {
+                self.pending_graph_updates.push_front(msg);
+                break;
+            }
+        }
+    }
+
+    /// Receives the [SignalFault]s that the [crate::nodes::NodeExecutor]
+    /// detected since the last call. Call this regularly, for instance
+    /// every UI frame. An error message for each fault is also
+    /// available via [NodeConfigurator::pop_error].
+    ///
+    /// The executor checks the outputs of each node after it was executed
+    /// for NaN, infinite and denormal samples. An output with NaN or
+    /// infinite samples is set to 0.0 and the node is reset. Denormal
+    /// samples are flushed to 0.0 without resetting the node. Each node is
+    /// only reported once, until the next program is uploaded.
+    pub fn update_signal_faults(&mut self) -> Vec<SignalFault> {
+        let mut faults = vec![];
+
+        while let Some(report) = self.shared.signal_fault_cons.pop() {
+            let node_id = match self.voice_slots.get(report.node_idx) {
+                Some(node_id) if *node_id != NodeId::Nop => *node_id,
+                _ => self.nodes.get(report.node_idx).map(|n| n.0.to_id()).unwrap_or(NodeId::Nop),
+            };
+
+            self.errors.push(format!(
+                "Signal Error\n\
+                Node {} produced {} samples at output '{}' and was reset.",
+                node_id,
+                report.kind,
+                node_id.out_name_by_idx(report.output).unwrap_or("?")
+            ));
+
+            faults.push(SignalFault { node_id, output: report.output, kind: report.kind });
+        }
+
+        faults
+    }
+
     pub fn unique_index_for(&self, ni: &NodeId) -> Option<usize> {
         self.node2idx.get(&ni).copied()
     }
@@ -371,10 +555,15 @@ impl NodeConfigurator {
                 self.param_modamt.insert(param, v);
 
                 if let Some(mod_idx) = mod_idx {
-                    let _ = self
-                        .shared
-                        .graph_update_prod
-                        .push(GraphMessage::ModamtUpdate { mod_idx, modamt });
+                    self.push_graph_update(GraphMessage::ModamtUpdate { mod_idx, modamt });
+
+                    let voice_mods = self.voice_mods.get(&mod_idx).cloned();
+                    for mod_idx in voice_mods.iter().flatten() {
+                        self.push_graph_update(GraphMessage::ModamtUpdate {
+                            mod_idx: *mod_idx,
+                            modamt,
+                        });
+                    }
                 }
 
                 false
@@ -400,6 +589,23 @@ impl NodeConfigurator {
     /// before calling this. If no graph or the corresponding parameter is not active yet,
     /// then the value will be remembered until [NodeConfigurator::rebuild_node_ports] is called.
     pub fn set_param(&mut self, param: ParamId, at: SAtom) {
+        self.update_param(param, at, None);
+    }
+
+    /// Like [NodeConfigurator::set_param], but the new value is applied
+    /// exactly at the `frame` offset into the next block that is processed
+    /// by the [crate::nodes::NodeExecutor] after
+    /// [crate::nodes::NodeExecutor::process_graph_updates].
+    /// Offsets past the end of that block are delayed into the
+    /// following blocks.
+    ///
+    /// This is meant for automation by a host or sequencer. Parameters are
+    /// not smoothed, they jump to the new value at the given frame.
+    pub fn set_param_at(&mut self, param: ParamId, at: SAtom, frame: usize) {
+        self.update_param(param, at, Some(frame));
+    }
+
+    fn update_param(&mut self, param: ParamId, at: SAtom, frame: Option<usize>) {
         if param.is_atom() {
             let at = if let SAtom::AudioSample((path, None)) = at.clone() {
                 if !path.is_empty() {
@@ -427,10 +633,12 @@ impl NodeConfigurator {
                 nparam.value = at.clone();
 
                 let at_idx = nparam.at_idx;
-                let _ = self
-                    .shared
-                    .graph_update_prod
-                    .push(GraphMessage::AtomUpdate { at_idx, value: at });
+                let msg = if let Some(frame) = frame {
+                    GraphMessage::AtomUpdateAt { at_idx, value: at, frame }
+                } else {
+                    GraphMessage::AtomUpdate { at_idx, value: at }
+                };
+                self.push_graph_update(msg);
             }
         } else {
             self.param_values.insert(param, at.f());
@@ -440,10 +648,12 @@ impl NodeConfigurator {
                 nparam.value = value;
 
                 let input_idx = nparam.input_idx;
-                let _ = self
-                    .shared
-                    .graph_update_prod
-                    .push(GraphMessage::ParamUpdate { input_idx, value });
+                let msg = if let Some(frame) = frame {
+                    GraphMessage::ParamUpdateAt { input_idx, value, frame }
+                } else {
+                    GraphMessage::ParamUpdate { input_idx, value }
+                };
+                self.push_graph_update(msg);
             }
         }
     }
@@ -634,7 +844,7 @@ impl NodeConfigurator {
                 i += 1;
             }
 
-            let _ = self.shared.graph_update_prod.push(GraphMessage::SetMonitor { bufs });
+            self.push_graph_update(GraphMessage::SetMonitor { bufs });
         }
     }
 
@@ -660,16 +870,124 @@ impl NodeConfigurator {
         self.params.clear();
         self.param_values.clear();
         self.param_modamt.clear();
+        self.node_states.clear();
         self.atoms.clear();
         self.atom_values.clear();
+        self.voice_region.clear();
+        self.voice_count = 1;
+        self.voice_steal = VoiceSteal::Oldest;
+        self.voice_slots.fill(NodeId::Nop);
+        self.voice_copies.clear();
+        self.voice_bus.clear();
+        self.voice_mods.clear();
+
+        self.push_graph_update(GraphMessage::Clear { prog: NodeProg::empty() });
+    }
 
-        let _ = self.shared.graph_update_prod.push(GraphMessage::Clear { prog: NodeProg::empty() });
+    /// Marks a region of the graph that is played polyphonically: The
+    /// `nodes` are duplicated for each of the `voices` (up to [MAX_VOICES]).
+    /// A value of 1 or less for `voices` disables the voice region.
+    ///
+    /// Each copy of a node plays a different voice, which is only relevant
+    /// for the `Voice` node, which provides the pitch, gate and velocity
+    /// of the notes played with [NodeConfigurator::note_on].
+    ///
+    /// The copies share the parameters and atoms of the original node.
+    /// Connections between nodes of the region are made within each voice.
+    /// Inputs from nodes outside of the region are fed to all voices, and
+    /// outputs to nodes outside the region carry the sum of all voices.
+    ///
+    /// The `FbWr`/`FbRd` and `TSeq` nodes don't work inside the voice region,
+    /// as their copies would share the same feedback buffer or tracker.
+    /// The voice region is not stored in a [crate::matrix_repr::MatrixRepr]
+    /// and reset by [NodeConfigurator::delete_nodes].
+    ///
+    /// The change takes effect with the next [NodeConfigurator::rebuild_node_ports].
+    pub fn set_voice_region(&mut self, nodes: &[NodeId], voices: usize) {
+        self.voice_region = nodes.to_vec();
+        self.voice_count = voices.clamp(1, MAX_VOICES);
+
+        self.push_graph_update(GraphMessage::SetVoices {
+            count: self.voice_count,
+            steal: self.voice_steal,
+        });
     }
 
-    pub fn create_node(&mut self, ni: NodeId) -> Option<(&NodeInfo, u8)> {
-        if let Some((mut node, info)) = node_factory(ni) {
-            let mut index: Option<usize> = None;
+    /// Returns the nodes of the voice region and the number of voices.
+    pub fn voice_region(&self) -> (&[NodeId], usize) {
+        (&self.voice_region[..], self.voice_count)
+    }
+
+    /// Sets the policy which voice is taken for a new note
+    /// if all voices are playing.
+    pub fn set_voice_steal(&mut self, steal: VoiceSteal) {
+        self.voice_steal = steal;
+
+        self.push_graph_update(GraphMessage::SetVoices {
+            count: self.voice_count,
+            steal: self.voice_steal,
+        });
+    }
+
+    /// Starts playing the MIDI `note` with the velocity `vel` (0..1)
+    /// on one of the voices.
+    pub fn note_on(&mut self, note: u8, vel: f32) {
+        self.push_graph_update(GraphMessage::NoteOn { note, vel });
+    }
+
+    /// Releases the voice that plays the MIDI `note`.
+    pub fn note_off(&mut self, note: u8) {
+        self.push_graph_update(GraphMessage::NoteOff { note });
+    }
+
+    /// Sets the [NodeState] of the node `node_id` and it's voice copies.
+    /// This is sent to the [crate::nodes::NodeExecutor] right away,
+    /// the [NodeProg] does not need to be rebuilt.
+    pub fn set_node_state(&mut self, node_id: NodeId, state: NodeState) {
+        if state == NodeState::Active {
+            self.node_states.remove(&node_id);
+        } else {
+            self.node_states.insert(node_id, state);
+        }
+
+        let mut indices = vec![];
+        if let Some((_, Some(node_instance))) = self.node_by_id_mut(&node_id) {
+            node_instance.set_state(state);
+            indices.push(node_instance.prog_idx);
+        }
+
+        for copy in self.voice_copies.get_mut(&node_id).into_iter().flatten() {
+            copy.set_state(state);
+            indices.push(copy.prog_idx);
+        }
 
+        for index in indices {
+            self.push_graph_update(GraphMessage::SetNodeState { index, state });
+        }
+    }
+
+    /// Returns the [NodeState] of the node `node_id`.
+    pub fn node_state(&self, node_id: &NodeId) -> NodeState {
+        self.node_states.get(node_id).copied().unwrap_or_default()
+    }
+
+    /// Returns all nodes that are not [NodeState::Active].
+    pub fn node_states(&self) -> Vec<(NodeId, NodeState)> {
+        let mut states: Vec<(NodeId, NodeState)> =
+            self.node_states.iter().map(|(id, state)| (*id, *state)).collect();
+        states.sort();
+        states
+    }
+
+    /// Sets the [Transport] from the frontend, for instance to start a
+    /// free running transport if there is no host transport. Hosts should
+    /// update it with [crate::nodes::NodeExecutor::set_transport] instead.
+    pub fn set_transport(&mut self, transport: Transport) {
+        self.push_graph_update(GraphMessage::SetTransport { transport });
+    }
+
+    pub fn create_node(&mut self, ni: NodeId) -> Option<(&NodeInfo, usize)> {
+        if let Some((mut node, info)) = node_factory(ni) {
             if let Node::TSeq { node } = &mut node {
                 let tracker_idx = ni.instance();
                 if let Some(trk) = self.trackers.get_mut(tracker_idx) {
@@ -677,47 +995,103 @@ impl NodeConfigurator {
                 }
             }
 
-            for i in 0..self.nodes.len() {
-                if let NodeId::Nop = self.nodes[i].0.to_id() {
-                    index = Some(i);
-                    break;
-                } else if ni == self.nodes[i].0.to_id() {
-                    return Some((&self.nodes[i].0, i as u8));
+            if let Some(index) = self.unique_index_for(&ni) {
+                if self.nodes[index].0.to_id() == ni {
+                    return Some((&self.nodes[index].0, index));
                 }
             }
 
-            if let Some(index) = index {
-                self.node2idx.insert(ni, index);
-
-                self.nodes[index] = (info, None);
+            // The slots of the voice copies are still used by the
+            // current program:
+            let index = (0..self.nodes.len()).find(|i| {
+                self.nodes[*i].0.to_id() == NodeId::Nop && self.voice_slots[*i] == NodeId::Nop
+            });
 
-                let _ = self
-                    .shared
-                    .graph_update_prod
-                    .push(GraphMessage::NewNode { index: index as u8, node });
-
-                Some((&self.nodes[index].0, index as u8))
+            let index = if let Some(index) = index {
+                index
             } else {
                 let index = self.nodes.len();
-                self.node2idx.insert(ni, index);
+                self.reserve_node_capacity(index * 2);
+                index
+            };
+
+            if let NodeId::FbWr(instance) | NodeId::FbRd(instance) = ni {
+                self.reserve_feedback_capacity(instance as usize);
+            }
 
-                self.nodes.resize_with((self.nodes.len() + 1) * 2, || {
-                    (NodeInfo::from_node_id(NodeId::Nop), None)
-                });
-                self.nodes[index] = (info, None);
+            self.node2idx.insert(ni, index);
+            self.nodes[index] = (info, None);
 
-                let _ = self
-                    .shared
-                    .graph_update_prod
-                    .push(GraphMessage::NewNode { index: index as u8, node });
+            self.push_graph_update(GraphMessage::NewNode { index, node });
 
-                Some((&self.nodes[index].0, index as u8))
-            }
+            Some((&self.nodes[index].0, index))
         } else {
             None
         }
     }
 
+    /// Returns the number of nodes the [crate::nodes::NodeExecutor]
+    /// has space for, including the voice copies of the voice region.
+    /// See also [NodeConfigurator::reserve_node_capacity].
+    pub fn node_capacity(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Grows the node capacity to at least `capacity` nodes. This
+    /// happens automatically in [NodeConfigurator::create_node] and
+    /// [NodeConfigurator::rebuild_node_ports] if necessary.
+    ///
+    /// The larger storage of the [crate::nodes::NodeExecutor] is
+    /// allocated here and handed over with [GraphMessage::GrowNodes],
+    /// the previous storage is freed on the drop thread. A running
+    /// crossfade (see [NodeConfigurator::set_prog_crossfade]) is cut short
+    /// and the current DSP load measurement window is discarded.
+    pub fn reserve_node_capacity(&mut self, capacity: usize) {
+        if capacity <= self.nodes.len() {
+            return;
+        }
+
+        self.nodes.resize_with(capacity, || (NodeInfo::from_node_id(NodeId::Nop), None));
+        self.voice_slots.resize(capacity, NodeId::Nop);
+        self.send_node_slots();
+    }
+
+    /// Grows the number of feedback delays, so that there is one
+    /// for the `FbWr` and `FbRd` nodes with the given `instance`.
+    /// They are handed over together with the node storage,
+    /// see [NodeConfigurator::reserve_node_capacity].
+    fn reserve_feedback_capacity(&mut self, instance: usize) {
+        if instance < self.feedback_capacity {
+            return;
+        }
+
+        self.feedback_capacity = (instance + 1).max(self.feedback_capacity * 2);
+        self.send_node_slots();
+    }
+
+    /// Allocates the storage of the [crate::nodes::NodeExecutor] for the
+    /// current node and feedback capacity and hands it over.
+    fn send_node_slots(&mut self) {
+        let capacity = self.nodes.len();
+
+        // The frontend keeps the values of the previous storage,
+        // so these are not freed by the drop thread:
+        self.shared.node_ctx_values.resize_with(2 * capacity, || Arc::new(AtomicFloat::new(0.0)));
+
+        let (load_meter, dsp_load_cons) = LoadMeter::new(capacity);
+        self.shared.dsp_load_cons = dsp_load_cons;
+        self.dsp_load = LoadWindow::new(capacity);
+
+        let slots = NodeSlots::new(
+            capacity,
+            self.feedback_capacity,
+            self.max_block_size,
+            self.shared.node_ctx_values.clone(),
+            load_meter,
+        );
+        self.push_graph_update(GraphMessage::GrowNodes { slots: Box::new(slots) });
+    }
+
     /// Returns the first instance of the given [NodeId] (starting with the
     /// instance of the [NodeId]) that has not been used.
     ///
@@ -777,15 +1151,17 @@ impl NodeConfigurator {
             // - hold the mod start index of this node.
             let mod_idx = mod_len;
 
+            // The slots of the voice copies are empty:
             if id == NodeId::Nop {
-                break;
+                continue;
             }
 
             let mut ni = NodeInstance::new(id);
             ni.set_index(i)
                 .set_output(out_idx, out_len)
                 .set_input(in_idx, in_len)
-                .set_atom(at_idx, at_len);
+                .set_atom(at_idx, at_len)
+                .set_state(self.node_states.get(&id).copied().unwrap_or_default());
 
             // - save offset and length of each node's
             //   allocation in the output vector.
@@ -849,7 +1225,131 @@ impl NodeConfigurator {
             }
         }
 
-        NodeProg::new(out_len, in_len, at_len, mod_len)
+        self.rebuild_voice_ports(&mut out_len, &mut in_len, &mut mod_len);
+
+        NodeProg::new(out_len, in_len, at_len, mod_len, self.max_block_size)
+    }
+
+    /// Allocates the node instances of the voice copies after the regular
+    /// nodes. The copies are executed in the free node indices from the end
+    /// of the executor's node vector, which grows if there are not enough.
+    /// They get their own inputs, outputs and modulators, but share the
+    /// parameters and atoms of the original node.
+    fn rebuild_voice_ports(
+        &mut self,
+        out_len: &mut usize,
+        in_len: &mut usize,
+        mod_len: &mut usize,
+    ) {
+        self.voice_copies.clear();
+        self.voice_bus.clear();
+        self.voice_mods.clear();
+
+        // The slots that are not used by the new voice copies
+        // are free again for regular nodes:
+        let prev_slots =
+            std::mem::replace(&mut self.voice_slots, vec![NodeId::Nop; self.nodes.len()]);
+
+        if self.voice_count <= 1 {
+            return;
+        }
+
+        let region_nodes = self
+            .voice_region
+            .iter()
+            .filter(|node_id| matches!(self.node_by_id(node_id), Some((_, Some(_)))))
+            .count();
+        let copy_count = region_nodes * (self.voice_count - 1);
+        let node_count = self.nodes.iter().filter(|n| n.0.to_id() != NodeId::Nop).count();
+
+        // Keep one slot free for the next created node:
+        let capacity = node_count + copy_count + 1;
+        if capacity > self.nodes.len() {
+            self.reserve_node_capacity(capacity.max(self.nodes.len() * 2));
+        }
+
+        let mut free_slots = (0..self.nodes.len())
+            .rev()
+            .filter(|i| self.nodes[*i].0.to_id() == NodeId::Nop)
+            .collect::<Vec<usize>>()
+            .into_iter();
+
+        for node_id in self.voice_region.clone().iter() {
+            let tmpl = if let Some((_, Some(tmpl))) = self.node_by_id(node_id) {
+                tmpl.clone()
+            } else {
+                continue;
+            };
+
+            let out_count = tmpl.out_end - tmpl.out_start;
+            let in_count = tmpl.in_end - tmpl.in_start;
+
+            let mut copies = vec![];
+            for voice in 1..self.voice_count {
+                let slot = if let Some(slot) = free_slots.next() { slot } else { return };
+
+                // Keep the state of the copies that already exist:
+                if prev_slots.get(slot) != Some(node_id) {
+                    if let Some((node, _info)) = node_factory(*node_id) {
+                        self.push_graph_update(GraphMessage::NewNode { index: slot, node });
+                    }
+                }
+                self.voice_slots[slot] = *node_id;
+
+                let mut ni = NodeInstance::new(*node_id);
+                ni.set_index(slot)
+                    .set_output(*out_len, *out_len + out_count)
+                    .set_input(*in_len, *in_len + in_count)
+                    .set_atom(tmpl.at_start, tmpl.at_end)
+                    .set_voice(voice, tmpl.in_start)
+                    .set_state(tmpl.state);
+                *out_len += out_count;
+                *in_len += in_count;
+
+                let mod_start = *mod_len;
+                for (input_idx, tmpl_mod_idx) in tmpl.in2mod_map.iter().enumerate() {
+                    if let Some(tmpl_mod_idx) = tmpl_mod_idx {
+                        ni.set_mod_idx(input_idx, *mod_len);
+                        self.voice_mods.entry(*tmpl_mod_idx).or_default().push(*mod_len);
+                        *mod_len += 1;
+                    }
+                }
+                ni.set_mod(mod_start, *mod_len);
+
+                copies.push(ni);
+            }
+
+            self.voice_bus.insert(*node_id, *out_len);
+            *out_len += out_count;
+
+            self.voice_copies.insert(*node_id, copies);
+        }
+    }
+
+    /// Returns the output index that holds the sum of all voices of the
+    /// output `out` of the voice region node `node_id`. The summing
+    /// is done after the last voice copy of that node was executed.
+    fn voice_bus_output(&self, prog: &mut NodeProg, node_id: &NodeId, out: u8) -> Option<usize> {
+        let tmpl = if let Some((_, Some(tmpl))) = self.node_by_id(node_id) {
+            tmpl
+        } else {
+            return None;
+        };
+        let copies = self.voice_copies.get(node_id)?;
+
+        let mut srcs = vec![tmpl.out_local2global(out)?];
+        for copy in copies.iter() {
+            srcs.push(copy.out_local2global(out)?);
+        }
+
+        for src in srcs.iter() {
+            prog.set_out_connected(*src);
+        }
+
+        let bus_idx = self.voice_bus.get(node_id)? + out as usize;
+        prog.append_voice_sum(copies.last()?.prog_idx, bus_idx, srcs);
+
+        Some(bus_idx)
     }
 
     /// Creates a new [NodeOp] and add it to the [NodeProg].
@@ -863,6 +1363,13 @@ impl NodeConfigurator {
             let op = node_instance.as_op();
             prog.append_op(op);
         }
+
+        // The voice copies are executed right after the original node:
+        if let Some(copies) = self.voice_copies.get(node_id) {
+            for copy in copies.iter() {
+                prog.append_op(copy.as_op());
+            }
+        }
     }
 
     /// Adds an adjacent output connection to the given node input.
@@ -880,12 +1387,17 @@ impl NodeConfigurator {
         node_input: (NodeId, u8),
         adjacent_output: (NodeId, u8),
     ) {
-        let output_index =
-            if let Some((_, Some(node_instance))) = self.node_by_id(&adjacent_output.0) {
-                node_instance.out_local2global(adjacent_output.1)
-            } else {
-                return;
-            };
+        let out_copies = self.voice_copies.get(&adjacent_output.0).cloned();
+        let in_copies = self.voice_copies.get(&node_input.0).cloned();
+
+        let output_index = if out_copies.is_some() && in_copies.is_none() {
+            // Leaving the voice region, read the sum of all voices:
+            self.voice_bus_output(prog, &adjacent_output.0, adjacent_output.1)
+        } else if let Some((_, Some(node_instance))) = self.node_by_id(&adjacent_output.0) {
+            node_instance.out_local2global(adjacent_output.1)
+        } else {
+            return;
+        };
 
         if let Some((_node_info, Some(node_instance))) = self.node_by_id_mut(&node_input.0) {
             node_instance.mark_used();
@@ -897,6 +1409,22 @@ impl NodeConfigurator {
                 prog.append_edge(op, input_index, output_index, mod_index);
             }
         }
+
+        // Connect the voice copies, either voice by voice inside the
+        // voice region, or all voices to the same output from outside:
+        for (i, copy) in in_copies.iter().flatten().enumerate() {
+            let output_index = if let Some(out_copies) = &out_copies {
+                out_copies.get(i).and_then(|out| out.out_local2global(adjacent_output.1))
+            } else {
+                output_index
+            };
+
+            let input_index = copy.in_local2global(node_input.1);
+            let mod_index = copy.mod_in_local2global(node_input.1);
+            if let (Some(input_index), Some(output_index)) = (input_index, output_index) {
+                prog.append_edge(copy.as_op(), input_index, output_index, mod_index);
+            }
+        }
     }
 
     /// Uploads a new NodeProg instance.
@@ -912,6 +1440,9 @@ impl NodeConfigurator {
     /// It must not be set when a completely new set of node instances
     /// was created, for instance when a completely new patch was loaded.
     ///
+    /// To prevent clicks when the routing changes, the outputs can be
+    /// crossfaded, see [NodeConfigurator::set_prog_crossfade].
+    ///
     /// Here is an example on how to use the [NodeConfigurator]
     /// directly to setup and upload a [NodeProg]:
     ///
@@ -944,6 +1475,10 @@ impl NodeConfigurator {
 
             if let Some((mod_idx, amt)) = param.modamt {
                 prog.modops_mut()[mod_idx].set_amt(amt);
+
+                for mod_idx in self.voice_mods.get(&mod_idx).into_iter().flatten() {
+                    prog.modops_mut()[*mod_idx].set_amt(amt);
+                }
             }
         }
 
@@ -954,7 +1489,9 @@ impl NodeConfigurator {
 
         self.output_fb_cons = prog.take_feedback_consumer();
 
-        let _ = self.shared.graph_update_prod.push(GraphMessage::NewProg { prog, copy_old_out });
+        prog.build_levels();
+
+        self.push_graph_update(GraphMessage::NewProg { prog, copy_old_out });
     }
 
     /// Retrieves the feedback value for a specific output port of the
@@ -977,7 +1514,12 @@ impl NodeConfigurator {
     /// up to date output feedback values that are available.
     ///
     /// Retrieve the output value by calling [NodeConfigurator::out_fb_for].
+    ///
+    /// The graph updates that did not fit into the graph update queue
+    /// are sent here too, if there is room for them now.
     pub fn update_output_feedback(&mut self) {
+        self.flush_graph_updates();
+
         if let Some(out_fb_output) = &mut self.output_fb_cons {
             out_fb_output.update();
             let out_vec = out_fb_output.output_buffer();
@@ -988,6 +1530,80 @@ impl NodeConfigurator {
         }
     }
 
+    /// Crossfades the outputs of the `Out` node from the previous to the
+    /// new program over `ms` milliseconds, after [NodeConfigurator::upload_prog].
+    /// This prevents clicks, when the routing changes while editing a patch.
+    /// 0.0 switches the programs at once, which is the default.
+    ///
+    /// While fading, the [crate::nodes::NodeExecutor] executes the
+    /// previous program next to the new one. The nodes that were removed
+    /// from the new program fade out. The nodes of both programs with
+    /// rerouted inputs are cloned when the crossfade starts, so the
+    /// previous program keeps executing them with their previous inputs.
+    /// Cloning allocates on the audio thread for nodes with buffers of
+    /// their own, like delays. The `FbWr` and `FbRd` nodes are not cloned.
+    /// A crossfade is cut short when the next program is uploaded.
+    pub fn set_prog_crossfade(&mut self, ms: f32) {
+        self.push_graph_update(GraphMessage::SetProgCrossfade { ms });
+    }
+
+    /// Enables or disables the measuring of the processing time of
+    /// the nodes in the [crate::nodes::NodeExecutor]. It's disabled by
+    /// default, because the time measurements cost some DSP time too.
+    ///
+    /// See also [NodeConfigurator::update_dsp_load].
+    pub fn set_dsp_profiling(&mut self, enabled: bool) {
+        self.push_graph_update(GraphMessage::SetDspProfiling { enabled });
+    }
+
+    /// Lets `count` worker threads execute the independent nodes of the
+    /// DSP graph, together with the audio thread. 0 executes all nodes
+    /// on the audio thread, which is the default.
+    ///
+    /// The graph is split into levels of nodes that don't depend on each
+    /// other, see [NodeProg::build_levels]. The nodes that access the audio
+    /// driver or the [crate::nodes::NodeExecContext] always run on the
+    /// audio thread, see [crate::dsp::NodeId::is_context_bound].
+    /// The output is exactly the same as with the sequential execution.
+    ///
+    /// The idle workers busy wait for the next audio block for a short
+    /// time, so this is only worth it for bigger graphs.
+    pub fn set_worker_threads(&mut self, count: usize) {
+        let pool = if count > 0 { Some(Box::new(WorkerPool::new(count))) } else { None };
+        self.push_graph_update(GraphMessage::SetWorkerPool { pool });
+    }
+
+    /// Checks if the backend has published new DSP load measurements.
+    /// They are published every [crate::nodes::DSP_LOAD_WINDOW_MS] of
+    /// processed audio, if enabled with [NodeConfigurator::set_dsp_profiling].
+    ///
+    /// Retrieve the measurements with [NodeConfigurator::node_dsp_load]
+    /// and [NodeConfigurator::dsp_load].
+    pub fn update_dsp_load(&mut self) {
+        let cons = &mut self.shared.dsp_load_cons;
+        if cons.updated() {
+            self.dsp_load.copy_from(cons.read());
+        }
+    }
+
+    /// Returns the DSP load of the node `node_id`. The voices of nodes
+    /// in the voice region are summed up.
+    pub fn node_dsp_load(&self, node_id: &NodeId) -> Option<DspLoad> {
+        let node_instance = self.node_by_id(node_id)?.1.as_ref()?;
+
+        let mut load = self.dsp_load.node(node_instance.prog_idx);
+        for copy in self.voice_copies.get(node_id).into_iter().flatten() {
+            self.dsp_load.add_node(&mut load, copy.prog_idx);
+        }
+
+        Some(load)
+    }
+
+    /// Returns the DSP load of the whole DSP graph.
+    pub fn dsp_load(&self) -> DspLoad {
+        self.dsp_load.total()
+    }
+
     pub fn get_minmax_monitor_samples(&mut self, idx: usize) -> &MinMaxMonitorSamples {
         self.shared.monitor.get_minmax_monitor_samples(idx)
     }
@@ -2,18 +2,22 @@
 // This file is a part of HexoDSP. Released under GPL-3.0-or-later.
 // See README.md and COPYING for details.
 
+use super::crossfade::{CaptureContext, FadeOp, ProgCrossfade};
+use super::dsp_load::{LoadMeter, NodeTimer};
 use super::{
-    DropMsg, GraphMessage, NodeProg, FB_DELAY_TIME_US, MAX_ALLOCATED_NODES, MAX_FB_DELAY_SIZE,
-    MAX_SMOOTHERS, UNUSED_MONITOR_IDX,
+    flush_denormals, DropMsg, FaultReport, GraphMessage, GraphReject, MidiEvent, MidiEventQueue,
+    MidiInput, ModOp, NodeOp, NodeProg, NodeState, SignalFaultKind, Transport, VoiceAllocator,
+    WorkerPool, FB_DELAY_TIME_US, MAX_FB_DELAYS, MAX_FB_DELAY_SIZE, MAX_MIDI_EVENTS, MAX_SMOOTHERS,
+    MAX_TIMED_UPDATES, UNUSED_MONITOR_IDX,
 };
-use crate::dsp::{Node, NodeContext, NodeId, MAX_BLOCK_SIZE};
+use crate::dsp::{Node, NodeContext, NodeId, ProcBuf, SAtom, MAX_BLOCK_SIZE};
 use crate::monitor::{MonitorBackend, MON_SIG_CNT};
 use crate::util::{AtomicFloat, Smoother};
 
 use crate::log;
 use std::io::Write;
 
-use ringbuf::{Consumer, Producer};
+use ringbuf::{Consumer, Producer, RingBuffer};
 use std::sync::Arc;
 
 use core::arch::x86_64::{
@@ -47,6 +51,10 @@ pub struct NodeExecutor {
     /// these will refresh the input buffers:
     pub(crate) target_refresh: Vec<(usize, f32)>,
 
+    /// Holds the parameter and atom updates that are applied at
+    /// a frame offset inside the processed blocks.
+    timed_updates: TimedUpdateQueue,
+
     /// Contains the to be executed nodes and output operations.
     /// Is copied from the input ringbuffer when a corresponding
     /// message arrives.
@@ -58,16 +66,100 @@ pub struct NodeExecutor {
     /// The sample rate
     pub(crate) sample_rate: f32,
 
+    /// The maximum number of frames the nodes process at once.
+    max_block_size: usize,
+
+    /// The number of frames the buffers of the nodes were allocated for,
+    /// which limits the maximum block size.
+    block_capacity: usize,
+
     /// Context that can be accessed by all (executed) nodes at runtime.
     pub(crate) exec_ctx: NodeExecContext,
 
+    /// Fades from the previous program to the new one,
+    /// after a new program was uploaded.
+    crossfade: ProgCrossfade,
+
+    /// The length of the [ProgCrossfade] in milliseconds, 0.0 disables it.
+    crossfade_ms: f32,
+
+    /// The invalid output and fault kind that was found by the signal
+    /// guard in the current block, for each node index.
+    signal_faults: Vec<Option<(u8, SignalFaultKind)>>,
+
+    /// Remembers for each node index, whether a [SignalFaultKind] was
+    /// already reported. This is cleared when the program changes.
+    faults_reported: Vec<bool>,
+
     /// The connection with the [crate::nodes::NodeConfigurator].
     shared: SharedNodeExec,
 
+    /// Executes the independent nodes in parallel, if set.
+    worker_pool: Option<Box<WorkerPool>>,
+
+    /// Receives the MIDI events sent by the [MidiInput].
+    midi_con: Consumer<MidiEvent>,
+
+    /// Holds the [MidiInput] until it is taken by [NodeExecutor::take_midi_input].
+    midi_input: Option<MidiInput>,
+
     /// A flag to remember if we already initialized the logger on the audio thread.
     dsp_log_init: bool,
 }
 
+/// The storage of the [NodeExecutor] for each node index. When the node
+/// capacity grows, the [crate::nodes::NodeConfigurator] allocates the
+/// larger storage and sends it with [GraphMessage::GrowNodes]. The executor
+/// moves the nodes over and sends the previous storage to the drop thread.
+pub struct NodeSlots {
+    nodes: Vec<Node>,
+    node_ctx_values: Vec<Arc<AtomicFloat>>,
+    signal_faults: Vec<Option<(u8, SignalFaultKind)>>,
+    faults_reported: Vec<bool>,
+    crossfade: ProgCrossfade,
+    load_meter: LoadMeter,
+    feedback_delay_buffers: Vec<FeedbackBuffer>,
+}
+
+impl NodeSlots {
+    /// Allocates the storage for `capacity` nodes and `feedback_capacity`
+    /// feedback delays. The `node_ctx_values` are shared with the frontend
+    /// and must hold two values per node.
+    pub(crate) fn new(
+        capacity: usize,
+        feedback_capacity: usize,
+        max_block_size: usize,
+        node_ctx_values: Vec<Arc<AtomicFloat>>,
+        load_meter: LoadMeter,
+    ) -> Self {
+        let mut nodes = Vec::new();
+        nodes.resize_with(capacity, || Node::Nop);
+
+        let mut feedback_delay_buffers = vec![];
+        feedback_delay_buffers
+            .resize_with(feedback_capacity, || FeedbackBuffer::with_block_size(max_block_size));
+
+        Self {
+            nodes,
+            node_ctx_values,
+            signal_faults: vec![None; capacity],
+            faults_reported: vec![false; capacity],
+            crossfade: ProgCrossfade::new(capacity, max_block_size),
+            load_meter,
+            feedback_delay_buffers,
+        }
+    }
+}
+
+impl std::fmt::Debug for NodeSlots {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeSlots")
+            .field("capacity", &self.nodes.len())
+            .field("feedback_capacity", &self.feedback_delay_buffers.len())
+            .finish()
+    }
+}
+
 /// Contains anything that connects the [NodeExecutor] with the frontend part.
 pub(crate) struct SharedNodeExec {
     /// Holds two context values interleaved.
@@ -81,6 +173,12 @@ pub(crate) struct SharedNodeExec {
     pub(crate) graph_drop_prod: Producer<DropMsg>,
     /// For sending feedback to the frontend thread.
     pub(crate) monitor_backend: MonitorBackend,
+    /// Measures the DSP load and publishes it to the frontend thread.
+    pub(crate) load_meter: LoadMeter,
+    /// For sending the signal faults of the nodes to the frontend thread.
+    pub(crate) signal_fault_prod: Producer<FaultReport>,
+    /// For sending the rejected graph updates back to the frontend thread.
+    pub(crate) graph_reject_prod: Producer<GraphReject>,
 }
 
 /// Contains audio driver context informations. Such as the number
@@ -90,6 +188,388 @@ pub trait NodeAudioContext {
     fn nframes(&self) -> usize;
     fn output(&mut self, channel: usize, frame: usize, v: f32);
     fn input(&mut self, channel: usize, frame: usize) -> f32;
+
+    /// The number of output channels the audio driver provides.
+    /// The `Out` node only writes to these.
+    fn output_channels(&self) -> usize {
+        2
+    }
+
+    /// The number of input channels the audio driver provides.
+    /// The `In` node only reads from these.
+    fn input_channels(&self) -> usize {
+        2
+    }
+}
+
+/// Passes a part of the block of the [NodeAudioContext] to the nodes,
+/// when the block is split for the timed parameter updates.
+struct SubBlockContext<'a, T: NodeAudioContext> {
+    ctx: &'a mut T,
+    offs: usize,
+    nframes: usize,
+}
+
+impl<'a, T: NodeAudioContext> NodeAudioContext for SubBlockContext<'a, T> {
+    #[inline]
+    fn nframes(&self) -> usize {
+        self.nframes
+    }
+
+    #[inline]
+    fn output(&mut self, channel: usize, frame: usize, v: f32) {
+        self.ctx.output(channel, self.offs + frame, v);
+    }
+
+    #[inline]
+    fn input(&mut self, channel: usize, frame: usize) -> f32 {
+        self.ctx.input(channel, self.offs + frame)
+    }
+
+    fn output_channels(&self) -> usize {
+        self.ctx.output_channels()
+    }
+
+    fn input_channels(&self) -> usize {
+        self.ctx.input_channels()
+    }
+}
+
+/// The [NodeAudioContext] of the nodes that are executed on the worker
+/// threads, without any audio input or output channels.
+struct DetachedAudioContext {
+    nframes: usize,
+}
+
+impl NodeAudioContext for DetachedAudioContext {
+    #[inline]
+    fn nframes(&self) -> usize {
+        self.nframes
+    }
+
+    fn output(&mut self, _channel: usize, _frame: usize, _v: f32) {}
+
+    fn input(&mut self, _channel: usize, _frame: usize) -> f32 {
+        0.0
+    }
+
+    fn output_channels(&self) -> usize {
+        0
+    }
+
+    fn input_channels(&self) -> usize {
+        0
+    }
+}
+
+/// Sets the flush to zero mode for denormals on the current thread.
+#[inline]
+pub(crate) fn flush_denormals_to_zero() {
+    unsafe {
+        _MM_SET_FLUSH_ZERO_MODE(_MM_FLUSH_ZERO_ON);
+    }
+}
+
+/// Raw views of the buffers of the [NodeProg] and of the nodes, for
+/// sharing them with the worker threads. The ops in a level of the
+/// program only access disjoint parts of them.
+#[derive(Clone, Copy)]
+struct ProgView {
+    nodes: *mut Node,
+    modops: *mut ModOp,
+    inp: *const ProcBuf,
+    cur_inp: *const ProcBuf,
+    out: *mut ProcBuf,
+    atoms: *const SAtom,
+    out_fb: *mut f32,
+    ctx_vals: *const Arc<AtomicFloat>,
+    faults: *mut Option<(u8, SignalFaultKind)>,
+    timer: NodeTimer,
+}
+
+unsafe impl Send for ProgView {}
+unsafe impl Sync for ProgView {}
+
+impl ProgView {
+    fn new(
+        nodes: &mut [Node],
+        prog: &mut NodeProg,
+        ctx_vals: &[Arc<AtomicFloat>],
+        faults: &mut [Option<(u8, SignalFaultKind)>],
+        timer: NodeTimer,
+    ) -> Self {
+        Self {
+            nodes: nodes.as_mut_ptr(),
+            modops: prog.modops.as_mut_ptr(),
+            inp: prog.inp.as_ptr(),
+            cur_inp: prog.cur_inp.as_ptr(),
+            out: prog.out.as_mut_ptr(),
+            atoms: prog.atoms.as_ptr(),
+            out_fb: prog.out_feedback.input_buffer().as_mut_ptr(),
+            ctx_vals: ctx_vals.as_ptr(),
+            faults: faults.as_mut_ptr(),
+            timer,
+        }
+    }
+
+    /// Executes the node of `op`. This must not be called at the same
+    /// time for ops that depend on each other, see [NodeProg::build_levels].
+    #[inline]
+    unsafe fn process_op<T: NodeAudioContext>(
+        &self,
+        op: &NodeOp,
+        ctx: &mut T,
+        ectx: &mut NodeExecContext,
+    ) {
+        self.process_node(op, &mut *self.nodes.add(op.idx), ctx, ectx);
+    }
+
+    /// Executes `node` with the inputs and outputs of `op`. This is the
+    /// node of `op` or a clone of it, see [FadeOp::Clone].
+    #[inline]
+    unsafe fn process_node<T: NodeAudioContext>(
+        &self,
+        op: &NodeOp,
+        node: &mut Node,
+        ctx: &mut T,
+        ectx: &mut NodeExecContext,
+    ) {
+        use std::slice::{from_raw_parts, from_raw_parts_mut};
+
+        let out = op.out_idxlen;
+        let inp = op.in_idxlen;
+        let at = op.at_idxlen;
+        let md = op.mod_idxlen;
+        let ctx_idx = op.idx * 2;
+        let param_idx = op.param_idx;
+        let nframes = ctx.nframes();
+
+        let t_start = self.timer.start();
+
+        for modop in from_raw_parts_mut(self.modops.add(md.0), md.1 - md.0) {
+            modop.process(nframes);
+        }
+
+        let outputs = from_raw_parts_mut(self.out.add(out.0), out.1 - out.0);
+
+        match op.state {
+            NodeState::Active => {
+                node.process(
+                    ctx,
+                    ectx,
+                    &NodeContext {
+                        out_connected: op.out_connected,
+                        in_connected: op.in_connected,
+                        // Voice copies read the parameters of the node they were copied from:
+                        params: from_raw_parts(self.inp.add(param_idx), inp.1 - inp.0),
+                        voice: op.voice as usize,
+                    },
+                    from_raw_parts(self.atoms.add(at.0), at.1 - at.0),
+                    from_raw_parts(self.cur_inp.add(inp.0), inp.1 - inp.0),
+                    outputs,
+                    from_raw_parts(self.ctx_vals.add(ctx_idx), 2),
+                );
+            }
+            NodeState::Bypassed | NodeState::Muted => {
+                for pb in outputs.iter_mut() {
+                    pb.fill(0.0);
+                }
+
+                if op.state == NodeState::Bypassed && inp.1 > inp.0 {
+                    if let Some(pb) = outputs.first_mut() {
+                        let inp_pb = *self.cur_inp.add(inp.0);
+                        for frame in 0..nframes {
+                            pb.write(frame, inp_pb.read(frame));
+                        }
+                    }
+                }
+
+                match node {
+                    // The Out node has no outputs, it's silenced instead:
+                    Node::Out { .. } => {
+                        for ch in 0..ctx.output_channels() {
+                            for frame in 0..nframes {
+                                ctx.output(ch, frame, 0.0);
+                            }
+                        }
+                    }
+                    // The feedback delay has to keep running, or the
+                    // FbRd would loop a stale signal or lag behind:
+                    Node::FbWr { node } => node.write_silence(ectx, nframes),
+                    Node::FbRd { node } => node.skip(ectx, nframes),
+                    _ => (),
+                }
+            }
+        }
+
+        // The signal guard stops invalid samples from spreading
+        // through the graph:
+        let mut fault: Option<(u8, SignalFaultKind)> = None;
+        for (i, pb) in outputs.iter_mut().enumerate() {
+            if let Some(kind) = SignalFaultKind::scan(pb, nframes) {
+                if kind.needs_reset() {
+                    pb.fill(0.0);
+                } else {
+                    flush_denormals(pb, nframes);
+                }
+
+                // A NaN or infinite output is reported before a denormal one:
+                let replace = match fault {
+                    Some((_, prev)) => kind.needs_reset() && !prev.needs_reset(),
+                    None => true,
+                };
+                if replace {
+                    fault = Some((i as u8, kind));
+                }
+            }
+        }
+
+        if let Some((_, kind)) = fault {
+            if kind.needs_reset() {
+                node.reset();
+            }
+            *self.faults.add(op.idx) = fault;
+        }
+
+        let last_frame_idx = nframes - 1;
+        for (i, pb) in outputs.iter().enumerate() {
+            *self.out_fb.add(out.0 + i) = pb.read(last_frame_idx);
+        }
+
+        self.sum_voices(op, nframes);
+
+        self.timer.end(op.idx, t_start);
+    }
+
+    /// Copies the outputs of a node, that was already executed by the
+    /// program of `src`, instead of executing it again.
+    /// See also [FadeOp::Copy].
+    #[inline]
+    unsafe fn copy_op(
+        &self,
+        op: &NodeOp,
+        src: &ProgView,
+        src_idx: usize,
+        len: usize,
+        nframes: usize,
+    ) {
+        for i in 0..len {
+            let from = *src.out.add(src_idx + i);
+            let mut to = *self.out.add(op.out_idxlen.0 + i);
+
+            for frame in 0..nframes {
+                to.write(frame, from.read(frame));
+            }
+        }
+
+        self.sum_voices(op, nframes);
+    }
+
+    /// Mixes the voices, after the last voice was executed.
+    #[inline]
+    unsafe fn sum_voices(&self, op: &NodeOp, nframes: usize) {
+        for (bus_idx, srcs) in op.voice_sums.iter() {
+            let mut bus = *self.out.add(*bus_idx);
+            for frame in 0..nframes {
+                bus.write(frame, srcs.iter().map(|src| (*self.out.add(*src)).read(frame)).sum());
+            }
+        }
+    }
+
+    /// Executes all ops of the program `prog`, that this view was created
+    /// from. The independent ops are executed by the `pool`, if given.
+    #[inline]
+    unsafe fn run<T: NodeAudioContext>(
+        &self,
+        prog: &NodeProg,
+        ctx: &mut T,
+        ectx: &mut NodeExecContext,
+        pool: Option<&mut WorkerPool>,
+    ) {
+        let nframes = ctx.nframes();
+
+        match pool {
+            Some(pool) if !prog.levels.is_empty() => {
+                for level in prog.levels.iter() {
+                    for op_pos in prog.schedule[level.serial.0..level.serial.1].iter() {
+                        self.process_op(&prog.prog[*op_pos], ctx, ectx);
+                    }
+
+                    let parallel = &prog.schedule[level.parallel.0..level.parallel.1];
+                    let ops = &prog.prog;
+                    pool.run(parallel.len(), &|i, ectx| {
+                        let mut ctx = DetachedAudioContext { nframes };
+                        self.process_op(&ops[parallel[i]], &mut ctx, ectx);
+                    });
+                }
+            }
+            _ => {
+                for op in prog.prog.iter() {
+                    self.process_op(op, ctx, ectx);
+                }
+            }
+        }
+    }
+}
+
+/// A parameter or atom update that is applied at a frame offset,
+/// see also [GraphMessage::ParamUpdateAt] and [GraphMessage::AtomUpdateAt].
+#[derive(Debug)]
+enum TimedUpdate {
+    Param { input_idx: usize, value: f32 },
+    Atom { at_idx: usize, value: SAtom },
+}
+
+/// Holds the [TimedUpdate]s sorted by their frame offset, relative
+/// to the start of the next processed (sub-)block.
+struct TimedUpdateQueue {
+    updates: Vec<(usize, TimedUpdate)>,
+}
+
+impl TimedUpdateQueue {
+    fn new() -> Self {
+        Self { updates: Vec::with_capacity(MAX_TIMED_UPDATES) }
+    }
+
+    /// Inserts the update after all updates with the same or an earlier
+    /// frame offset. If the queue is full, the update is handed back.
+    fn push(&mut self, frame: usize, upd: TimedUpdate) -> Option<TimedUpdate> {
+        if self.updates.len() >= MAX_TIMED_UPDATES {
+            return Some(upd);
+        }
+
+        let idx = self.updates.iter().rposition(|(f, _)| *f <= frame).map(|i| i + 1);
+        self.updates.insert(idx.unwrap_or(0), (frame, upd));
+        None
+    }
+
+    /// Returns the frame offset of the next update.
+    #[inline]
+    fn next_frame(&self) -> Option<usize> {
+        self.updates.first().map(|(f, _)| *f)
+    }
+
+    /// Removes the next update, if it is due at the current frame.
+    #[inline]
+    fn pop_due(&mut self) -> Option<TimedUpdate> {
+        if self.next_frame() == Some(0) {
+            Some(self.updates.remove(0).1)
+        } else {
+            None
+        }
+    }
+
+    /// Moves the remaining updates `nframes` closer.
+    #[inline]
+    fn advance(&mut self, nframes: usize) {
+        for (frame, _) in self.updates.iter_mut() {
+            *frame = frame.saturating_sub(nframes);
+        }
+    }
+
+    fn clear(&mut self) -> impl Iterator<Item = TimedUpdate> + '_ {
+        self.updates.drain(..).map(|(_, upd)| upd)
+    }
 }
 
 /// Implements a trivial buffer for the feedback nodes
@@ -101,29 +581,43 @@ pub trait NodeAudioContext {
 /// See also `sample_count` field.
 pub struct FeedbackBuffer {
     /// The feedback buffer that holds the samples of the previous period.
-    buffer: [f32; MAX_FB_DELAY_SIZE],
+    buffer: Box<[f32]>,
     /// The write pointer.
     write_ptr: usize,
     /// Read pointer, is always behind write_ptr by an initial amount
     read_ptr: usize,
+    /// The shortest delay in samples, which is the maximum block size.
+    /// A [FeedbackBuffer] can't delay less than one block, because the
+    /// `FbRd` node might be executed before the `FbWr` node.
+    min_delay: usize,
 }
 
 impl FeedbackBuffer {
+    /// Creates a [FeedbackBuffer] for blocks of up to [MAX_BLOCK_SIZE] frames.
     pub fn new() -> Self {
-        let delay_sample_count = (44100.0 as usize * FB_DELAY_TIME_US) / 1000000;
-        Self {
-            buffer: [0.0; MAX_FB_DELAY_SIZE],
-            write_ptr: delay_sample_count % MAX_FB_DELAY_SIZE,
+        Self::with_block_size(MAX_BLOCK_SIZE)
+    }
+
+    /// Creates a [FeedbackBuffer] for blocks of up to `max_block_size` frames.
+    /// If the block is longer than [FB_DELAY_TIME_US], the delay is
+    /// one block long.
+    pub fn with_block_size(max_block_size: usize) -> Self {
+        let mut this = Self {
+            buffer: vec![0.0; MAX_FB_DELAY_SIZE.max(2 * max_block_size)].into_boxed_slice(),
+            write_ptr: 0,
             read_ptr: 0,
-        }
+            min_delay: max_block_size,
+        };
+        this.set_sample_rate(44100.0);
+        this
     }
 
     pub fn clear(&mut self) {
-        self.buffer = [0.0; MAX_FB_DELAY_SIZE];
+        self.buffer.fill(0.0);
     }
 
     pub fn set_sample_rate(&mut self, sr: f32) {
-        self.buffer = [0.0; MAX_FB_DELAY_SIZE];
+        self.buffer.fill(0.0);
         // The delay sample count maximum is defined by MAX_FB_DELAY_SRATE,
         // after that the feedback delays become shorter than they should be
         // and things won't sound the same at sample rate
@@ -136,21 +630,21 @@ impl FeedbackBuffer {
         // For more elaborate and longer delays an extra delay node should
         // be used before FbWr or after FbRd.
 
-        let delay_sample_count = (sr as usize * FB_DELAY_TIME_US) / 1000000;
-        self.write_ptr = delay_sample_count % MAX_FB_DELAY_SIZE;
+        let delay_sample_count = ((sr as usize * FB_DELAY_TIME_US) / 1000000).max(self.min_delay);
+        self.write_ptr = delay_sample_count % self.buffer.len();
         self.read_ptr = 0;
     }
 
     #[inline]
     pub fn write(&mut self, s: f32) {
-        self.write_ptr = (self.write_ptr + 1) % MAX_FB_DELAY_SIZE;
+        self.write_ptr = (self.write_ptr + 1) % self.buffer.len();
         self.buffer[self.write_ptr] = s;
         //d// println!("WRITE[{}] = {:8.3}", self.write_ptr, s);
     }
 
     #[inline]
     pub fn read(&mut self) -> f32 {
-        self.read_ptr = (self.read_ptr + 1) % MAX_FB_DELAY_SIZE;
+        self.read_ptr = (self.read_ptr + 1) % self.buffer.len();
         self.buffer[self.read_ptr]
         //d// let s = self.buffer[self.read_ptr];
         //d// println!("READ[{}] = {:8.3}", self.read_ptr, s);
@@ -168,13 +662,36 @@ impl Default for FeedbackBuffer {
 /// This is used for instance to implement the feedbackd delay nodes.
 pub struct NodeExecContext {
     pub feedback_delay_buffers: Vec<FeedbackBuffer>,
+    /// The notes that are played by the voices, read by the `Voice` node.
+    pub voices: VoiceAllocator,
+    /// The MIDI events of the currently processed block.
+    pub midi: MidiEventQueue,
+    /// The tempo and song position at the start of the processed block.
+    pub transport: Transport,
 }
 
 impl NodeExecContext {
-    fn new() -> Self {
+    /// A context for the nodes that are executed on the worker
+    /// threads. These don't access the context at all, see also
+    /// [crate::dsp::NodeId::is_context_bound].
+    pub(crate) fn detached() -> Self {
+        Self {
+            feedback_delay_buffers: vec![],
+            voices: VoiceAllocator::new(),
+            midi: MidiEventQueue::new(),
+            transport: Transport::new(),
+        }
+    }
+
+    fn new(max_block_size: usize) -> Self {
         let mut fbdb = vec![];
-        fbdb.resize_with(MAX_ALLOCATED_NODES, FeedbackBuffer::new);
-        Self { feedback_delay_buffers: fbdb }
+        fbdb.resize_with(MAX_FB_DELAYS, || FeedbackBuffer::with_block_size(max_block_size));
+        Self {
+            feedback_delay_buffers: fbdb,
+            voices: VoiceAllocator::new(),
+            midi: MidiEventQueue::new(),
+            transport: Transport::new(),
+        }
     }
 
     fn set_sample_rate(&mut self, srate: f32) {
@@ -187,32 +704,71 @@ impl NodeExecContext {
         for b in self.feedback_delay_buffers.iter_mut() {
             b.clear();
         }
+
+        self.voices = VoiceAllocator::new();
     }
 }
 
 impl NodeExecutor {
-    pub(crate) fn new(shared: SharedNodeExec) -> Self {
+    pub(crate) fn new(shared: SharedNodeExec, capacity: usize, max_block_size: usize) -> Self {
         let mut nodes = Vec::new();
-        nodes.resize_with(MAX_ALLOCATED_NODES, || Node::Nop);
+        nodes.resize_with(capacity, || Node::Nop);
 
         let mut smoothers = Vec::new();
         smoothers.resize_with(MAX_SMOOTHERS, || (0, Smoother::new()));
 
         let target_refresh = Vec::with_capacity(MAX_SMOOTHERS);
 
+        let (midi_prod, midi_con) = RingBuffer::new(MAX_MIDI_EVENTS).split();
+
         NodeExecutor {
             nodes,
             smoothers,
             target_refresh,
+            timed_updates: TimedUpdateQueue::new(),
             sample_rate: 44100.0,
+            max_block_size,
+            block_capacity: max_block_size,
             prog: NodeProg::empty(),
             monitor_signal_cur_inp_indices: [UNUSED_MONITOR_IDX; MON_SIG_CNT],
-            exec_ctx: NodeExecContext::new(),
+            exec_ctx: NodeExecContext::new(max_block_size),
+            crossfade: ProgCrossfade::new(capacity, max_block_size),
+            crossfade_ms: 0.0,
+            signal_faults: vec![None; capacity],
+            faults_reported: vec![false; capacity],
             dsp_log_init: false,
             shared,
+            worker_pool: None,
+            midi_con,
+            midi_input: Some(MidiInput { prod: midi_prod }),
         }
     }
 
+    /// Returns the [MidiInput] for sending MIDI events from another thread.
+    /// There is only one, so this returns `None` after the first call.
+    pub fn take_midi_input(&mut self) -> Option<MidiInput> {
+        self.midi_input.take()
+    }
+
+    /// Sets the tempo and song position of the host. Call this from the
+    /// audio thread before [NodeExecutor::process], with the position at
+    /// the start of the period. See also [Transport].
+    pub fn set_transport(&mut self, transport: Transport) {
+        self.exec_ctx.transport = transport;
+    }
+
+    pub fn transport(&self) -> &Transport {
+        &self.exec_ctx.transport
+    }
+
+    /// Queues a MIDI event for the nodes. Call this from the audio thread
+    /// before [NodeExecutor::process], with the event's frame offset
+    /// relative to the start of the block. Returns `false` if the
+    /// queue is full and the event was dropped.
+    pub fn feed_midi_event(&mut self, event: MidiEvent) -> bool {
+        self.exec_ctx.midi.push(event)
+    }
+
     pub fn no_logging(&mut self) {
         self.dsp_log_init = true;
     }
@@ -222,8 +778,22 @@ impl NodeExecutor {
         while let Some(upd) = self.shared.graph_update_con.pop() {
             match upd {
                 GraphMessage::NewNode { index, mut node } => {
+                    // The node storage is too small, if it did not grow in time:
+                    if index >= self.nodes.len() {
+                        let _ = self.shared.graph_reject_prod.push(GraphReject::Node { index });
+                        let _ = self.shared.graph_drop_prod.push(DropMsg::Node { node });
+                        continue;
+                    }
+
                     node.set_sample_rate(self.sample_rate);
-                    let prev_node = std::mem::replace(&mut self.nodes[index as usize], node);
+
+                    // The previous program must not execute the new node:
+                    if self.crossfade.uses_node(index) {
+                        self.finish_crossfade();
+                    }
+
+                    let prev_node = std::mem::replace(&mut self.nodes[index], node);
+                    self.faults_reported[index] = false;
 
                     log(|w| {
                         let _ = write!(w, "[dbg] Create node index={}", index);
@@ -231,7 +801,12 @@ impl NodeExecutor {
 
                     let _ = self.shared.graph_drop_prod.push(DropMsg::Node { node: prev_node });
                 }
+                GraphMessage::GrowNodes { slots } => {
+                    self.grow_nodes(slots);
+                }
                 GraphMessage::Clear { prog } => {
+                    self.finish_crossfade();
+
                     for n in self.nodes.iter_mut() {
                         if n.to_id(0) != NodeId::Nop {
                             let prev_node = std::mem::replace(n, Node::Nop);
@@ -241,6 +816,13 @@ impl NodeExecutor {
                     }
 
                     self.exec_ctx.clear();
+                    self.faults_reported.fill(false);
+
+                    for upd in self.timed_updates.clear() {
+                        if let TimedUpdate::Atom { value, .. } = upd {
+                            let _ = self.shared.graph_drop_prod.push(DropMsg::Atom { atom: value });
+                        }
+                    }
 
                     self.monitor_signal_cur_inp_indices = [UNUSED_MONITOR_IDX; MON_SIG_CNT];
 
@@ -252,13 +834,21 @@ impl NodeExecutor {
                     let _ = self.shared.graph_drop_prod.push(DropMsg::Prog { prog: prev_prog });
                 }
                 GraphMessage::NewProg { prog, copy_old_out } => {
+                    if prog.prog.iter().any(|op| op.idx >= self.nodes.len()) {
+                        let _ = self.shared.graph_reject_prod.push(GraphReject::Prog);
+                        let _ = self.shared.graph_drop_prod.push(DropMsg::Prog { prog });
+                        continue;
+                    }
+
+                    // A running crossfade is cut short by the next program:
+                    self.finish_crossfade();
+
                     let mut prev_prog = std::mem::replace(&mut self.prog, prog);
 
-                    unsafe {
-                        _MM_SET_FLUSH_ZERO_MODE(_MM_FLUSH_ZERO_ON);
-                    }
+                    flush_denormals_to_zero();
 
                     self.monitor_signal_cur_inp_indices = [UNUSED_MONITOR_IDX; MON_SIG_CNT];
+                    self.faults_reported.fill(false);
 
                     // XXX: Copying from the old vector works, because we only
                     //      append nodes to the _end_ of the node instance vector.
@@ -310,7 +900,17 @@ impl NodeExecutor {
 
                     self.prog.assign_outputs();
 
-                    let _ = self.shared.graph_drop_prod.push(DropMsg::Prog { prog: prev_prog });
+                    let fade_len = (self.crossfade_ms * self.sample_rate / 1000.0) as usize;
+                    if fade_len > 0 && !prev_prog.prog.is_empty() {
+                        // The input buffers might have been swapped above:
+                        if !prev_prog.locked_buffers {
+                            prev_prog.assign_outputs();
+                        }
+
+                        self.crossfade.start(prev_prog, &self.prog, &self.nodes, fade_len);
+                    } else {
+                        let _ = self.shared.graph_drop_prod.push(DropMsg::Prog { prog: prev_prog });
+                    }
 
                     log(|w| {
                         let _ = write!(
@@ -329,19 +929,113 @@ impl NodeExecutor {
                 GraphMessage::ParamUpdate { input_idx, value } => {
                     self.set_param(input_idx, value);
                 }
+                GraphMessage::AtomUpdateAt { at_idx, value, frame } => {
+                    self.push_timed_update(frame, TimedUpdate::Atom { at_idx, value });
+                }
+                GraphMessage::ParamUpdateAt { input_idx, value, frame } => {
+                    self.push_timed_update(frame, TimedUpdate::Param { input_idx, value });
+                }
                 GraphMessage::ModamtUpdate { mod_idx, modamt } => {
                     self.set_modamt(mod_idx, modamt);
                 }
                 GraphMessage::SetMonitor { bufs } => {
                     self.monitor_signal_cur_inp_indices = bufs;
                 }
+                GraphMessage::NoteOn { note, vel } => {
+                    self.exec_ctx.voices.note_on(note, vel);
+                }
+                GraphMessage::NoteOff { note } => {
+                    self.exec_ctx.voices.note_off(note);
+                }
+                GraphMessage::SetVoices { count, steal } => {
+                    self.exec_ctx.voices.set_voices(count, steal);
+                }
+                GraphMessage::SetDspProfiling { enabled } => {
+                    self.shared.load_meter.set_enabled(enabled);
+                }
+                GraphMessage::SetNodeState { index, state } => {
+                    // The previous program of a running crossfade shares the node:
+                    let prev = self.crossfade.prev.iter_mut().flat_map(|prev| prev.prog.iter_mut());
+                    for op in self.prog.prog.iter_mut().chain(prev).filter(|op| op.idx == index) {
+                        op.state = state;
+                    }
+                }
+                GraphMessage::SetProgCrossfade { ms } => {
+                    self.crossfade_ms = ms.max(0.0);
+                }
+                GraphMessage::SetTransport { transport } => {
+                    self.exec_ctx.transport = transport;
+                }
+                GraphMessage::SetWorkerPool { pool } => {
+                    let prev_pool = std::mem::replace(&mut self.worker_pool, pool);
+
+                    if let Some(pool) = prev_pool {
+                        let _ = self.shared.graph_drop_prod.push(DropMsg::WorkerPool { pool });
+                    }
+                }
             }
         }
     }
 
+    /// Moves the nodes and feedback delays into the larger `slots` and
+    /// sends the previous storage to the drop thread. A running crossfade
+    /// is cut short.
+    fn grow_nodes(&mut self, mut slots: Box<NodeSlots>) {
+        self.finish_crossfade();
+
+        if slots.nodes.len() < self.nodes.len()
+            || slots.feedback_delay_buffers.len() < self.exec_ctx.feedback_delay_buffers.len()
+        {
+            let _ = self.shared.graph_drop_prod.push(DropMsg::NodeSlots { slots });
+            return;
+        }
+
+        for (node, slot) in self.nodes.iter_mut().zip(slots.nodes.iter_mut()) {
+            std::mem::swap(node, slot);
+        }
+        slots.faults_reported[..self.faults_reported.len()]
+            .copy_from_slice(&self.faults_reported[..]);
+        slots.load_meter.take_settings(&self.shared.load_meter);
+
+        let fb_len = self.exec_ctx.feedback_delay_buffers.len();
+        for (fb, slot) in self
+            .exec_ctx
+            .feedback_delay_buffers
+            .iter_mut()
+            .zip(slots.feedback_delay_buffers.iter_mut())
+        {
+            std::mem::swap(fb, slot);
+        }
+        for fb in slots.feedback_delay_buffers[fb_len..].iter_mut() {
+            fb.set_sample_rate(self.sample_rate);
+        }
+
+        std::mem::swap(&mut self.nodes, &mut slots.nodes);
+        std::mem::swap(&mut self.shared.node_ctx_values, &mut slots.node_ctx_values);
+        std::mem::swap(&mut self.signal_faults, &mut slots.signal_faults);
+        std::mem::swap(&mut self.faults_reported, &mut slots.faults_reported);
+        std::mem::swap(&mut self.crossfade, &mut slots.crossfade);
+        std::mem::swap(&mut self.shared.load_meter, &mut slots.load_meter);
+        std::mem::swap(
+            &mut self.exec_ctx.feedback_delay_buffers,
+            &mut slots.feedback_delay_buffers,
+        );
+
+        log(|w| {
+            let _ = write!(w, "[dbg] Grown node capacity to {}", self.nodes.len());
+        });
+
+        let _ = self.shared.graph_drop_prod.push(DropMsg::NodeSlots { slots });
+    }
+
+    fn finish_crossfade(&mut self) {
+        self.crossfade.finish(&mut self.shared.graph_drop_prod);
+    }
+
     pub fn set_sample_rate(&mut self, sample_rate: f32) {
         self.sample_rate = sample_rate;
         self.exec_ctx.set_sample_rate(sample_rate);
+        self.shared.load_meter.set_sample_rate(sample_rate);
         for n in self.nodes.iter_mut() {
             n.set_sample_rate(sample_rate);
         }
@@ -351,6 +1045,22 @@ impl NodeExecutor {
         }
     }
 
+    /// Sets the maximum number of frames the nodes process at once.
+    /// Longer periods passed to [NodeExecutor::process] are split into
+    /// blocks of this size. Smaller blocks increase the time resolution of
+    /// parameter changes and MIDI events, at the cost of more overhead.
+    ///
+    /// The buffers are allocated for the maximum block size that was
+    /// given to [crate::nodes::new_node_engine_with_block_size] (by default
+    /// [crate::dsp::MAX_BLOCK_SIZE]), so the value is clamped to `1..=` that.
+    pub fn set_max_block_size(&mut self, max_block_size: usize) {
+        self.max_block_size = max_block_size.clamp(1, self.block_capacity);
+    }
+
+    pub fn max_block_size(&self) -> usize {
+        self.max_block_size
+    }
+
     #[inline]
     pub fn get_nodes(&self) -> &Vec<Node> {
         &self.nodes
@@ -395,6 +1105,45 @@ impl NodeExecutor {
         }
     }
 
+    fn push_timed_update(&mut self, frame: usize, upd: TimedUpdate) {
+        // If too many updates are pending, apply it right away:
+        if let Some(upd) = self.timed_updates.push(frame, upd) {
+            self.apply_timed_update(upd);
+        }
+    }
+
+    fn apply_timed_update(&mut self, upd: TimedUpdate) {
+        let prog = &mut self.prog;
+
+        match upd {
+            TimedUpdate::Atom { at_idx, value } => {
+                if at_idx >= prog.atoms.len() {
+                    let _ = self.shared.graph_drop_prod.push(DropMsg::Atom { atom: value });
+                    return;
+                }
+
+                let garbage = std::mem::replace(&mut prog.atoms[at_idx], value);
+                let _ = self.shared.graph_drop_prod.push(DropMsg::Atom { atom: garbage });
+            }
+            TimedUpdate::Param { input_idx, value } => {
+                if input_idx >= prog.params.len() {
+                    return;
+                }
+
+                // The value is not smoothed, stop any running smoother:
+                for (sm_inp_idx, smoother) in self.smoothers.iter_mut() {
+                    if *sm_inp_idx == input_idx {
+                        smoother.stop();
+                    }
+                }
+                self.target_refresh.retain(|(idx, _)| *idx != input_idx);
+
+                prog.params[input_idx] = value;
+                prog.inp[input_idx].fill(value);
+            }
+        }
+    }
+
     #[inline]
     fn process_smoothers(&mut self, nframes: usize) {
         let prog = &mut self.prog;
@@ -419,6 +1168,9 @@ impl NodeExecutor {
         }
     }
 
+    /// Processes the audio period of `ctx`. Periods of any length can be
+    /// passed, they are split into blocks of [NodeExecutor::max_block_size].
+    /// Call [NodeExecutor::process_graph_updates] before this.
     #[inline]
     pub fn process<T: NodeAudioContext>(&mut self, ctx: &mut T) {
         // let tb = std::time::Instant::now();
@@ -430,50 +1182,142 @@ impl NodeExecutor {
             });
         }
 
+        while let Some(event) = self.midi_con.pop() {
+            self.exec_ctx.midi.push(event);
+        }
+
+        let nframes = ctx.nframes();
+
+        self.shared.load_meter.start_block();
+
+        if nframes <= self.max_block_size {
+            self.process_timed(ctx);
+        } else {
+            // Split periods that are longer than the buffers of the nodes:
+            let mut offs = 0;
+            while offs < nframes {
+                let len = self.max_block_size.min(nframes - offs);
+                self.process_timed(&mut SubBlockContext { ctx, offs, nframes: len });
+                offs += len;
+            }
+        }
+
+        self.shared.load_meter.end_block(nframes);
+    }
+
+    #[inline]
+    fn process_timed<T: NodeAudioContext>(&mut self, ctx: &mut T) {
+        let nframes = ctx.nframes();
+
+        if self.timed_updates.next_frame().map(|f| f >= nframes).unwrap_or(true) {
+            self.process_block(ctx);
+            self.timed_updates.advance(nframes);
+            return;
+        }
+
+        // Split the block, so that the timed updates are applied
+        // exactly at their frame:
+        let mut offs = 0;
+        while offs < nframes {
+            while let Some(upd) = self.timed_updates.pop_due() {
+                self.apply_timed_update(upd);
+            }
+
+            let len = self.timed_updates.next_frame().unwrap_or(nframes).min(nframes - offs);
+
+            self.process_block(&mut SubBlockContext { ctx, offs, nframes: len });
+            self.timed_updates.advance(len);
+            offs += len;
+        }
+    }
+
+    #[inline]
+    fn process_block<T: NodeAudioContext>(&mut self, ctx: &mut T) {
         self.process_smoothers(ctx.nframes());
 
-        let nodes = &mut self.nodes;
-        let ctx_vals = &mut self.shared.node_ctx_values;
+        self.exec_ctx.midi.start_block(ctx.nframes());
+
         let prog = &mut self.prog;
         let exec_ctx = &mut self.exec_ctx;
-
-        let prog_out_fb = prog.out_feedback.input_buffer();
+        let timer = self.shared.load_meter.node_timer();
+        let view = ProgView::new(
+            &mut self.nodes,
+            prog,
+            &self.shared.node_ctx_values,
+            &mut self.signal_faults,
+            timer,
+        );
 
         let nframes = ctx.nframes();
 
-        for op in prog.prog.iter() {
-            let out = op.out_idxlen;
-            let inp = op.in_idxlen;
-            let at = op.at_idxlen;
-            let md = op.mod_idxlen;
-            let ctx_idx = op.idx as usize * 2;
+        if self.crossfade.is_active() {
+            let xfade = &mut self.crossfade;
+            xfade.clear_buffers(nframes);
+
+            unsafe {
+                view.run(
+                    prog,
+                    &mut CaptureContext { ctx, bufs: &mut xfade.new_bufs },
+                    exec_ctx,
+                    self.worker_pool.as_deref_mut(),
+                );
+            }
 
-            for modop in prog.modops[md.0..md.1].iter_mut() {
-                modop.process(nframes);
+            // The previous program is executed after the new one,
+            // so that it can copy the outputs of the shared nodes:
+            if let Some(prev) = xfade.prev.as_mut() {
+                let prev_view = ProgView::new(
+                    &mut self.nodes,
+                    prev,
+                    &self.shared.node_ctx_values,
+                    &mut self.signal_faults,
+                    timer,
+                );
+                let mut prev_ctx = CaptureContext { ctx, bufs: &mut xfade.prev_bufs };
+
+                for (op, fade_op) in prev.prog.iter().zip(xfade.ops.iter()) {
+                    match fade_op {
+                        FadeOp::Process => unsafe {
+                            prev_view.process_op(op, &mut prev_ctx, exec_ctx)
+                        },
+                        FadeOp::Copy { src, len } => unsafe {
+                            prev_view.copy_op(op, &view, *src, *len, nframes)
+                        },
+                        FadeOp::Clone { slot } => unsafe {
+                            prev_view.process_node(
+                                op,
+                                &mut xfade.nodes[*slot],
+                                &mut prev_ctx,
+                                exec_ctx,
+                            )
+                        },
+                    }
+                }
             }
 
-            nodes[op.idx as usize].process(
-                ctx,
-                exec_ctx,
-                &NodeContext {
-                    out_connected: op.out_connected,
-                    in_connected: op.in_connected,
-                    params: &prog.inp[inp.0..inp.1],
-                },
-                &prog.atoms[at.0..at.1],
-                &prog.cur_inp[inp.0..inp.1],
-                &mut prog.out[out.0..out.1],
-                &ctx_vals[ctx_idx..ctx_idx + 2],
-            );
-
-            let last_frame_idx = nframes - 1;
-            for (pb, out_buf_idx) in prog.out[out.0..out.1].iter().zip(out.0..out.1) {
-                prog_out_fb[out_buf_idx] = pb.read(last_frame_idx);
+            if xfade.mix(ctx) {
+                xfade.finish(&mut self.shared.graph_drop_prod);
             }
+        } else {
+            unsafe { view.run(prog, ctx, exec_ctx, self.worker_pool.as_deref_mut()) };
         }
 
         prog.out_feedback.publish();
 
+        // Each faulty node is only reported once, until the program changes:
+        for (node_idx, fault) in self.signal_faults.iter_mut().enumerate() {
+            if let Some((output, kind)) = fault.take() {
+                if !self.faults_reported[node_idx] {
+                    self.faults_reported[node_idx] = true;
+                    let _ =
+                        self.shared.signal_fault_prod.push(FaultReport { node_idx, output, kind });
+                }
+            }
+        }
+
+        exec_ctx.midi.end_block(nframes);
+        exec_ctx.transport.advance(nframes, self.sample_rate);
+
         self.shared.monitor_backend.check_recycle();
 
         // let ta = std::time::Instant::now();
@@ -512,7 +1356,8 @@ impl NodeExecutor {
     /// * `realtime`: If this is set, the function will sleep.
     ///
     /// You can use it's source as reference for your own audio
-    /// DSP thread processing function.
+    /// DSP thread processing function. For rendering patches at other
+    /// sample rates or into WAV files use [crate::render::OfflineRenderer].
     pub fn test_run(&mut self, seconds: f32, realtime: bool) -> (Vec<f32>, Vec<f32>) {
         const SAMPLE_RATE: f32 = 44100.0;
         self.set_sample_rate(SAMPLE_RATE);
@@ -47,9 +47,10 @@ impl Node {
 pub struct NodeGraphOrdering {
     node2idx: HashMap<NodeId, usize>,
     node_count: usize,
-    nodes: [Node; MAX_ALLOCATED_NODES],
+    /// Grows, if more than [MAX_ALLOCATED_NODES] nodes are added.
+    nodes: Vec<Node>,
 
-    in_degree: [usize; MAX_ALLOCATED_NODES],
+    in_degree: Vec<usize>,
 }
 
 impl NodeGraphOrdering {
@@ -57,8 +58,8 @@ impl NodeGraphOrdering {
         Self {
             node2idx: HashMap::new(),
             node_count: 0,
-            nodes: [Node::new(); MAX_ALLOCATED_NODES],
-            in_degree: [0; MAX_ALLOCATED_NODES],
+            nodes: vec![Node::new(); MAX_ALLOCATED_NODES],
+            in_degree: vec![0; MAX_ALLOCATED_NODES],
         }
     }
 
@@ -74,6 +75,11 @@ impl NodeGraphOrdering {
             let idx = self.node_count;
             self.node_count += 1;
 
+            if idx >= self.nodes.len() {
+                self.nodes.push(Node::new());
+                self.in_degree.push(0);
+            }
+
             self.nodes[idx].clear();
             self.nodes[idx].node_id = node_id;
             self.node2idx.insert(node_id, idx);
@@ -101,9 +107,9 @@ impl NodeGraphOrdering {
     }
 
     pub fn has_path(&self, from_node_id: NodeId, to_node_id: NodeId) -> Option<bool> {
-        let mut visited_set: HashSet<NodeId> = HashSet::with_capacity(MAX_ALLOCATED_NODES);
+        let mut visited_set: HashSet<NodeId> = HashSet::with_capacity(self.node_count);
 
-        let mut node_stack = Vec::with_capacity(MAX_ALLOCATED_NODES);
+        let mut node_stack = Vec::with_capacity(self.node_count);
         node_stack.push(from_node_id);
 
         while let Some(node_id) = node_stack.pop() {
@@ -133,7 +139,7 @@ impl NodeGraphOrdering {
     /// and no proper order can be computed. `out` will be cleared
     /// in this case.
     pub fn calculate_order(&mut self, out: &mut Vec<NodeId>) -> bool {
-        let mut deq = std::collections::VecDeque::with_capacity(MAX_ALLOCATED_NODES);
+        let mut deq = std::collections::VecDeque::with_capacity(self.node_count);
 
         for indeg in self.in_degree.iter_mut() {
             *indeg = 0;
@@ -341,6 +347,24 @@ mod tests {
         assert!(!ng.calculate_order(&mut out));
     }
 
+    #[test]
+    fn check_ngraph_order_grow() {
+        let mut ng = NodeGraphOrdering::new();
+
+        for i in 0..300 {
+            ng.add_node(NodeId::Amp(i));
+        }
+        for i in 1..300 {
+            ng.add_edge(NodeId::Amp(300 - i), NodeId::Amp(299 - i));
+        }
+
+        let mut out = vec![];
+        assert!(ng.calculate_order(&mut out));
+        assert_eq!(out.len(), 300);
+        assert_eq!(out[0], NodeId::Amp(299));
+        assert_eq!(out[299], NodeId::Amp(0));
+    }
+
     #[test]
     fn check_ngraph_clear() {
         let mut ng = NodeGraphOrdering::new();
@@ -25,10 +25,11 @@ impl Drop for ModOp {
 }
 
 impl ModOp {
-    pub fn new() -> Self {
+    /// Creates a modulation op for blocks of up to `block_size` frames.
+    pub fn new(block_size: usize) -> Self {
         Self {
             amount: 0.0,
-            modbuf: ProcBuf::new(),
+            modbuf: ProcBuf::with_len(block_size),
             outbuf: ProcBuf::null(),
             inbuf: ProcBuf::null(),
         }
@@ -65,12 +66,46 @@ impl ModOp {
     }
 }
 
+/// The processing state of a node, which can be changed without
+/// rebuilding the [NodeProg], see [crate::nodes::NodeConfigurator::set_node_state].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum NodeState {
+    /// The node is processed.
+    #[default]
+    Active,
+    /// The node is not processed, the first input is passed
+    /// to the first output. Any other outputs are 0.0.
+    Bypassed,
+    /// The node is not processed, all outputs are 0.0.
+    Muted,
+}
+
+impl NodeState {
+    /// The name of the state in the [crate::matrix_repr::MatrixRepr].
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeState::Active => "active",
+            NodeState::Bypassed => "bypass",
+            NodeState::Muted => "mute",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "active" => Some(NodeState::Active),
+            "bypass" => Some(NodeState::Bypassed),
+            "mute" => Some(NodeState::Muted),
+            _ => None,
+        }
+    }
+}
+
 /// Step in a `NodeProg` that stores the to be
 /// executed node and output operations.
 #[derive(Debug, Clone)]
 pub struct NodeOp {
     /// Stores the index of the node
-    pub idx: u8,
+    pub idx: usize,
     /// Output index and length of the node:
     pub out_idxlen: (usize, usize),
     /// Input index and length of the node:
@@ -89,6 +124,20 @@ pub struct NodeOp {
     /// A bit mask which indicates which of the output ports are actually
     /// used/connected to some input.
     pub out_connected: u64,
+    /// Start index of the smoothed parameter inputs in `NodeProg::inp`.
+    /// This is the start of `in_idxlen` for all nodes except the voice copies,
+    /// which share the parameters of the node they were copied from.
+    pub param_idx: usize,
+    /// The voice this node plays, see also [crate::nodes::VoiceAllocator].
+    pub voice: u8,
+    /// Output buffers that are summed up after this node was executed,
+    /// (<bus out vec index>, <out vec indices of all voices>).
+    pub voice_sums: Vec<(usize, Vec<usize>)>,
+    /// If set, the node is always executed on the audio thread,
+    /// see also [crate::dsp::NodeId::is_context_bound].
+    pub context_bound: bool,
+    /// Whether the node is processed, bypassed or muted.
+    pub state: NodeState,
 }
 
 impl NodeOp {
@@ -147,10 +196,35 @@ impl std::fmt::Display for NodeOp {
             }
         }
 
+        if self.voice > 0 {
+            write!(f, " voice={} params={}", self.voice, self.param_idx)?;
+        }
+
+        if self.state != NodeState::Active {
+            write!(f, " state={}", self.state.name())?;
+        }
+
+        for (bus_idx, srcs) in self.voice_sums.iter() {
+            write!(f, " sum=(o{:?} => o{})", srcs, bus_idx)?;
+        }
+
         write!(f, ")")
     }
 }
 
+/// A level of the [NodeProg] for the parallel execution. The ops of a level
+/// only depend on the outputs of the ops in the previous levels,
+/// see also [NodeProg::build_levels].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgLevel {
+    /// Index and length in [NodeProg::schedule] of the context bound ops,
+    /// which are executed on the audio thread in their order.
+    pub serial: (usize, usize),
+    /// Index and length in [NodeProg::schedule] of the ops that
+    /// may be executed by the worker threads.
+    pub parallel: (usize, usize),
+}
+
 /// A node graph execution program. It comes with buffers
 /// for the inputs, outputs and node parameters (knob values).
 #[derive(Debug)]
@@ -182,6 +256,13 @@ pub struct NodeProg {
     /// The modulators for the input parameters.
     pub modops: Vec<ModOp>,
 
+    /// The indices into `prog`, sorted by the level they are executed in.
+    pub schedule: Vec<usize>,
+
+    /// The levels of the program for the parallel execution. If this is
+    /// empty, the program can only be executed sequentially.
+    pub levels: Vec<ProgLevel>,
+
     /// A marker, that checks if we can still swap buffers with
     /// with other NodeProg instances. This is usally set if the ProcBuf pointers
     /// have been copied into `cur_inp`. You can call `unlock_buffers` to
@@ -233,6 +314,8 @@ impl NodeProg {
             atoms: vec![],
             prog: vec![],
             modops: vec![],
+            schedule: vec![],
+            levels: vec![],
             out_feedback: input_fb,
             out_fb_cons: Some(output_fb),
             locked_buffers: false,
@@ -240,16 +323,24 @@ impl NodeProg {
         }
     }
 
-    pub fn new(out_len: usize, inp_len: usize, at_len: usize, mod_len: usize) -> Self {
+    /// Creates a program with buffers for blocks of up to `block_size` frames,
+    /// see also [crate::nodes::new_node_engine_with_block_size].
+    pub fn new(
+        out_len: usize,
+        inp_len: usize,
+        at_len: usize,
+        mod_len: usize,
+        block_size: usize,
+    ) -> Self {
         let mut out = vec![];
-        out.resize_with(out_len, ProcBuf::new);
+        out.resize_with(out_len, || ProcBuf::with_len(block_size));
 
         let out_fb = vec![0.0; out_len];
         let tb = TripleBuffer::new(out_fb);
         let (input_fb, output_fb) = tb.split();
 
         let mut inp = vec![];
-        inp.resize_with(inp_len, ProcBuf::new);
+        inp.resize_with(inp_len, || ProcBuf::with_len(block_size));
         let mut cur_inp = vec![];
         cur_inp.resize_with(inp_len, ProcBuf::null);
 
@@ -258,7 +349,7 @@ impl NodeProg {
         let mut atoms = vec![];
         atoms.resize(at_len, SAtom::setting(0));
         let mut modops = vec![];
-        modops.resize_with(mod_len, ModOp::new);
+        modops.resize_with(mod_len, || ModOp::new(block_size));
 
         Self {
             out,
@@ -268,6 +359,8 @@ impl NodeProg {
             atoms,
             modops,
             prog: vec![],
+            schedule: vec![],
+            levels: vec![],
             out_feedback: input_fb,
             out_fb_cons: Some(output_fb),
             locked_buffers: false,
@@ -303,6 +396,37 @@ impl NodeProg {
         self.prog.push(node_op);
     }
 
+    /// Marks the output `out_index` as connected in the [NodeOp]
+    /// it belongs to.
+    pub fn set_out_connected(&mut self, out_index: usize) {
+        for n_op in self.prog.iter_mut() {
+            if n_op.out_idx_belongs_to_nodeop(out_index) {
+                n_op.set_out_idx_connected_flag(out_index);
+            }
+        }
+    }
+
+    /// Lets the node `op_idx` write the sum of the `src_out_indices` into
+    /// the output buffer `bus_index`, right after it was executed.
+    /// This is used to mix the voices of the voice region.
+    pub fn append_voice_sum(
+        &mut self,
+        op_idx: usize,
+        bus_index: usize,
+        src_out_indices: Vec<usize>,
+    ) {
+        if self.prog.iter().any(|n_op| n_op.voice_sums.iter().any(|(b, _)| *b == bus_index)) {
+            return;
+        }
+
+        for n_op in self.prog.iter_mut() {
+            if n_op.idx == op_idx {
+                n_op.voice_sums.push((bus_index, src_out_indices));
+                return;
+            }
+        }
+    }
+
     pub fn append_edge(
         &mut self,
         node_op: NodeOp,
@@ -310,11 +434,7 @@ impl NodeProg {
         out_index: usize,
         mod_index: Option<usize>,
     ) {
-        for n_op in self.prog.iter_mut() {
-            if n_op.out_idx_belongs_to_nodeop(out_index) {
-                n_op.set_out_idx_connected_flag(out_index);
-            }
-        }
+        self.set_out_connected(out_index);
 
         for n_op in self.prog.iter_mut() {
             if n_op.idx == node_op.idx {
@@ -325,6 +445,79 @@ impl NodeProg {
         }
     }
 
+    /// Partitions the `prog` into levels for the parallel execution.
+    /// Each op is placed in the level after the last level that writes
+    /// one of it's inputs. The context bound ops keep their order, by placing
+    /// each of them in the same or a later level than the previous one.
+    ///
+    /// This is called by [crate::nodes::NodeConfigurator::upload_prog].
+    pub fn build_levels(&mut self) {
+        self.schedule.clear();
+        self.levels.clear();
+
+        // The op that writes each output buffer:
+        let mut writers: Vec<Option<usize>> = vec![None; self.out.len()];
+        for (op_pos, op) in self.prog.iter().enumerate() {
+            for out_idx in op.out_idxlen.0..op.out_idxlen.1 {
+                if let Some(w) = writers.get_mut(out_idx) {
+                    *w = Some(op_pos);
+                }
+            }
+
+            for (bus_idx, _) in op.voice_sums.iter() {
+                if let Some(w) = writers.get_mut(*bus_idx) {
+                    *w = Some(op_pos);
+                }
+            }
+        }
+
+        let mut op_levels = vec![0; self.prog.len()];
+        let mut last_bound_level = None;
+        let mut level_count = 0;
+
+        for (op_pos, op) in self.prog.iter().enumerate() {
+            let deps = op
+                .inputs
+                .iter()
+                .map(|io| io.0)
+                .chain(op.voice_sums.iter().flat_map(|(_, srcs)| srcs.iter().copied()));
+
+            let mut level = 0;
+            for out_idx in deps {
+                match writers.get(out_idx).copied().flatten() {
+                    Some(w) if w < op_pos => level = level.max(op_levels[w] + 1),
+                    Some(w) if w == op_pos => (),
+                    // Only a sequential execution keeps the order
+                    // of a program that is not sorted:
+                    Some(_) => return,
+                    None => (),
+                }
+            }
+
+            if op.context_bound {
+                if let Some(bound_level) = last_bound_level {
+                    level = level.max(bound_level);
+                }
+                last_bound_level = Some(level);
+            }
+
+            op_levels[op_pos] = level;
+            level_count = level_count.max(level + 1);
+        }
+
+        for level in 0..level_count {
+            let ops = (0..self.prog.len()).filter(|op_pos| op_levels[*op_pos] == level);
+
+            let start = self.schedule.len();
+            self.schedule.extend(ops.clone().filter(|op_pos| self.prog[*op_pos].context_bound));
+            let split = self.schedule.len();
+            self.schedule.extend(ops.filter(|op_pos| !self.prog[*op_pos].context_bound));
+
+            self.levels
+                .push(ProgLevel { serial: (start, split), parallel: (split, self.schedule.len()) });
+        }
+    }
+
     /// This is called right after the [crate::nodes::NodeExecutor]
     /// received this NodeProg from the [crate::nodes::NodeConfigurator].
     /// It initializes internal buffers with parameter data.
@@ -382,16 +575,19 @@ impl NodeProg {
             let out_bufs = &mut self.out;
 
             let inp = op.in_idxlen;
+            // Voice copies read the parameters of the node they were copied from:
+            let params = (op.param_idx, op.param_idx + (inp.1 - inp.0));
 
             // First step (refresh inputs):
-            input_bufs[inp.0..inp.1].copy_from_slice(&self.inp[inp.0..inp.1]);
+            input_bufs[inp.0..inp.1].copy_from_slice(&self.inp[params.0..params.1]);
 
             // Second step (assign outputs):
             for io in op.inputs.iter() {
                 input_bufs[io.1] = out_bufs[io.0];
 
                 if let Some(idx) = io.2 {
-                    input_bufs[io.1] = self.modops[idx].lock(self.inp[io.1], out_bufs[io.0]);
+                    let param_buf = self.inp[params.0 + io.1 - inp.0];
+                    input_bufs[io.1] = self.modops[idx].lock(param_buf, out_bufs[io.0]);
                 }
             }
         }
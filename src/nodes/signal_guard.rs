@@ -0,0 +1,137 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use crate::dsp::{NodeId, ProcBuf};
+use std::num::FpCategory;
+
+/// The number of evenly spaced frames of each output buffer, that are
+/// checked for denormals by the signal guard in each block. NaN and
+/// infinite samples are searched in the whole buffer.
+pub const SIGNAL_GUARD_FRAMES: usize = 8;
+
+/// The maximum number of [SignalFault]s that are queued for the frontend.
+/// Further faults are dropped.
+pub const MAX_SIGNAL_FAULTS: usize = 64;
+
+/// The kind of invalid sample that was found in an output of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalFaultKind {
+    NaN,
+    Inf,
+    Denormal,
+}
+
+impl SignalFaultKind {
+    /// Returns the kind of fault of the sample `v`, if it's not a normal number.
+    #[inline]
+    pub fn of(v: f32) -> Option<Self> {
+        match v.classify() {
+            FpCategory::Nan => Some(SignalFaultKind::NaN),
+            FpCategory::Infinite => Some(SignalFaultKind::Inf),
+            FpCategory::Subnormal => Some(SignalFaultKind::Denormal),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the output buffer has to be set to 0.0 and the node
+    /// has to be reset. Denormals are only flushed to 0.0.
+    #[inline]
+    pub fn needs_reset(&self) -> bool {
+        !matches!(self, SignalFaultKind::Denormal)
+    }
+
+    /// Checks all of the first `nframes` in `buf` for NaN and infinite
+    /// samples, and [SIGNAL_GUARD_FRAMES] of them, up to and including the
+    /// last frame, for denormals. A NaN or infinite sample takes precedence
+    /// over a denormal one.
+    #[inline]
+    pub(crate) fn scan(buf: &ProcBuf, nframes: usize) -> Option<Self> {
+        let mut nan = false;
+        let mut inf = false;
+        for frame in 0..nframes {
+            let v = buf.read(frame);
+            nan |= v.is_nan();
+            inf |= v.is_infinite();
+        }
+
+        if nan {
+            return Some(SignalFaultKind::NaN);
+        } else if inf {
+            return Some(SignalFaultKind::Inf);
+        }
+
+        let step = (nframes / SIGNAL_GUARD_FRAMES).max(1);
+        (0..nframes)
+            .rev()
+            .step_by(step)
+            .take(SIGNAL_GUARD_FRAMES)
+            .find_map(|frame| SignalFaultKind::of(buf.read(frame)))
+    }
+}
+
+/// Sets all denormal samples of the first `nframes` in `buf` to 0.0.
+#[inline]
+pub(crate) fn flush_denormals(buf: &mut ProcBuf, nframes: usize) {
+    for frame in 0..nframes {
+        if buf.read(frame).is_subnormal() {
+            buf.write(frame, 0.0);
+        }
+    }
+}
+
+impl std::fmt::Display for SignalFaultKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignalFaultKind::NaN => write!(f, "NaN"),
+            SignalFaultKind::Inf => write!(f, "Inf"),
+            SignalFaultKind::Denormal => write!(f, "denormal"),
+        }
+    }
+}
+
+/// An invalid sample in the output of a node, that was detected by the
+/// [crate::nodes::NodeExecutor]. For NaN and infinite samples the output
+/// buffer was set to 0.0 and the node was reset, denormal samples were
+/// flushed to 0.0. See [crate::nodes::NodeConfigurator::update_signal_faults].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignalFault {
+    pub node_id: NodeId,
+    /// The index of the output of the node.
+    pub output: u8,
+    pub kind: SignalFaultKind,
+}
+
+/// A [SignalFault] as it's sent from the [crate::nodes::NodeExecutor],
+/// which only knows the index of the node.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FaultReport {
+    pub(crate) node_idx: usize,
+    pub(crate) output: u8,
+    pub(crate) kind: SignalFaultKind,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_scan_single_sample() {
+        let mut buf = ProcBuf::with_len(128);
+
+        // Frame 5 is between the frames that are checked for denormals:
+        buf.write(5, f32::MIN_POSITIVE / 4.0);
+        assert_eq!(SignalFaultKind::scan(&buf, 128), None);
+
+        buf.write(5, f32::INFINITY);
+        assert_eq!(SignalFaultKind::scan(&buf, 128), Some(SignalFaultKind::Inf));
+
+        buf.write(6, f32::NAN);
+        assert_eq!(SignalFaultKind::scan(&buf, 128), Some(SignalFaultKind::NaN));
+
+        // Only the first nframes are checked:
+        assert_eq!(SignalFaultKind::scan(&buf, 5), None);
+
+        buf.free();
+    }
+}
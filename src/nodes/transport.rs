@@ -0,0 +1,90 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+/// The transport state of the host, like the tempo and the song position.
+/// It's accessible via [crate::nodes::NodeExecContext::transport] and
+/// is read by the `Clock` node.
+///
+/// The host sets it with [crate::nodes::NodeExecutor::set_transport] before
+/// each processed period. While playing, the position is advanced by the
+/// [crate::nodes::NodeExecutor] after each block, so the transport keeps
+/// running if it is not updated. Positions are measured in quarter notes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transport {
+    /// The tempo in quarter notes per minute.
+    pub bpm: f64,
+    /// The numerator of the time signature, the number of beats in a bar.
+    pub sig_num: u32,
+    /// The denominator of the time signature, the note value of a beat.
+    pub sig_den: u32,
+    pub playing: bool,
+    /// The song position at the start of the block.
+    pub beat: f64,
+    /// The current bar, counted from 0.
+    pub bar: i64,
+    /// The song position of the start of the current bar.
+    pub bar_start: f64,
+}
+
+impl Transport {
+    /// A stopped transport at 120 BPM in 4/4 time.
+    pub fn new() -> Self {
+        Self {
+            bpm: 120.0,
+            sig_num: 4,
+            sig_den: 4,
+            playing: false,
+            beat: 0.0,
+            bar: 0,
+            bar_start: 0.0,
+        }
+    }
+
+    /// Sets the song position to `beat` and calculates the bar from it,
+    /// assuming the time signature did not change since the song start.
+    /// Use this, if the host does not provide the bar position.
+    pub fn set_position(&mut self, beat: f64) {
+        let bar_len = self.bar_len();
+        self.beat = beat;
+        self.bar = (beat / bar_len).floor() as i64;
+        self.bar_start = self.bar as f64 * bar_len;
+    }
+
+    /// Returns the length of a beat in quarter notes.
+    pub fn beat_len(&self) -> f64 {
+        4.0 / (self.sig_den.max(1) as f64)
+    }
+
+    /// Returns the length of a bar in quarter notes.
+    pub fn bar_len(&self) -> f64 {
+        self.sig_num.max(1) as f64 * self.beat_len()
+    }
+
+    /// Returns the number of quarter notes per sample.
+    #[inline]
+    pub fn beats_per_sample(&self, sample_rate: f32) -> f64 {
+        self.bpm.max(0.0) / (60.0 * sample_rate as f64)
+    }
+
+    /// Moves the song position `nframes` further, if the transport is playing.
+    pub fn advance(&mut self, nframes: usize, sample_rate: f32) {
+        if !self.playing {
+            return;
+        }
+
+        self.beat += nframes as f64 * self.beats_per_sample(sample_rate);
+
+        let bar_len = self.bar_len();
+        while self.beat >= self.bar_start + bar_len {
+            self.bar_start += bar_len;
+            self.bar += 1;
+        }
+    }
+}
+
+impl Default for Transport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+/// The maximum number of voices of the voice region,
+/// see also [crate::nodes::NodeConfigurator::set_voice_region].
+pub const MAX_VOICES: usize = 16;
+
+/// Selects the voice that is taken for a new note,
+/// when all voices are playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceSteal {
+    /// Steal the voice that plays the longest.
+    Oldest,
+    /// Steal the voice that plays the lowest note.
+    Lowest,
+    /// Steal the voice that plays the highest note.
+    Highest,
+    /// Don't steal any voice, the new note is dropped.
+    None,
+}
+
+/// The state of a single voice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Voice {
+    /// The MIDI note number of the most recently played note.
+    pub note: u8,
+    /// The velocity of the most recently played note (0..1).
+    pub vel: f32,
+    /// Whether the note is held.
+    pub gate: bool,
+    /// Incremented for each note that is started on this voice,
+    /// used to retrigger the gate if a playing voice is taken
+    /// for a new note.
+    pub trig_count: u32,
+    /// The time stamp of the last note on or off event.
+    age: u64,
+}
+
+impl Voice {
+    fn new() -> Self {
+        Self { note: 69, vel: 0.0, gate: false, trig_count: 0, age: 0 }
+    }
+
+    /// Returns the pitch of the voice in the normalized pitch
+    /// format of the frequency inputs: 0.0 is A4 (440Hz) and
+    /// 0.1 is one octave.
+    pub fn pitch(&self) -> f32 {
+        (self.note as f32 - 69.0) / 120.0
+    }
+}
+
+/// Assigns notes to a fixed number of voices.
+///
+/// Free voices are reused in the order they were released. If all voices
+/// are playing, a voice is stolen according to the [VoiceSteal] policy.
+/// Playing a note that is already held retriggers its voice.
+///
+///```
+/// use hexodsp::nodes::{VoiceAllocator, VoiceSteal};
+///
+/// let mut va = VoiceAllocator::new();
+/// va.set_voices(2, VoiceSteal::Oldest);
+///
+/// assert_eq!(va.note_on(60, 1.0), Some(0));
+/// assert_eq!(va.note_on(64, 1.0), Some(1));
+/// // The oldest note 60 is stolen:
+/// assert_eq!(va.note_on(67, 1.0), Some(0));
+///
+/// va.note_off(64);
+/// assert!(!va.voice(1).gate);
+///```
+#[derive(Debug, Clone)]
+pub struct VoiceAllocator {
+    voices: [Voice; MAX_VOICES],
+    count: usize,
+    steal: VoiceSteal,
+    clock: u64,
+}
+
+impl VoiceAllocator {
+    pub fn new() -> Self {
+        Self { voices: [Voice::new(); MAX_VOICES], count: 1, steal: VoiceSteal::Oldest, clock: 0 }
+    }
+
+    /// Sets the number of voices, clamped to `1..=`[MAX_VOICES],
+    /// and the stealing policy. All notes are released.
+    pub fn set_voices(&mut self, count: usize, steal: VoiceSteal) {
+        self.count = count.clamp(1, MAX_VOICES);
+        self.steal = steal;
+        self.all_notes_off();
+    }
+
+    pub fn voice_count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the state of the voice `idx`.
+    pub fn voice(&self, idx: usize) -> &Voice {
+        &self.voices[idx.min(MAX_VOICES - 1)]
+    }
+
+    /// Starts the note and returns the voice it is played on.
+    /// Returns `None` if all voices are playing and [VoiceSteal::None]
+    /// is selected.
+    pub fn note_on(&mut self, note: u8, vel: f32) -> Option<usize> {
+        self.clock += 1;
+
+        let active = &self.voices[0..self.count];
+        let idx = if let Some(idx) = active.iter().position(|v| v.gate && v.note == note) {
+            idx
+        } else if let Some(idx) = Self::find_min(active, |v| !v.gate, |v| v.age) {
+            idx
+        } else {
+            match self.steal {
+                VoiceSteal::Oldest => Self::find_min(active, |_| true, |v| v.age)?,
+                VoiceSteal::Lowest => Self::find_min(active, |_| true, |v| v.note as u64)?,
+                VoiceSteal::Highest => {
+                    Self::find_min(active, |_| true, |v| u8::MAX as u64 - v.note as u64)?
+                }
+                VoiceSteal::None => return None,
+            }
+        };
+
+        let voice = &mut self.voices[idx];
+        voice.note = note;
+        voice.vel = vel;
+        voice.gate = true;
+        voice.trig_count = voice.trig_count.wrapping_add(1);
+        voice.age = self.clock;
+
+        Some(idx)
+    }
+
+    /// Releases the voice that plays `note`.
+    pub fn note_off(&mut self, note: u8) {
+        self.clock += 1;
+
+        for voice in self.voices[0..self.count].iter_mut() {
+            if voice.gate && voice.note == note {
+                voice.gate = false;
+                voice.age = self.clock;
+            }
+        }
+    }
+
+    /// Releases all voices.
+    pub fn all_notes_off(&mut self) {
+        for voice in self.voices.iter_mut() {
+            voice.gate = false;
+        }
+    }
+
+    fn find_min<F: Fn(&Voice) -> bool, K: Fn(&Voice) -> u64>(
+        voices: &[Voice],
+        filter: F,
+        key: K,
+    ) -> Option<usize> {
+        voices
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| filter(v))
+            .min_by_key(|(_, v)| key(v))
+            .map(|(i, _)| i)
+    }
+}
+
+impl Default for VoiceAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+use super::node_exec::flush_denormals_to_zero;
+use super::NodeExecContext;
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// The time the idle workers spin, waiting for the next job.
+const WORKER_SPIN_TIME: Duration = Duration::from_millis(2);
+/// After this time without jobs the workers sleep between the
+/// checks for new jobs, instead of only yielding.
+const WORKER_YIELD_TIME: Duration = Duration::from_millis(100);
+const WORKER_SLEEP_TIME: Duration = Duration::from_micros(100);
+
+/// A job of the [WorkerPool], called with the item index
+/// and the [NodeExecContext] of the executing thread.
+pub(crate) type PoolJob<'a> = dyn Fn(usize, &mut NodeExecContext) + Sync + 'a;
+
+struct PoolShared {
+    /// The items of the current job, packed as
+    /// `(<generation> << 32) | (<item count> << 16) | <next item>`.
+    /// The generation prevents stale workers from claiming the
+    /// items of a later job.
+    claim: AtomicU64,
+    /// The number of finished items of the current job.
+    done: AtomicUsize,
+    /// The current job, only valid while items can be claimed.
+    job: UnsafeCell<Option<*const PoolJob<'static>>>,
+    /// One context for the audio thread and one for each worker.
+    contexts: Vec<UnsafeCell<NodeExecContext>>,
+    stop: AtomicBool,
+}
+
+unsafe impl Send for PoolShared {}
+unsafe impl Sync for PoolShared {}
+
+impl PoolShared {
+    /// Claims and executes items of the current job, until all are claimed.
+    /// Returns true if an item was executed.
+    fn work(&self, ctx_idx: usize) -> bool {
+        let mut worked = false;
+        let mut claim = self.claim.load(Ordering::Acquire);
+
+        loop {
+            let count = (claim >> 16) & 0xFFFF;
+            let item = claim & 0xFFFF;
+            if item >= count {
+                return worked;
+            }
+
+            match self.claim.compare_exchange_weak(
+                claim,
+                claim + 1,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    // The job stays valid until the claimed item is done:
+                    unsafe {
+                        if let Some(job) = *self.job.get() {
+                            (*job)(item as usize, &mut *self.contexts[ctx_idx].get());
+                        }
+                    }
+
+                    self.done.fetch_add(1, Ordering::Release);
+                    worked = true;
+                    claim = self.claim.load(Ordering::Acquire);
+                }
+                Err(cur) => claim = cur,
+            }
+        }
+    }
+}
+
+/// A pool of worker threads that execute the independent nodes of
+/// a level of the [crate::nodes::NodeProg] together with the audio thread,
+/// see also [crate::nodes::NodeConfigurator::set_worker_threads].
+///
+/// The pool is created by the frontend and sent to the
+/// [crate::nodes::NodeExecutor]. Running a job does not allocate or lock,
+/// the workers wait for jobs by spinning. The audio thread works on the
+/// job too, so it never waits for a worker that did not start yet.
+pub struct WorkerPool {
+    shared: Arc<PoolShared>,
+    threads: Vec<JoinHandle<()>>,
+    generation: u64,
+}
+
+impl WorkerPool {
+    pub(crate) fn new(worker_count: usize) -> Self {
+        let mut contexts = vec![];
+        contexts.resize_with(worker_count + 1, || UnsafeCell::new(NodeExecContext::detached()));
+
+        let shared = Arc::new(PoolShared {
+            claim: AtomicU64::new(0),
+            done: AtomicUsize::new(0),
+            job: UnsafeCell::new(None),
+            contexts,
+            stop: AtomicBool::new(false),
+        });
+
+        let threads = (0..worker_count)
+            .map(|i| {
+                let shared = shared.clone();
+                std::thread::Builder::new()
+                    .name(format!("hexodsp worker {}", i))
+                    .spawn(move || worker_loop(&shared, i + 1))
+                    .expect("Spawning worker thread")
+            })
+            .collect();
+
+        Self { shared, threads, generation: 0 }
+    }
+
+    /// Calls the `job` for each of the `count` items and returns
+    /// after all of them are finished. The items are distributed among
+    /// the calling thread and the workers.
+    pub(crate) fn run(&mut self, count: usize, job: &PoolJob<'_>) {
+        if count == 0 {
+            return;
+        }
+        assert!(count <= 0xFFFF);
+
+        let shared = &*self.shared;
+
+        // The lifetime is extended only for the duration of this call:
+        unsafe {
+            *shared.job.get() =
+                Some(std::mem::transmute::<*const PoolJob<'_>, *const PoolJob<'static>>(job));
+        }
+
+        shared.done.store(0, Ordering::Relaxed);
+        self.generation = (self.generation + 1) & 0xFFFF_FFFF;
+        shared.claim.store((self.generation << 32) | ((count as u64) << 16), Ordering::Release);
+
+        shared.work(0);
+
+        while shared.done.load(Ordering::Acquire) < count {
+            std::hint::spin_loop();
+        }
+
+        unsafe {
+            *shared.job.get() = None;
+        }
+    }
+}
+
+fn worker_loop(shared: &PoolShared, ctx_idx: usize) {
+    // The same floating point behaviour as on the audio thread:
+    flush_denormals_to_zero();
+
+    let mut last_work = Instant::now();
+
+    while !shared.stop.load(Ordering::Relaxed) {
+        if shared.work(ctx_idx) {
+            last_work = Instant::now();
+            continue;
+        }
+
+        let idle = last_work.elapsed();
+        if idle < WORKER_SPIN_TIME {
+            std::hint::spin_loop();
+        } else if idle < WORKER_YIELD_TIME {
+            std::thread::yield_now();
+        } else {
+            std::thread::sleep(WORKER_SLEEP_TIME);
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+
+        for th in self.threads.drain(..) {
+            let _ = th.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for WorkerPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkerPool").field("workers", &self.threads.len()).finish()
+    }
+}
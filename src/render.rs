@@ -0,0 +1,310 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+/*! Deterministic offline rendering of DSP graphs.
+
+Unlike [crate::NodeExecutor::test_run], which is hard wired to 44100 Hz and
+blocks of [crate::dsp::MAX_BLOCK_SIZE] frames, the [OfflineRenderer] lets you
+choose the sample rate and block size. It renders the output of the `Out`
+node into memory as fast as possible, from where it can be written to a WAV
+file:
+
+```
+use hexodsp::*;
+use hexodsp::render::OfflineRenderer;
+
+let (node_conf, mut node_exec) = new_node_engine();
+let mut matrix = Matrix::new(node_conf, 3, 3);
+
+let mut chain = MatrixCellChain::new(CellDir::B);
+chain.node_out("sin", "sig").node_inp("out", "ch1");
+chain.place(&mut matrix, 0, 0).unwrap();
+matrix.sync().unwrap();
+
+let audio = OfflineRenderer::new(48000.0).channels(1).render(&mut node_exec, 0.5);
+
+assert_eq!(audio.len(), 24000);
+// audio.write_wav("sine.wav").unwrap();
+```
+
+Rendering a patch that was loaded with [crate::matrix_repr::MatrixRepr]
+does not even need a [crate::Matrix], see [OfflineRenderer::render_repr].
+
+The output is deterministic: Rendering the same patch with the same
+settings always results in the same samples. Changing the block size
+does not change the output of simple patches like an oscillator into
+the `Out` node, but nodes that sample their inputs once per block
+may render slightly differently.
+*/
+
+use crate::dsp::{MAX_BLOCK_SIZE, MAX_OUTPUT_CHANNELS};
+use crate::matrix::MatrixError;
+use crate::matrix_repr::MatrixRepr;
+use crate::nodes::{new_node_engine, NodeExecutor};
+use crate::{Context, Matrix};
+
+/// The output needs to stay below the tail threshold for this long
+/// for the tail rendering to stop.
+const TAIL_SILENCE_MS: f32 = 100.0;
+
+/// Renders the output of a DSP graph offline, see also the [crate::render]
+/// module documentation.
+#[derive(Debug, Clone, Copy)]
+pub struct OfflineRenderer {
+    sample_rate: f32,
+    block_size: usize,
+    channels: usize,
+    pre_roll: f32,
+    tail: Option<(f32, f32)>,
+}
+
+impl OfflineRenderer {
+    /// Creates a renderer for stereo output at the given sample rate,
+    /// processing blocks of [crate::dsp::MAX_BLOCK_SIZE] frames.
+    pub fn new(sample_rate: f32) -> Self {
+        Self { sample_rate, block_size: MAX_BLOCK_SIZE, channels: 2, pre_roll: 0.0, tail: None }
+    }
+
+    /// Sets the number of frames that are passed to [NodeExecutor::process]
+    /// at once, like the period size of an audio driver. Blocks longer than
+    /// [NodeExecutor::max_block_size] are split by the executor.
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Sets the number of rendered output channels. The value is clamped
+    /// to `1..=`[crate::dsp::MAX_OUTPUT_CHANNELS].
+    pub fn channels(mut self, channels: usize) -> Self {
+        self.channels = channels.clamp(1, MAX_OUTPUT_CHANNELS);
+        self
+    }
+
+    /// Sets the time in seconds that is rendered and discarded before the
+    /// actual output. Useful to let oscillators, reverbs and LFOs settle.
+    pub fn pre_roll(mut self, seconds: f32) -> Self {
+        self.pre_roll = seconds.max(0.0);
+        self
+    }
+
+    /// Enables the tail detection: After the requested duration, rendering
+    /// continues until the absolute value of all output channels stayed
+    /// below `threshold` for 100ms, but at most for `max_seconds`.
+    pub fn tail(mut self, threshold: f32, max_seconds: f32) -> Self {
+        self.tail = Some((threshold, max_seconds.max(0.0)));
+        self
+    }
+
+    /// Renders `seconds` of audio with the given [crate::NodeExecutor].
+    ///
+    /// If you build your patch with a [crate::Matrix], make sure to
+    /// call [crate::Matrix::sync] before rendering.
+    pub fn render(&self, node_exec: &mut NodeExecutor, seconds: f32) -> RenderedAudio {
+        self.render_input(node_exec, &[], seconds)
+    }
+
+    /// Like [OfflineRenderer::render], but feeds the `input` channels to
+    /// the audio input of the graph, which can be read with the `In` node.
+    /// Use this to process a file with an effect patch.
+    ///
+    /// The input starts with the rendered output, the pre-roll and
+    /// the tail receive silence.
+    pub fn render_input(
+        &self,
+        node_exec: &mut NodeExecutor,
+        input: &[&[f32]],
+        seconds: f32,
+    ) -> RenderedAudio {
+        node_exec.set_sample_rate(self.sample_rate);
+        node_exec.process_graph_updates();
+
+        let mut blocks = BlockRenderer::new(self.block_size, input.len(), self.channels);
+
+        let pre_roll = self.secs_to_frames(self.pre_roll);
+        let mut discard = vec![vec![]; self.channels];
+        blocks.render(node_exec, pre_roll, &mut discard, &[]);
+
+        let mut channels = vec![vec![]; self.channels];
+        blocks.render(node_exec, self.secs_to_frames(seconds), &mut channels, input);
+
+        if let Some((threshold, max_seconds)) = self.tail {
+            let max_frames = self.secs_to_frames(max_seconds);
+            let silence_frames = self.secs_to_frames(TAIL_SILENCE_MS / 1000.0);
+
+            let mut tail_len = 0;
+            let mut silent_len = 0;
+            while tail_len < max_frames && silent_len < silence_frames {
+                let offs = channels[0].len();
+                let nframes = self.block_size.min(max_frames - tail_len);
+                blocks.render(node_exec, nframes, &mut channels, &[]);
+
+                for i in offs..(offs + nframes) {
+                    if channels.iter().any(|ch| ch[i].abs() >= threshold) {
+                        silent_len = 0;
+                    } else {
+                        silent_len += 1;
+                        if silent_len == silence_frames {
+                            for ch in channels.iter_mut() {
+                                ch.truncate(i + 1);
+                            }
+                            break;
+                        }
+                    }
+                }
+                tail_len += nframes;
+            }
+        }
+
+        RenderedAudio { sample_rate: self.sample_rate, channels }
+    }
+
+    /// Loads the patch from `repr` into a new DSP engine and renders
+    /// `seconds` of audio with it. See also [crate::matrix_repr::load_patch_from_file].
+    pub fn render_repr(
+        &self,
+        repr: &MatrixRepr,
+        seconds: f32,
+    ) -> Result<RenderedAudio, MatrixError> {
+        let w = repr.cells.iter().map(|c| c.x + 1).max().unwrap_or(1);
+        let h = repr.cells.iter().map(|c| c.y + 1).max().unwrap_or(1);
+
+        let (node_conf, mut node_exec) = new_node_engine();
+        let mut matrix = Matrix::new(node_conf, w, h);
+        matrix.from_repr(repr)?;
+
+        Ok(self.render(&mut node_exec, seconds))
+    }
+
+    fn secs_to_frames(&self, seconds: f32) -> usize {
+        (seconds * self.sample_rate).round() as usize
+    }
+}
+
+/// Holds the input and output buffers for a block of audio.
+struct BlockRenderer {
+    block_size: usize,
+    input: Vec<Vec<f32>>,
+    output: Vec<Vec<f32>>,
+}
+
+impl BlockRenderer {
+    fn new(block_size: usize, input_channels: usize, output_channels: usize) -> Self {
+        Self {
+            block_size,
+            input: vec![vec![0.0; block_size]; input_channels.max(1)],
+            output: vec![vec![0.0; block_size]; output_channels],
+        }
+    }
+
+    /// Renders `nframes` and appends them to `channels`. The `input`
+    /// channels are fed to the graph from their start, and padded with
+    /// silence if they are shorter than `nframes`.
+    fn render(
+        &mut self,
+        node_exec: &mut NodeExecutor,
+        nframes: usize,
+        channels: &mut [Vec<f32>],
+        input: &[&[f32]],
+    ) {
+        let mut offs = 0;
+        while offs < nframes {
+            let cur_nframes = (nframes - offs).min(self.block_size);
+
+            for (i, buf) in self.input.iter_mut().enumerate() {
+                let src = input.get(i).copied().unwrap_or(&[]);
+                for (j, s) in buf[..cur_nframes].iter_mut().enumerate() {
+                    *s = src.get(offs + j).copied().unwrap_or(0.0);
+                }
+            }
+            let input_bufs: Vec<&[f32]> =
+                self.input.iter().map(|buf| &buf[..cur_nframes]).collect();
+
+            let mut output_bufs: Vec<&mut [f32]> = self
+                .output
+                .iter_mut()
+                .map(|buf| {
+                    // Stays silent if there is no Out node:
+                    buf.fill(0.0);
+                    &mut buf[..cur_nframes]
+                })
+                .collect();
+            let mut context = Context {
+                nframes: cur_nframes,
+                output: &mut output_bufs[..],
+                input: &input_bufs[..],
+            };
+
+            node_exec.process(&mut context);
+
+            for (ch, out) in channels.iter_mut().zip(self.output.iter()) {
+                ch.extend_from_slice(&out[..cur_nframes]);
+            }
+
+            offs += cur_nframes;
+        }
+    }
+}
+
+/// The audio rendered by the [OfflineRenderer].
+#[derive(Debug, Clone)]
+pub struct RenderedAudio {
+    /// The sample rate the audio was rendered at.
+    pub sample_rate: f32,
+    /// The samples of each output channel.
+    pub channels: Vec<Vec<f32>>,
+}
+
+impl RenderedAudio {
+    /// Returns the number of rendered frames.
+    pub fn len(&self) -> usize {
+        self.channels.first().map(|ch| ch.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the samples of the output channel `channel`.
+    pub fn channel(&self, channel: usize) -> &[f32] {
+        &self.channels[channel][..]
+    }
+
+    /// Writes the audio as 32-bit float WAV file to `path`.
+    pub fn write_wav(&self, path: &str) -> Result<(), hound::Error> {
+        let spec = hound::WavSpec {
+            channels: self.channels.len() as u16,
+            sample_rate: self.sample_rate as u32,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for i in 0..self.len() {
+            for ch in self.channels.iter() {
+                writer.write_sample(ch[i])?;
+            }
+        }
+        writer.finalize()
+    }
+
+    /// Writes the audio as 16-bit integer WAV file to `path`.
+    /// Samples outside of `-1..1` are clipped.
+    pub fn write_wav_i16(&self, path: &str) -> Result<(), hound::Error> {
+        let spec = hound::WavSpec {
+            channels: self.channels.len() as u16,
+            sample_rate: self.sample_rate as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for i in 0..self.len() {
+            for ch in self.channels.iter() {
+                let s = ch[i].clamp(-1.0, 1.0) * (i16::MAX as f32);
+                writer.write_sample(s as i16)?;
+            }
+        }
+        writer.finalize()
+    }
+}
@@ -30,7 +30,6 @@ impl Smoother {
     }
 
     #[inline]
-    #[allow(dead_code)]
     pub fn stop(&mut self) {
         self.done = true;
     }
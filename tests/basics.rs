@@ -1114,6 +1114,37 @@ fn check_matrix_node_feedback() {
     assert_eq!(fft_res_r[1], (904, 206));
 }
 
+#[test]
+fn check_matrix_node_feedback_instance_300() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+
+    let amp = NodeId::Amp(0);
+    let wr = NodeId::FbWr(300);
+    let rd = NodeId::FbRd(300);
+    let out = NodeId::Out(0);
+
+    matrix.place(0, 0, Cell::empty(amp).out(None, None, amp.out("sig")));
+    matrix.place(0, 1, Cell::empty(wr).input(wr.inp("inp"), None, None));
+    matrix.place(1, 0, Cell::empty(rd).out(None, None, rd.out("sig")));
+    matrix.place(1, 1, Cell::empty(out).input(out.inp("ch1"), None, None));
+    matrix.sync().unwrap();
+
+    matrix.set_param(amp.inp_param("inp").unwrap(), SAtom::param(0.5));
+
+    // The 600 FbWr and FbRd instances don't fit into the graph update queue at once:
+    for _ in 0..4 {
+        node_exec.process_graph_updates();
+        matrix.update_filters();
+    }
+
+    let (out_l, _) = run_for_ms(&mut node_exec, 50.0);
+    // The feedback delay is 3.14ms long:
+    assert_float_eq!(out_l[100], 0.0);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.5);
+    assert!(matrix.pop_error().is_none());
+}
+
 #[test]
 fn check_matrix_tseq_perf() {
     use hexodsp::dsp::tracker::UIPatternModel;
@@ -0,0 +1,167 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::{new_node_engine_with_block_size, MidiEvent};
+use hexodsp::render::OfflineRenderer;
+use hexodsp::Context;
+
+fn setup_patch(matrix: &mut Matrix) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("sin", "sig").node_io("pverb", "in_l", "sig_l").node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+    pset_d(matrix, NodeId::Sin(0), "freq", 220.0);
+}
+
+/// Processes one period of `nframes` and returns the left channel.
+fn process_period(node_exec: &mut NodeExecutor, nframes: usize) -> Vec<f32> {
+    let input = vec![0.0; nframes];
+    let mut out_l = vec![0.0; nframes];
+    let mut out_r = vec![0.0; nframes];
+
+    node_exec.process_graph_updates();
+    node_exec.process(&mut Context {
+        nframes,
+        output: &mut [&mut out_l[..], &mut out_r[..]],
+        input: &[&input[..]],
+    });
+
+    out_l
+}
+
+#[test]
+fn check_block_size_long_periods() {
+    let render = |max_block_size: usize, period: usize| {
+        let (node_conf, mut node_exec) = new_node_engine_with_block_size(max_block_size);
+        let mut matrix = Matrix::new(node_conf, 3, 3);
+        setup_patch(&mut matrix);
+        OfflineRenderer::new(44100.0).block_size(period).render(&mut node_exec, 0.5)
+    };
+
+    // Long periods are split and give the same output as short ones:
+    let reference = render(128, 128);
+    assert!(reference.channel(0).iter().any(|s| s.abs() > 0.1));
+    assert!(reference.channel(0) == render(128, 1024).channel(0));
+    assert!(reference.channel(0) == render(128, 1000).channel(0));
+
+    let reference = render(32, 32);
+    assert!(reference.channel(0) == render(32, 512).channel(0));
+    assert!(reference.channel(0) == render(32, 77).channel(0));
+}
+
+#[test]
+fn check_block_size_setting() {
+    let (_node_conf, mut node_exec) = new_node_engine();
+    assert_eq!(node_exec.max_block_size(), hexodsp::dsp::MAX_BLOCK_SIZE);
+    node_exec.set_max_block_size(0);
+    assert_eq!(node_exec.max_block_size(), 1);
+    node_exec.set_max_block_size(100000);
+    assert_eq!(node_exec.max_block_size(), hexodsp::dsp::MAX_BLOCK_SIZE);
+
+    let (_node_conf, node_exec) = new_node_engine_with_block_size(64);
+    assert_eq!(node_exec.max_block_size(), 64);
+
+    // The maximum is the block size the engine was created with:
+    let (_node_conf, mut node_exec) = new_node_engine_with_block_size(1024);
+    assert_eq!(node_exec.max_block_size(), 1024);
+    node_exec.set_max_block_size(100000);
+    assert_eq!(node_exec.max_block_size(), 1024);
+    node_exec.set_max_block_size(256);
+    assert_eq!(node_exec.max_block_size(), 256);
+}
+
+#[test]
+fn check_block_size_large_blocks() {
+    let render = |max_block_size: usize, period: usize| {
+        let (node_conf, mut node_exec) = new_node_engine_with_block_size(max_block_size);
+        let mut matrix = Matrix::new(node_conf, 3, 3);
+        setup_patch(&mut matrix);
+        OfflineRenderer::new(44100.0).block_size(period).render(&mut node_exec, 0.5)
+    };
+
+    // The nodes process blocks longer than the default maximum:
+    let reference = render(128, 128);
+    assert!(reference.channel(0) == render(1024, 1024).channel(0));
+    assert!(reference.channel(0) == render(1024, 3000).channel(0));
+    assert!(reference.channel(0) == render(512, 100).channel(0));
+}
+
+#[test]
+fn check_block_size_feedback_delay() {
+    let first_non_zero = |max_block_size: usize| {
+        let (node_conf, mut node_exec) = new_node_engine_with_block_size(max_block_size);
+        let mut matrix = Matrix::new(node_conf, 3, 3);
+        let mut chain = MatrixCellChain::new(CellDir::B);
+        chain.node_out("sin", "sig").node_inp("fbwr", "inp");
+        chain.place(&mut matrix, 0, 0).unwrap();
+        let mut chain = MatrixCellChain::new(CellDir::B);
+        chain.node_out("fbrd", "sig").node_inp("out", "ch1");
+        chain.place(&mut matrix, 1, 0).unwrap();
+        matrix.sync().unwrap();
+
+        let audio = OfflineRenderer::new(44100.0).render(&mut node_exec, 0.1);
+        audio.channel(0).iter().position(|s| *s != 0.0).unwrap()
+    };
+
+    // The feedback delay is 3.14ms, or one block if that is longer:
+    assert_eq!(first_non_zero(128), 138);
+    assert_eq!(first_non_zero(512), 512);
+}
+
+#[test]
+fn check_block_size_events() {
+    init_test!(matrix, node_exec, 3);
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("midip", "gate").node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+    node_exec.set_sample_rate(44100.0);
+
+    // The MIDI events and timed updates are placed exactly in a long period:
+    let gain = NodeId::Out(0).inp_param("gain").unwrap();
+    matrix.set_param_at(gain, SAtom::param(gain.norm(0.5)), 900);
+    node_exec.feed_midi_event(MidiEvent::from_bytes(700, &[0x90, 60, 127]).unwrap());
+    node_exec.feed_midi_event(MidiEvent::from_bytes(1000, &[0x80, 60, 0]).unwrap());
+
+    let out = process_period(node_exec, 1024);
+    assert_eq!(collect_non_zero(&out[..]), vec![(700, 300)]);
+    assert_float_eq!(out[899], 1.0);
+    assert_float_eq!(out[900], 0.5);
+}
+
+#[test]
+fn check_block_size_large_blocks_tseq() {
+    use hexodsp::dsp::tracker::UIPatternModel;
+
+    let render = |max_block_size: usize| {
+        let (node_conf, mut node_exec) = new_node_engine_with_block_size(max_block_size);
+        let mut matrix = Matrix::new(node_conf, 3, 3);
+        let mut chain = MatrixCellChain::new(CellDir::B);
+        chain.node_out("sin", "sig").node_io("tseq", "clock", "trk1").node_inp("out", "ch1");
+        chain.place(&mut matrix, 0, 0).unwrap();
+        matrix.sync().unwrap();
+        pset_d(&mut matrix, NodeId::Sin(0), "freq", 10.0);
+        pset_s(&mut matrix, NodeId::TSeq(0), "cmode", 1);
+
+        let pat = matrix.get_pattern_data(0).unwrap();
+        {
+            let mut pr = pat.lock().unwrap();
+            pr.set_rows(16);
+            pr.set_cell_value(0, 0, 0xFFF);
+            pr.set_cell_value(15, 0, 0x000);
+        }
+        for _ in 0..10 {
+            matrix.check_pattern_data(0);
+        }
+
+        OfflineRenderer::new(44100.0).block_size(max_block_size).render(&mut node_exec, 0.5)
+    };
+
+    let reference = render(128);
+    assert!(reference.channel(0).iter().any(|s| s.abs() > 0.1));
+    assert!(reference.channel(0) == render(1000).channel(0));
+}
@@ -0,0 +1,130 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::cc_map::CcMapping;
+use hexodsp::matrix_repr::MatrixRepr;
+
+fn freq_param() -> ParamId {
+    NodeId::Sin(0).inp_param("freq").unwrap()
+}
+
+#[test]
+fn check_cc_map_range() {
+    init_test!(matrix, node_exec, 3);
+    let freq = freq_param();
+    let gain = NodeId::Out(0).inp_param("gain").unwrap();
+
+    matrix.add_cc_mapping(CcMapping::new(Some(1), 20, freq).range(-0.2, 0.2));
+    matrix.add_cc_mapping(CcMapping::new(None, 20, gain).invert(true));
+
+    assert!(matrix.handle_cc(1, 20, 127));
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 0.2);
+    assert_float_eq!(matrix.get_param(&gain).unwrap().f(), 0.0);
+
+    // Only the omni mapping receives other channels:
+    assert!(matrix.handle_cc(2, 20, 0));
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 0.2);
+    assert_float_eq!(matrix.get_param(&gain).unwrap().f(), 1.0);
+
+    assert!(!matrix.handle_cc(1, 21, 0));
+
+    // Replacing a mapping:
+    matrix.add_cc_mapping(CcMapping::new(Some(1), 20, freq).range(0.0, 0.1));
+    assert_eq!(matrix.cc_mappings().len(), 2);
+    matrix.handle_cc(1, 20, 127);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 0.1);
+
+    matrix.remove_cc_mappings(&gain);
+    assert_eq!(matrix.cc_mappings(), &[CcMapping::new(Some(1), 20, freq).range(0.0, 0.1)]);
+}
+
+#[test]
+fn check_cc_map_setting() {
+    init_test!(matrix, node_exec, 3);
+    let mono = NodeId::Out(0).inp_param("mono").unwrap();
+
+    matrix.add_cc_mapping(CcMapping::new(None, 64, mono));
+    matrix.handle_cc(0, 64, 127);
+    assert_eq!(matrix.get_param(&mono).unwrap().i(), 1);
+    matrix.handle_cc(0, 64, 10);
+    assert_eq!(matrix.get_param(&mono).unwrap().i(), 0);
+}
+
+#[test]
+fn check_cc_map_soft_takeover() {
+    init_test!(matrix, node_exec, 3);
+    let freq = freq_param();
+
+    matrix.set_param(freq, SAtom::param(0.0));
+    matrix.add_cc_mapping(CcMapping::new(None, 1, freq).range(-1.0, 1.0).soft_takeover(true));
+
+    // The controller does not change the parameter until it reached it's value:
+    matrix.handle_cc(0, 1, 0);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 0.0);
+    matrix.handle_cc(0, 1, 40);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 0.0);
+    // Jumping over the value picks it up:
+    matrix.handle_cc(0, 1, 100);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 100.0 / 127.0 * 2.0 - 1.0);
+    matrix.handle_cc(0, 1, 127);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 1.0);
+
+    // After the parameter was changed elsewhere it needs to be picked up again:
+    matrix.set_param(freq, SAtom::param(-0.5));
+    matrix.handle_cc(0, 1, 126);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), -0.5);
+    matrix.handle_cc(0, 1, 32);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 32.0 / 127.0 * 2.0 - 1.0);
+}
+
+#[test]
+fn check_cc_map_learn() {
+    init_test!(matrix, node_exec, 3);
+    let freq = freq_param();
+
+    matrix.add_cc_mapping(CcMapping::new(None, 1, freq));
+    matrix.learn_cc(Some(freq));
+    assert_eq!(matrix.cc_learn_param(), Some(freq));
+
+    // The learning message binds the controller, without changing the value:
+    matrix.set_param(freq, SAtom::param(0.0));
+    assert!(matrix.handle_cc(3, 74, 127));
+    assert_eq!(matrix.cc_learn_param(), None);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 0.0);
+    assert_eq!(matrix.cc_mappings(), &[CcMapping::new(Some(3), 74, freq)]);
+
+    matrix.handle_cc(3, 74, 127);
+    assert_float_eq!(matrix.get_param(&freq).unwrap().f(), 1.0);
+    assert!(!matrix.handle_cc(0, 1, 127));
+}
+
+#[test]
+fn check_cc_map_repr() {
+    let freq = freq_param();
+    let gain = NodeId::Out(0).inp_param("gain").unwrap();
+    let mappings = vec![
+        CcMapping::new(Some(15), 7, gain).range(0.25, 0.75).invert(true),
+        CcMapping::new(None, 74, freq).soft_takeover(true),
+    ];
+
+    let s = {
+        init_test!(matrix, node_exec, 3);
+        for m in mappings.iter() {
+            matrix.add_cc_mapping(*m);
+        }
+        matrix.to_repr().serialize()
+    };
+
+    init_test!(matrix, node_exec, 3);
+    matrix.from_repr(&MatrixRepr::deserialize(&s).unwrap()).unwrap();
+    assert_eq!(matrix.cc_mappings(), &mappings[..]);
+
+    // Patches without controller mappings don't contain them:
+    matrix.clear();
+    assert!(matrix.cc_mappings().is_empty());
+    assert!(!matrix.to_repr().serialize().contains("ccmap"));
+}
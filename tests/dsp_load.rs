@@ -0,0 +1,69 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::DspLoad;
+
+fn setup_chain(matrix: &mut Matrix) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("sin", "sig").node_io("pverb", "in_l", "sig_l").node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+#[test]
+fn check_dsp_load_nodes() {
+    init_test!(matrix, node_exec, 3);
+    setup_chain(matrix);
+
+    // Nothing is measured by default:
+    run_for_ms(node_exec, 300.0);
+    matrix.update_dsp_load();
+    assert_eq!(matrix.dsp_load(), DspLoad::default());
+    assert_eq!(matrix.node_dsp_load(&NodeId::Sin(0)), Some(DspLoad::default()));
+
+    matrix.set_dsp_profiling(true);
+    run_for_ms(node_exec, 300.0);
+    matrix.update_dsp_load();
+
+    let total = matrix.dsp_load();
+    assert!(total.avg_us > 0.0 && total.peak_us >= total.avg_us, "{:?}", total);
+    assert!(total.load > 0.0 && total.peak_load >= total.load, "{:?}", total);
+
+    let loads = matrix.dsp_load_by_node();
+    assert_eq!(loads.len(), 3);
+    let mut sum_us = 0.0;
+    for (node_id, load) in loads.iter() {
+        assert!(load.avg_us > 0.0, "{}: {:?}", node_id, load);
+        assert!(load.peak_us >= load.avg_us, "{}: {:?}", node_id, load);
+        sum_us += load.avg_us;
+    }
+    assert!(sum_us <= total.avg_us, "{} <= {:?}", sum_us, total);
+    assert!(loads[0].1.load >= loads[2].1.load);
+
+    assert_eq!(matrix.node_dsp_load(&NodeId::Amp(0)), None);
+
+    matrix.set_dsp_profiling(false);
+    run_for_ms(node_exec, 10.0);
+    matrix.update_dsp_load();
+    assert_eq!(matrix.dsp_load(), DspLoad::default());
+}
+
+#[test]
+fn check_dsp_load_voices() {
+    init_test!(matrix, node_exec, 3);
+    matrix.set_voice_region(&[NodeId::Sin(0)], 4);
+    setup_chain(matrix);
+
+    matrix.set_dsp_profiling(true);
+    run_for_ms(node_exec, 300.0);
+    matrix.update_dsp_load();
+
+    // The voices are summed up, so they are counted with the template:
+    let sin = matrix.node_dsp_load(&NodeId::Sin(0)).unwrap();
+    assert!(sin.avg_us > 0.0);
+    assert!(sin.avg_us <= matrix.dsp_load().avg_us);
+}
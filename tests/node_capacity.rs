@@ -0,0 +1,153 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::new_node_engine_with_capacity;
+
+#[test]
+fn check_node_capacity_grow() {
+    let (mut node_conf, mut node_exec) = new_node_engine();
+    assert_eq!(node_conf.node_capacity(), 256);
+
+    let amp_count = 300;
+    for i in 0..amp_count {
+        node_conf.create_node(NodeId::Amp(i));
+    }
+    node_conf.create_node(NodeId::Out(0));
+    assert_eq!(node_conf.node_capacity(), 512);
+
+    let mut prog = node_conf.rebuild_node_ports();
+    for i in 0..amp_count {
+        node_conf.add_prog_node(&mut prog, &NodeId::Amp(i));
+    }
+    node_conf.add_prog_node(&mut prog, &NodeId::Out(0));
+
+    for i in 1..amp_count {
+        node_conf.set_prog_node_exec_connection(
+            &mut prog,
+            (NodeId::Amp(i), NodeId::Amp(i).inp("inp").unwrap()),
+            (NodeId::Amp(i - 1), NodeId::Amp(i - 1).out("sig").unwrap()),
+        );
+    }
+    let last = NodeId::Amp(amp_count - 1);
+    node_conf.set_prog_node_exec_connection(
+        &mut prog,
+        (NodeId::Out(0), NodeId::Out(0).inp("ch1").unwrap()),
+        (last, last.out("sig").unwrap()),
+    );
+
+    node_conf.upload_prog(prog, false);
+    node_conf.set_param(NodeId::Amp(0).inp_param("inp").unwrap(), SAtom::param(0.5));
+
+    let (out_l, _) = node_exec.test_run(0.1, false);
+    assert_eq!(node_exec.get_nodes().len(), 512);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.5);
+    assert_float_eq!(node_conf.led_value_for(&last), 0.5);
+}
+
+#[test]
+fn check_node_capacity_initial() {
+    let (node_conf, node_exec) = new_node_engine_with_capacity(1000);
+    assert_eq!(node_conf.node_capacity(), 1000);
+    assert_eq!(node_exec.get_nodes().len(), 1000);
+}
+
+#[test]
+fn check_node_capacity_voices() {
+    let (node_conf, mut node_exec) = new_node_engine_with_capacity(4);
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+
+    // The 7 voice copies don't fit into the initial capacity:
+    matrix.set_voice_region(&[NodeId::Voice(0)], 8);
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("voice", "gate").node_inp("out", "ch1");
+    chain.place(&mut matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+    assert!(matrix.pop_error().is_none());
+
+    for note in 60..68 {
+        matrix.note_on(note, 1.0);
+    }
+    let (out_l, _) = run_for_ms(&mut node_exec, 10.0);
+    assert_float_eq!(out_l[100], 8.0);
+
+    // New nodes don't replace the voice copies:
+    matrix.place(1, 0, Cell::empty(NodeId::Sin(0)));
+    matrix.sync().unwrap();
+    let (out_l, _) = run_for_ms(&mut node_exec, 10.0);
+    assert_float_eq!(out_l[100], 8.0);
+}
+
+#[test]
+fn check_node_capacity_full_queue() {
+    let (mut node_conf, mut node_exec) = new_node_engine_with_capacity(4);
+
+    // Fill up the graph update queue, before the node capacity grows:
+    for _ in 0..1024 {
+        node_conf.set_prog_crossfade(0.0);
+    }
+    for i in 0..6 {
+        node_conf.create_node(NodeId::Amp(i));
+    }
+    node_conf.create_node(NodeId::Out(0));
+    assert_eq!(node_conf.node_capacity(), 8);
+    node_exec.process_graph_updates();
+    assert_eq!(node_exec.get_nodes().len(), 4);
+
+    // The updates that did not fit are sent as soon as there is room:
+    let mut prog = node_conf.rebuild_node_ports();
+    node_conf.add_prog_node(&mut prog, &NodeId::Amp(5));
+    node_conf.add_prog_node(&mut prog, &NodeId::Out(0));
+    node_conf.set_prog_node_exec_connection(
+        &mut prog,
+        (NodeId::Out(0), NodeId::Out(0).inp("ch1").unwrap()),
+        (NodeId::Amp(5), NodeId::Amp(5).out("sig").unwrap()),
+    );
+    node_conf.upload_prog(prog, false);
+    node_conf.set_param(NodeId::Amp(5).inp_param("inp").unwrap(), SAtom::param(0.5));
+
+    for _ in 0..4 {
+        node_exec.process_graph_updates();
+        node_conf.update_output_feedback();
+    }
+
+    let (out_l, _) = node_exec.test_run(0.1, false);
+    assert_eq!(node_exec.get_nodes().len(), 8);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.5);
+    assert!(node_conf.pop_error().is_none());
+}
+
+#[test]
+fn check_node_capacity_full_queue_param_order() {
+    let (mut node_conf, mut node_exec) = new_node_engine_with_capacity(4);
+    node_conf.create_node(NodeId::Amp(0));
+    node_conf.create_node(NodeId::Out(0));
+
+    for _ in 0..1024 {
+        node_conf.set_prog_crossfade(0.0);
+    }
+
+    // The parameter update must not overtake the program:
+    let mut prog = node_conf.rebuild_node_ports();
+    node_conf.add_prog_node(&mut prog, &NodeId::Amp(0));
+    node_conf.add_prog_node(&mut prog, &NodeId::Out(0));
+    node_conf.set_prog_node_exec_connection(
+        &mut prog,
+        (NodeId::Out(0), NodeId::Out(0).inp("ch1").unwrap()),
+        (NodeId::Amp(0), NodeId::Amp(0).out("sig").unwrap()),
+    );
+    node_conf.upload_prog(prog, false);
+    node_conf.set_param(NodeId::Amp(0).inp_param("inp").unwrap(), SAtom::param(0.5));
+
+    for _ in 0..4 {
+        node_exec.process_graph_updates();
+        node_conf.update_output_feedback();
+    }
+
+    let (out_l, _) = node_exec.test_run(0.1, false);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.5);
+    assert!(node_conf.pop_error().is_none());
+}
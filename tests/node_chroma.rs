@@ -0,0 +1,112 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+fn setup_chroma(matrix: &mut Matrix, out: &str) {
+    let sin_1 = NodeId::Sin(0);
+    let sin_2 = NodeId::Sin(1);
+    let sin_3 = NodeId::Sin(2);
+    let mix3 = NodeId::Mix3(0);
+    let chroma = NodeId::Chroma(0);
+    let out_1 = NodeId::Out(0);
+
+    matrix.place(1, 0, Cell::empty(sin_1).out(None, None, sin_1.out("sig")));
+    matrix.place(0, 1, Cell::empty(sin_2).out(None, sin_2.out("sig"), None));
+    matrix.place(0, 2, Cell::empty(sin_3).out(sin_3.out("sig"), None, None));
+    matrix.place(
+        1,
+        1,
+        Cell::empty(mix3).input(mix3.inp("ch1"), mix3.inp("ch2"), mix3.inp("ch3")).out(
+            None,
+            None,
+            mix3.out("sig"),
+        ),
+    );
+    matrix.place(
+        1,
+        2,
+        Cell::empty(chroma).input(chroma.inp("inp"), None, None).out(None, None, chroma.out(out)),
+    );
+    matrix.place(1, 3, Cell::empty(out_1).input(out_1.inp("ch1"), None, None));
+
+    pset_d(matrix, mix3, "gain1", 0.3);
+    pset_d(matrix, mix3, "gain2", 0.3);
+    pset_d(matrix, mix3, "gain3", 0.3);
+    matrix.sync().unwrap();
+}
+
+fn set_notes(matrix: &mut Matrix, notes: [f32; 3]) {
+    for (i, freq) in notes.iter().enumerate() {
+        pset_d(matrix, NodeId::Sin(i as u16), "freq", *freq);
+    }
+}
+
+// Pitch of the root in the same format as Quant/CQnt, C4 = -0.075
+fn root_pitch(semis_above_c: f32) -> f32 {
+    (semis_above_c - 9.0) / 120.0
+}
+
+#[test]
+fn check_node_chroma_major_minor() {
+    init_test!(matrix, node_exec, 5);
+    setup_chroma(matrix, "root");
+
+    // C major: C4, E4, G4
+    set_notes(matrix, [261.63, 329.63, 392.0]);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert_float_eq!(out[out.len() - 1], root_pitch(0.0));
+
+    // A minor: A3, C4, E5
+    set_notes(matrix, [220.0, 261.63, 659.26]);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert_float_eq!(out[out.len() - 1], root_pitch(9.0));
+
+    // F# major: F#3, A#4, C#5
+    set_notes(matrix, [185.0, 466.16, 554.37]);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert_float_eq!(out[out.len() - 1], root_pitch(6.0));
+}
+
+#[test]
+fn check_node_chroma_conf_and_qual() {
+    init_test!(matrix, node_exec, 5);
+    setup_chroma(matrix, "conf");
+
+    // G major: G3, B3, D4
+    set_notes(matrix, [196.0, 246.94, 293.66]);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert!(out[out.len() - 1] > 0.9);
+
+    // Only minor chords are accepted, G major is not a good match:
+    pset_s(matrix, NodeId::Chroma(0), "qual", 2);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    let conf_min = out[out.len() - 1];
+    assert!(conf_min < 0.8);
+
+    // Silence:
+    pset_d(matrix, NodeId::Mix3(0), "ogain", 0.0);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert_float_eq!(out[out.len() - 1], 0.0);
+}
+
+#[test]
+fn check_node_chroma_trig() {
+    init_test!(matrix, node_exec, 5);
+    setup_chroma(matrix, "t");
+
+    set_notes(matrix, [261.63, 329.63, 392.0]);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert_eq!(collect_gates(&out[..]).len(), 1);
+
+    // Same root, no new trigger:
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert_eq!(collect_gates(&out[..]).len(), 0);
+
+    // D minor: D4, F4, A4
+    set_notes(matrix, [293.66, 349.23, 440.0]);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert_eq!(collect_gates(&out[..]).len(), 1);
+}
@@ -0,0 +1,159 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::Transport;
+
+fn setup_clock(matrix: &mut Matrix, out: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("clock", out).node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+fn playing(bpm: f64, sig_num: u32, beat: f64) -> Transport {
+    let mut transport = Transport::new();
+    transport.bpm = bpm;
+    transport.sig_num = sig_num;
+    transport.playing = true;
+    transport.set_position(beat);
+    transport
+}
+
+/// Compares the gates with a tolerance of one frame for rounding errors.
+fn assert_gates_near(gates: Vec<(usize, usize)>, expected: &[(usize, usize)]) {
+    assert_eq!(gates.len(), expected.len(), "{:?} != {:?}", gates, expected);
+    for (g, e) in gates.iter().zip(expected.iter()) {
+        assert!(
+            (g.0 as i64 - e.0 as i64).abs() <= 1 && (g.1 as i64 - e.1 as i64).abs() <= 1,
+            "{:?} != {:?}",
+            gates,
+            expected
+        );
+    }
+}
+
+fn assert_starts_near(gates: Vec<(usize, usize)>, expected: &[usize]) {
+    let starts: Vec<usize> = gates.iter().map(|(start, _)| *start).collect();
+    assert_eq!(starts.len(), expected.len(), "{:?} != {:?}", starts, expected);
+    for (s, e) in starts.iter().zip(expected.iter()) {
+        assert!((*s as i64 - *e as i64).abs() <= 1, "{:?} != {:?}", starts, expected);
+    }
+}
+
+#[test]
+fn check_node_clock_gates() {
+    init_test!(matrix, node_exec, 3);
+    setup_clock(matrix, "clk");
+
+    // At 120 BPM a quarter note is 22050 frames long:
+    node_exec.set_transport(playing(120.0, 4, 0.0));
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    assert_gates_near(collect_gates(&out[..]), &[(0, 11025), (22050, 11025)]);
+
+    pset_s(matrix, NodeId::Clock(0), "div", 10);
+    node_exec.set_transport(playing(120.0, 4, 0.0));
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    assert_gates_near(
+        collect_gates(&out[..]),
+        &[(0, 5512), (11025, 5512), (22050, 5512), (33075, 5512)],
+    );
+
+    // Triplets and a shorter pulse width:
+    pset_s(matrix, NodeId::Clock(0), "div", 8);
+    pset_d(matrix, NodeId::Clock(0), "pw", 0.25);
+    node_exec.set_transport(Transport::new());
+    run_for_ms(node_exec, 50.0);
+    node_exec.set_transport(playing(120.0, 4, 0.0));
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    assert_gates_near(collect_gates(&out[..]), &[(0, 3675), (14700, 3675), (29400, 3675)]);
+}
+
+#[test]
+fn check_node_clock_swing() {
+    init_test!(matrix, node_exec, 3);
+    setup_clock(matrix, "clk");
+
+    pset_s(matrix, NodeId::Clock(0), "div", 10);
+    pset_d(matrix, NodeId::Clock(0), "swing", 1.0);
+    run_for_ms(node_exec, 50.0);
+
+    // Every second 1/8 note is delayed by a 1/16 note:
+    node_exec.set_transport(playing(120.0, 4, 0.0));
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    assert_gates_near(
+        collect_gates(&out[..]),
+        &[(0, 8269), (16538, 2756), (22050, 8269), (38588, 2756)],
+    );
+}
+
+#[test]
+fn check_node_clock_beat_bar() {
+    let beat_bar_starts = |out: &str| {
+        init_test!(matrix, node_exec, 3);
+        setup_clock(matrix, out);
+
+        node_exec.set_transport(playing(120.0, 3, 0.0));
+        let (out, _) = run_for_ms(node_exec, 2500.0);
+        collect_gates(&out[..])
+    };
+
+    assert_starts_near(beat_bar_starts("beat"), &[0, 22050, 44100, 66150, 88200]);
+    assert_starts_near(beat_bar_starts("bar"), &[0, 66150]);
+
+    // Starting in the middle of a bar does not trigger the bar:
+    init_test!(matrix, node_exec, 3);
+    setup_clock(matrix, "bar");
+    node_exec.set_transport(playing(120.0, 4, 2.5));
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    assert_starts_near(collect_gates(&out[..]), &[33075]);
+}
+
+#[test]
+fn check_node_clock_phase() {
+    init_test!(matrix, node_exec, 3);
+    setup_clock(matrix, "phase");
+
+    node_exec.set_transport(playing(120.0, 4, 1.0));
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    assert_float_eq!(out[0], 0.25);
+    assert_float_eq!(out[22050], 0.5);
+    assert_float_eq!(out[44099], 0.75);
+}
+
+#[test]
+fn check_node_clock_reset() {
+    init_test!(matrix, node_exec, 3);
+    setup_clock(matrix, "rst");
+
+    // Stopped transport:
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert!(out.iter().all(|s| *s == 0.0));
+
+    // Starting the transport:
+    node_exec.set_transport(playing(120.0, 4, 4.0));
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert_eq!(collect_gates(&out[..]), vec![(0, 89)]);
+
+    // Running on:
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert!(out.iter().all(|s| *s == 0.0));
+
+    // Jumping to another position:
+    let mut transport = *node_exec.transport();
+    transport.set_position(16.0);
+    node_exec.set_transport(transport);
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert_eq!(collect_gates(&out[..]), vec![(0, 89)]);
+
+    // Stopping silences the outputs:
+    let mut transport = *node_exec.transport();
+    transport.playing = false;
+    node_exec.set_transport(transport);
+    setup_clock(matrix, "clk");
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert!(out.iter().all(|s| *s == 0.0));
+}
@@ -0,0 +1,131 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+fn setup_dtmf(matrix: &mut Matrix, out: &str) {
+    let sin_1 = NodeId::Sin(0);
+    let sin_2 = NodeId::Sin(1);
+    let mix3 = NodeId::Mix3(0);
+    let dtmf = NodeId::Dtmf(0);
+    let out_1 = NodeId::Out(0);
+
+    matrix.place(1, 0, Cell::empty(sin_1).out(None, None, sin_1.out("sig")));
+    matrix.place(0, 1, Cell::empty(sin_2).out(None, sin_2.out("sig"), None));
+    matrix.place(
+        1,
+        1,
+        Cell::empty(mix3).input(mix3.inp("ch1"), mix3.inp("ch2"), None).out(
+            None,
+            None,
+            mix3.out("sig"),
+        ),
+    );
+    matrix.place(
+        1,
+        2,
+        Cell::empty(dtmf).input(dtmf.inp("inp"), None, None).out(None, None, dtmf.out(out)),
+    );
+    matrix.place(1, 3, Cell::empty(out_1).input(out_1.inp("ch1"), None, None));
+
+    pset_d(matrix, mix3, "gain1", 0.4);
+    pset_d(matrix, mix3, "gain2", 0.4);
+    matrix.sync().unwrap();
+}
+
+fn set_tones(matrix: &mut Matrix, low: f32, high: f32) {
+    pset_d(matrix, NodeId::Sin(0), "freq", low);
+    pset_d(matrix, NodeId::Sin(1), "freq", high);
+}
+
+fn set_silence(matrix: &mut Matrix) {
+    pset_d(matrix, NodeId::Mix3(0), "ogain", 0.0);
+}
+
+fn set_sound(matrix: &mut Matrix) {
+    pset_d(matrix, NodeId::Mix3(0), "ogain", 1.0);
+}
+
+#[test]
+fn check_node_dtmf_keys() {
+    init_test!(matrix, node_exec, 5);
+    setup_dtmf(matrix, "key");
+
+    // No key decoded yet:
+    set_silence(matrix);
+    let (out, _) = run_for_ms(node_exec, 50.0);
+    assert_float_eq!(out[out.len() - 1], 0.0);
+
+    // '5' => 770 Hz + 1336 Hz
+    set_tones(matrix, 770.0, 1336.0);
+    set_sound(matrix);
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert_float_eq!(out[out.len() - 1], 6.0 / 16.0);
+
+    // The key is held after the release:
+    set_silence(matrix);
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert_float_eq!(out[out.len() - 1], 6.0 / 16.0);
+
+    // 'D' => 941 Hz + 1633 Hz
+    set_tones(matrix, 941.0, 1633.0);
+    set_sound(matrix);
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert_float_eq!(out[out.len() - 1], 16.0 / 16.0);
+
+    // '*' => 941 Hz + 1209 Hz
+    set_tones(matrix, 941.0, 1209.0);
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert_float_eq!(out[out.len() - 1], 13.0 / 16.0);
+}
+
+#[test]
+fn check_node_dtmf_trig_gate() {
+    init_test!(matrix, node_exec, 5);
+    setup_dtmf(matrix, "t");
+
+    set_silence(matrix);
+    run_for_ms(node_exec, 50.0);
+
+    // Two presses of '1' => 697 Hz + 1209 Hz
+    set_tones(matrix, 697.0, 1209.0);
+    for _ in 0..2 {
+        set_sound(matrix);
+        let (out, _) = run_for_ms(node_exec, 100.0);
+        assert_eq!(collect_gates(&out[..]).len(), 1);
+
+        set_silence(matrix);
+        let (out, _) = run_for_ms(node_exec, 100.0);
+        assert_eq!(collect_gates(&out[..]).len(), 0);
+    }
+}
+
+#[test]
+fn check_node_dtmf_rejects() {
+    init_test!(matrix, node_exec, 5);
+    setup_dtmf(matrix, "gat");
+
+    // A single tone is not a key:
+    set_tones(matrix, 697.0, 100.0);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert!(out.iter().all(|v| *v < 0.5));
+
+    // Too much twist:
+    set_tones(matrix, 697.0, 1209.0);
+    pset_d(matrix, NodeId::Mix3(0), "gain1", 0.02);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert!(out.iter().all(|v| *v < 0.5));
+
+    // Too quiet:
+    pset_d(matrix, NodeId::Mix3(0), "gain1", 0.05);
+    pset_d(matrix, NodeId::Mix3(0), "gain2", 0.05);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert!(out.iter().all(|v| *v < 0.5));
+
+    // Lowering the threshold accepts the key:
+    pset_d(matrix, NodeId::Dtmf(0), "thrs", 0.02);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert_float_eq!(out[out.len() - 1], 1.0);
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+fn setup_follow(matrix: &mut Matrix, out: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("sin", "sig").node_io("follow", "inp", out).node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+fn last(buf: &[f32]) -> f32 {
+    buf[buf.len() - 1]
+}
+
+fn assert_near(v: f32, expected: f32, tolerance: f32) {
+    assert!((v - expected).abs() < tolerance, "{} != {}", v, expected);
+}
+
+#[test]
+fn check_node_follow_modes() {
+    init_test!(matrix, node_exec, 3);
+    setup_follow(matrix, "env");
+
+    let fol = NodeId::Follow(0);
+    pset_d(matrix, NodeId::Sin(0), "freq", 1000.0);
+
+    let (out, _) = run_for_ms(node_exec, 300.0);
+    assert_near(last(&out[..]), 1.0, 0.02);
+
+    pset_s(matrix, fol, "mode", 1);
+    let (out, _) = run_for_ms(node_exec, 300.0);
+    assert_near(last(&out[..]), 0.7071, 0.02);
+
+    // The K-weighting is about +0.7dB at 1kHz:
+    pset_s(matrix, fol, "mode", 2);
+    let (out, _) = run_for_ms(node_exec, 300.0);
+    assert_near(last(&out[..]), 0.7654, 0.02);
+}
+
+#[test]
+fn check_node_follow_lufs_weighting() {
+    init_test!(matrix, node_exec, 3);
+    setup_follow(matrix, "env");
+
+    let fol = NodeId::Follow(0);
+    pset_s(matrix, fol, "mode", 2);
+
+    // Low frequencies are attenuated, high frequencies boosted:
+    pset_d(matrix, NodeId::Sin(0), "freq", 40.0);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    let low = last(&out[..]);
+    pset_d(matrix, NodeId::Sin(0), "freq", 5000.0);
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    let high = last(&out[..]);
+
+    assert!(low < 0.5, "low={}", low);
+    assert!(high > 1.0, "high={}", high);
+}
+
+#[test]
+fn check_node_follow_atk_rel() {
+    init_test!(matrix, node_exec, 3);
+    setup_follow(matrix, "env");
+
+    let fol = NodeId::Follow(0);
+    pset_d(matrix, NodeId::Sin(0), "freq", 1000.0);
+    pset_d(matrix, fol, "atk", 100.0);
+
+    // After one attack time, the envelope reached about 63%
+    // (a bit more, as 'atk' is smoothed from it's default at the start):
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    let env = last(&out[..]);
+    assert!(env > 0.55 && env < 0.75, "env={}", env);
+
+    let (out, _) = run_for_ms(node_exec, 500.0);
+    assert_near(last(&out[..]), 1.0, 0.02);
+
+    // Silence the input and check the release:
+    pset_d(matrix, fol, "rel", 50.0);
+    matrix.place(0, 0, Cell::empty(NodeId::Sin(0)));
+    matrix.sync().unwrap();
+
+    let (out, _) = run_for_ms(node_exec, 50.0);
+    let env = last(&out[..]);
+    assert!(env > 0.3 && env < 0.45, "env={}", env);
+}
+
+#[test]
+fn check_node_follow_gate_hysteresis() {
+    init_test!(matrix, node_exec, 3);
+    setup_follow(matrix, "gat");
+
+    let fol = NodeId::Follow(0);
+    pset_d(matrix, NodeId::Sin(0), "freq", 1000.0);
+    // The RMS envelope of the sine is about 0.707:
+    pset_s(matrix, fol, "mode", 1);
+    pset_d(matrix, fol, "hyst", 0.25);
+
+    pset_d(matrix, fol, "thrs", 0.5);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert_float_eq!(last(&out[..]), 1.0);
+
+    // Still above thrs reduced by the hysteresis (0.6), the gate stays open:
+    pset_d(matrix, fol, "thrs", 0.8);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert!(out.iter().all(|s| *s > 0.5));
+
+    // Below 0.76, the gate closes:
+    pset_d(matrix, fol, "hyst", 0.05);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert_float_eq!(last(&out[..]), 0.0);
+
+    // And below 'thrs' it does not open again:
+    pset_d(matrix, fol, "hyst", 0.25);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert!(out.iter().all(|s| *s < 0.5));
+}
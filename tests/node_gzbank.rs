@@ -0,0 +1,352 @@
+// Copyright (c) 2022 theloni-monk <theo.acooper@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+fn setup_gzbank(matrix: &mut Matrix, band_out: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain
+        .node_out("sin", "sig")
+        .node_io("gzbank", "inp", band_out)
+        .node_inp("out", "ch1")
+        .place(matrix, 0, 0)
+        .unwrap();
+    matrix.sync().unwrap();
+}
+
+#[test]
+fn check_node_gzbank_lin_partials() {
+    init_test!(matrix, node_exec, 3);
+    setup_gzbank(matrix, "b4");
+
+    let sin = NodeId::Sin(0);
+    let gz = NodeId::GzBank(0);
+
+    // Bands at 110, 220, 330, 440, ... Hz
+    pset_d(matrix, gz, "freq", 110.0);
+    pset_d(matrix, gz, "spc", 1.0);
+    pset_d_wait(matrix, node_exec, sin, "freq", 440.0);
+
+    run_for_ms(node_exec, 100.0);
+    let rmsmm = run_and_get_each_rms_mimax(node_exec, 50.0);
+    assert_float_eq!((rmsmm[1].1 * 10.0).round(), 10.0);
+    assert_float_eq!((rmsmm[1].2 * 10.0).round(), 10.0);
+
+    // Move the sine away from the band:
+    pset_d_wait(matrix, node_exec, sin, "freq", 220.0);
+    run_for_ms(node_exec, 100.0);
+    let rmsmm = run_and_get_each_rms_mimax(node_exec, 50.0);
+    assert!(rmsmm[1].2 < 0.05);
+}
+
+#[test]
+fn check_node_gzbank_band_count() {
+    init_test!(matrix, node_exec, 3);
+    setup_gzbank(matrix, "b4");
+
+    let sin = NodeId::Sin(0);
+    let gz = NodeId::GzBank(0);
+
+    pset_d(matrix, gz, "freq", 110.0);
+    pset_d(matrix, gz, "spc", 1.0);
+    pset_s(matrix, gz, "bands", 3);
+    pset_d_wait(matrix, node_exec, sin, "freq", 440.0);
+
+    // Band 4 is not analyzed anymore and stays silent:
+    let rmsmm = run_and_get_each_rms_mimax(node_exec, 50.0);
+    assert_float_eq!(rmsmm[1].2, 0.0);
+}
+
+#[test]
+fn check_node_gzbank_semi_and_list() {
+    init_test!(matrix, node_exec, 3);
+    setup_gzbank(matrix, "b2");
+
+    let sin = NodeId::Sin(0);
+    let gz = NodeId::GzBank(0);
+
+    // 12 semitones per band => b2 is at 880 Hz
+    pset_d(matrix, gz, "freq", 440.0);
+    pset_d(matrix, gz, "spc", 12.0);
+    pset_s(matrix, gz, "scale", 2);
+    pset_d(matrix, gz, "win", 100.0);
+    pset_d_wait(matrix, node_exec, sin, "freq", 880.0);
+
+    run_for_ms(node_exec, 200.0);
+    let rmsmm = run_and_get_each_rms_mimax(node_exec, 100.0);
+    assert!(rmsmm[1].1 > 0.9);
+
+    // Select the frequencies from a list, b2 is now at 1000 Hz:
+    matrix.set_param(gz.inp_param("freqs").unwrap(), SAtom::micro(&[880.0, 1000.0]));
+    pset_s(matrix, gz, "scale", 3);
+    run_for_ms(node_exec, 200.0);
+    let rmsmm = run_and_get_each_rms_mimax(node_exec, 100.0);
+    assert!(rmsmm[1].2 < 0.05);
+
+    pset_d_wait(matrix, node_exec, sin, "freq", 1000.0);
+    run_for_ms(node_exec, 200.0);
+    let rmsmm = run_and_get_each_rms_mimax(node_exec, 100.0);
+    assert!(rmsmm[1].1 > 0.9);
+}
+
+fn run_gzbank_with_block_size(block_size: usize) -> Vec<f32> {
+    init_test!(matrix, node_exec, 3);
+
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain
+        .node_out("sin", "sig")
+        .set_denorm("freq", 440.0)
+        .node_io("gzbank", "inp", "b4")
+        .set_denorm("freq", 110.0)
+        .set_denorm("win", 23.0)
+        .set_denorm("hop", 7.0)
+        .node_inp("out", "ch1")
+        .place(matrix, 0, 0)
+        .unwrap();
+    matrix.sync().unwrap();
+
+    node_exec.set_sample_rate(SAMPLE_RATE);
+    node_exec.process_graph_updates();
+
+    let nframes = 4410;
+    let input = vec![0.0; nframes];
+    let mut output_l = vec![0.0; nframes];
+    let mut output_r = vec![0.0; nframes];
+
+    let mut offs = 0;
+    while offs < nframes {
+        let cur_nframes = block_size.min(nframes - offs);
+        let mut context = hexodsp::Context {
+            nframes: cur_nframes,
+            output: &mut [
+                &mut output_l[offs..(offs + cur_nframes)],
+                &mut output_r[offs..(offs + cur_nframes)],
+            ],
+            input: &[&input[offs..(offs + cur_nframes)]],
+        };
+        node_exec.process(&mut context);
+        offs += cur_nframes;
+    }
+
+    output_l
+}
+
+#[test]
+fn check_node_gzbank_block_size_independent() {
+    let out_128 = run_gzbank_with_block_size(128);
+    let out_64 = run_gzbank_with_block_size(64);
+    let out_37 = run_gzbank_with_block_size(37);
+
+    assert_eq!(out_128, out_64);
+    assert_eq!(out_128, out_37);
+
+    // The output is updated every hop of 7ms (309 samples),
+    // starting with the last sample of the first 23ms (1014 samples) window:
+    let mut changes = vec![];
+    for i in 1..out_128.len() {
+        if out_128[i] != out_128[i - 1] {
+            changes.push(i);
+        }
+    }
+    assert_eq!(&changes[0..4], &[1013, 1013 + 309, 1013 + 2 * 309, 1013 + 3 * 309]);
+}
+
+#[test]
+fn check_node_gzbank_fractional_bin() {
+    init_test!(matrix, node_exec, 3);
+    setup_gzbank(matrix, "b1");
+
+    let sin = NodeId::Sin(0);
+    let gz = NodeId::GzBank(0);
+
+    // A 10ms window holds 4.4 periods of 440 Hz, the nearest integer
+    // bin would be at 400 Hz:
+    pset_d(matrix, gz, "freq", 440.0);
+    pset_d(matrix, gz, "win", 10.0);
+    pset_d(matrix, gz, "hop", 10.0);
+    pset_s(matrix, gz, "wfun", 1);
+    pset_d_wait(matrix, node_exec, sin, "freq", 440.0);
+
+    run_for_ms(node_exec, 100.0);
+    let rmsmm = run_and_get_each_rms_mimax(node_exec, 50.0);
+    assert_float_eq!((rmsmm[1].1 * 1000.0).round(), 1000.0);
+    assert_float_eq!((rmsmm[1].2 * 1000.0).round(), 1000.0);
+}
+
+#[test]
+fn check_node_gzbank_window_leakage() {
+    let mut levels = vec![];
+
+    for wfun in 0..4 {
+        init_test!(matrix, node_exec, 3);
+        setup_gzbank(matrix, "b1");
+
+        let sin = NodeId::Sin(0);
+        let gz = NodeId::GzBank(0);
+
+        // 90 Hz next to the band, 4.5 bins for a 50ms window:
+        pset_d(matrix, gz, "freq", 440.0);
+        pset_s(matrix, gz, "wfun", wfun);
+        pset_d_wait(matrix, node_exec, sin, "freq", 530.0);
+
+        run_for_ms(node_exec, 100.0);
+        let rmsmm = run_and_get_each_rms_mimax(node_exec, 100.0);
+        levels.push(rmsmm[1].2);
+    }
+    // Rect leaks the most, Blackman-Harris the least:
+    assert!(levels[0] > 0.07);
+    assert!(levels[1] < 0.005);
+    assert!(levels[2] < 0.01);
+    assert!(levels[3] < 0.0001);
+}
+
+#[test]
+fn check_node_gzbank_complex() {
+    for (out, expected) in [("ph", -0.5), ("re", 0.0), ("im", -1.0)] {
+        init_test!(matrix, node_exec, 3);
+        setup_gzbank(matrix, out);
+
+        let gz = NodeId::GzBank(0);
+        pset_d(matrix, gz, "freq", 440.0);
+        pset_s(matrix, gz, "bands", 1);
+
+        // 50ms hold exactly 22 periods of 440 Hz, so every window starts
+        // at the same phase of the sine, which is -PI/2 relative to a cosine:
+        let (ch1, _) = run_for_ms(node_exec, 200.0);
+        for i in [4410, 6615, 8819] {
+            assert!((ch1[i] - expected).abs() < 0.001, "{} {} != {}", out, ch1[i], expected);
+        }
+    }
+}
+
+#[test]
+fn check_node_gzbank_dbfs() {
+    for (att, expected) in [(1.0, 0.0), (0.5, -6.02), (0.1, -20.0), (0.0, -144.0)] {
+        init_test!(matrix, node_exec, 4);
+
+        let mut chain = MatrixCellChain::new(CellDir::B);
+        chain
+            .node_out("sin", "sig")
+            .node_io("amp", "inp", "sig")
+            .set_denorm("att", att)
+            .node_io("gzbank", "inp", "b1")
+            .set_denorm("freq", 440.0)
+            .set_atom("unit", SAtom::setting(1))
+            .node_inp("out", "ch1")
+            .place(matrix, 0, 0)
+            .unwrap();
+        matrix.sync().unwrap();
+
+        run_for_ms(node_exec, 100.0);
+        let rmsmm = run_and_get_each_rms_mimax(node_exec, 50.0);
+        assert!((rmsmm[1].1 - expected).abs() < 0.05, "{} {}", rmsmm[1].1, expected);
+        assert!((rmsmm[1].2 - expected).abs() < 0.05, "{} {}", rmsmm[1].2, expected);
+    }
+}
+
+#[test]
+fn check_goertzel_bank_change_freqs() {
+    use hexodsp::dsp::goertzel::GoertzelBank;
+
+    let new_bank = |freq: f32| {
+        let mut bank = GoertzelBank::new();
+        bank.set_sample_rate(44100.0);
+        bank.set_window(1024, 256);
+        bank.set_bands(&[freq]);
+        bank
+    };
+    let input = |i: usize| {
+        let t = i as f32 / 44100.0;
+        0.5 * (t * 1000.0 * 2.0 * std::f32::consts::PI).sin()
+            + 0.25 * (t * 3000.0 * 2.0 * std::f32::consts::PI).sin()
+    };
+
+    let mut bank = new_bank(1000.0);
+    let mut unchanged = new_bank(1000.0);
+    let mut fresh = None;
+    let mut fresh_compared = false;
+
+    for i in 0..8192 {
+        // The change lands in the middle of a hop:
+        if i == 2100 {
+            bank.set_bands(&[3000.0]);
+        }
+        if i > 2100 && fresh.is_none() && bank.is_hop_start() {
+            fresh = Some(new_bank(3000.0));
+        }
+
+        let completed = bank.tick(input(i));
+        unchanged.tick(input(i));
+
+        if let Some(fresh) = &mut fresh {
+            if fresh.tick(input(i)) {
+                // The first window that started after the change
+                // is the same as the first window of a fresh bank:
+                assert!(completed);
+                assert_eq!(bank.level(0), fresh.level(0));
+                assert_eq!(bank.complex(0), fresh.complex(0));
+                assert!((bank.level(0) - 0.25).abs() < 0.01, "{}", bank.level(0));
+                fresh_compared = true;
+                break;
+            }
+        }
+
+        // The windows that were running during the change
+        // finish with the previous frequency:
+        if completed {
+            assert_eq!(bank.level(0), unchanged.level(0));
+            assert_eq!(bank.complex(0), unchanged.complex(0));
+        }
+    }
+
+    assert!(fresh_compared);
+}
+
+#[test]
+fn check_node_gzbank_load_gz3filt() {
+    use hexodsp::matrix_repr::MatrixRepr;
+
+    // A patch with the Gz3Filt node, that was replaced by GzBank:
+    let patch = r#"{"VERSION":2,"atoms":[],
+        "cells":[["sin",0,0,0,[-1,-1,-1],[-1,-1,"sig"]],
+                 ["goertzel",0,0,1,["inp",-1,-1],[-1,-1,"sigf3"]],
+                 ["out",0,0,2,["ch1",-1,-1],[-1,-1,-1]]],
+        "params":[["sin",0,"freq",440.0],
+                  ["goertzel",0,"freq1",220.0],["goertzel",0,"freq2",330.0],
+                  ["goertzel",0,"freq3",440.0],["goertzel",0,"latency",2205.0],
+                  ["goertzel",0,"gain",1.0]]}"#;
+
+    let repr = MatrixRepr::deserialize(patch).unwrap();
+    assert_eq!(repr.cells[1].node_id, NodeId::GzBank(0));
+
+    init_test!(matrix, node_exec, 3);
+    matrix.from_repr(&repr).unwrap();
+
+    let gz = NodeId::GzBank(0);
+    assert_eq!(matrix.get_param(&gz.inp_param("scale").unwrap()), Some(SAtom::setting(3)));
+    assert_eq!(
+        matrix.get_param(&gz.inp_param("freqs").unwrap()),
+        Some(SAtom::micro(&[220.0, 330.0, 440.0]))
+    );
+    let win = gz.inp_param("win").unwrap();
+    assert_float_eq!(win.denorm(matrix.get_param(&win).unwrap().f()), 50.0);
+
+    // The third band is connected to the output:
+    let (ch1, _) = run_for_ms(node_exec, 200.0);
+    assert!((ch1[ch1.len() - 1] - 1.0).abs() < 0.01, "{}", ch1[ch1.len() - 1]);
+
+    // Before version 2 the parameters were saved normalized:
+    let patch = r#"{"VERSION":1,
+        "cells":[["goertzel",1,0,0,[-1,-1,-1],["sigf1",-1,-1]]],
+        "params":[["goertzel",1,"freq2",0.1]]}"#;
+    let repr = MatrixRepr::deserialize(patch).unwrap();
+    assert_eq!(repr.cells[0].node_id, NodeId::GzBank(1));
+    assert_eq!(repr.cells[0].out[0], 0);
+    let freqs = NodeId::GzBank(1).inp_param("freqs").unwrap();
+    assert_eq!(
+        repr.atoms.iter().find(|(p, _)| *p == freqs).map(|(_, a)| a.clone()),
+        Some(SAtom::micro(&[220.0, 880.0, 440.0]))
+    );
+}
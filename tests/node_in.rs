@@ -0,0 +1,55 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::render::OfflineRenderer;
+
+fn setup_in(matrix: &mut Matrix, out: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("in", out).node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+#[test]
+fn check_node_in_passthrough() {
+    init_test!(matrix, node_exec, 3);
+    setup_in(matrix, "sig1");
+
+    let input: Vec<f32> = (0..4410).map(|i| ((i as f32) * 0.05).sin()).collect();
+    let audio = OfflineRenderer::new(44100.0).render_input(node_exec, &[&input[..]], 0.1);
+    assert_eq!(audio.channel(0), &input[..]);
+
+    // The gain is applied, after it's smoothing is done:
+    pset_d(matrix, NodeId::In(0), "gain", 0.5);
+    OfflineRenderer::new(44100.0).render(node_exec, 0.1);
+    let audio = OfflineRenderer::new(44100.0).render_input(node_exec, &[&input[..]], 0.1);
+    for (out, inp) in audio.channel(0).iter().zip(input.iter()) {
+        assert_float_eq!(*out, 0.5 * inp);
+    }
+}
+
+#[test]
+fn check_node_in_channel_pairs() {
+    init_test!(matrix, node_exec, 3);
+    setup_in(matrix, "sig2");
+
+    let input = [[0.1; 128], [0.2; 128], [0.3; 128], [0.4; 128]];
+    let input: Vec<&[f32]> = input.iter().map(|ch| &ch[..]).collect();
+    let renderer = OfflineRenderer::new(44100.0).channels(1);
+
+    let audio = renderer.render_input(node_exec, &input[..], 0.001);
+    assert_float_eq!(audio.channel(0)[40], 0.2);
+
+    pset_s(matrix, NodeId::In(0), "chp", 1);
+    let audio = renderer.render_input(node_exec, &input[..], 0.001);
+    assert_float_eq!(audio.channel(0)[40], 0.4);
+
+    // Channels that are not provided are silent:
+    pset_s(matrix, NodeId::In(0), "chp", 2);
+    let audio = renderer.render_input(node_exec, &input[..], 0.001);
+    assert!(audio.channel(0).iter().all(|s| *s == 0.0));
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::{MidiEvent, MidiMsg};
+
+fn setup_midip(matrix: &mut Matrix, out: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("midip", out).node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+fn feed(node_exec: &mut hexodsp::nodes::NodeExecutor, frame: usize, bytes: &[u8]) {
+    assert!(node_exec.feed_midi_event(MidiEvent::from_bytes(frame, bytes).unwrap()));
+}
+
+fn out_fb(matrix: &mut Matrix, out: &str) -> f32 {
+    let midip = NodeId::MidiP(0);
+    matrix.update_output_feedback();
+    matrix.out_fb_for(&midip, midip.out(out).unwrap()).unwrap()
+}
+
+#[test]
+fn check_midi_msg_parse() {
+    assert_eq!(
+        MidiMsg::from_bytes(&[0x93, 60, 100]),
+        Some(MidiMsg::NoteOn { channel: 3, note: 60, vel: 100 })
+    );
+    assert_eq!(
+        MidiMsg::from_bytes(&[0x90, 60, 0]),
+        Some(MidiMsg::NoteOff { channel: 0, note: 60, vel: 0 })
+    );
+    assert_eq!(
+        MidiMsg::from_bytes(&[0xE1, 0x00, 0x40]),
+        Some(MidiMsg::PitchBend { channel: 1, value: 8192 })
+    );
+    assert_eq!(
+        MidiMsg::from_bytes(&[0xDF, 64]),
+        Some(MidiMsg::ChannelPressure { channel: 15, value: 64 })
+    );
+    assert_eq!(MidiMsg::from_bytes(&[0x90, 60]), None);
+    assert_eq!(MidiMsg::from_bytes(&[0xF8]), None);
+}
+
+#[test]
+fn check_node_midip_timestamps() {
+    init_test!(matrix, node_exec, 3);
+    setup_midip(matrix, "gate");
+
+    // The events are sample accurate, even across blocks:
+    feed(node_exec, 10, &[0x90, 60, 127]);
+    feed(node_exec, 300, &[0x80, 60, 0]);
+    feed(node_exec, 200, &[0x90, 64, 127]);
+    feed(node_exec, 300, &[0x80, 64, 0]);
+
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_eq!(collect_gates(&out[..]), vec![(10, 190), (201, 99)]);
+}
+
+#[test]
+fn check_node_midip_pitch() {
+    init_test!(matrix, node_exec, 3);
+    setup_midip(matrix, "pit");
+
+    feed(node_exec, 0, &[0x90, 81, 127]);
+    feed(node_exec, 64, &[0x90, 57, 64]);
+    let (out, _) = run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out[10], 0.1);
+    assert_float_eq!(out[100], -0.1);
+    assert_float_eq!(out_fb(matrix, "vel"), 64.0 / 127.0);
+
+    // Releasing the most recent note returns to the held note:
+    feed(node_exec, 0, &[0x80, 57, 0]);
+    let (out, _) = run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out[10], 0.1);
+    assert_float_eq!(out_fb(matrix, "gate"), 1.0);
+
+    // Pitch bend up to the default range of 2 semitones:
+    feed(node_exec, 0, &[0xE0, 0x7F, 0x7F]);
+    let (out, _) = run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out[10], 0.1 + 2.0 / 120.0);
+    assert_float_eq!(out_fb(matrix, "pb"), 1.0);
+
+    pset_s(matrix, NodeId::MidiP(0), "pbr", 12);
+    feed(node_exec, 0, &[0xE0, 0x00, 0x00]);
+    let (out, _) = run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out[10], 0.0);
+
+    feed(node_exec, 0, &[0xB0, 123, 0]);
+    run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out_fb(matrix, "gate"), 0.0);
+}
+
+#[test]
+fn check_node_midip_controllers() {
+    init_test!(matrix, node_exec, 3);
+    setup_midip(matrix, "mw");
+
+    feed(node_exec, 0, &[0xB0, 1, 127]);
+    feed(node_exec, 0, &[0xD0, 127]);
+    feed(node_exec, 0, &[0x90, 60, 100]);
+    feed(node_exec, 0, &[0xA0, 60, 0]);
+    let (out, _) = run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out[10], 1.0);
+    assert_float_eq!(out_fb(matrix, "at"), 0.0);
+    assert_float_eq!(out_fb(matrix, "vel"), 100.0 / 127.0);
+
+    // A poly pressure for another note is ignored:
+    feed(node_exec, 0, &[0xA0, 61, 127]);
+    run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out_fb(matrix, "at"), 0.0);
+}
+
+#[test]
+fn check_node_midip_channel() {
+    init_test!(matrix, node_exec, 3);
+    setup_midip(matrix, "gate");
+    pset_s(matrix, NodeId::MidiP(0), "chan", 2);
+
+    // Only the second channel is received:
+    feed(node_exec, 0, &[0x90, 60, 127]);
+    feed(node_exec, 50, &[0x91, 60, 127]);
+    let (out, _) = run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out[49], 0.0);
+    assert_float_eq!(out[50], 1.0);
+
+    // The MIDI input sends the events from another thread:
+    let mut midi_in = node_exec.take_midi_input().unwrap();
+    assert!(node_exec.take_midi_input().is_none());
+    std::thread::spawn(move || {
+        midi_in.send(MidiEvent::from_bytes(20, &[0x81, 60, 0]).unwrap());
+    })
+    .join()
+    .unwrap();
+
+    let (out, _) = run_for_ms(node_exec, 5.0);
+    assert_float_eq!(out[19], 1.0);
+    assert_float_eq!(out[20], 0.0);
+}
+
+#[test]
+fn check_node_midip_glide() {
+    init_test!(matrix, node_exec, 3);
+    setup_midip(matrix, "pit");
+    pset_d(matrix, NodeId::MidiP(0), "glide", 100.0);
+    run_for_ms(node_exec, 50.0);
+
+    feed(node_exec, 0, &[0x90, 81, 127]);
+    // After 50ms the pitch is half an octave up:
+    let (out, _) = run_for_ms(node_exec, 50.0);
+    assert!((out[out.len() - 1] - 0.05).abs() < 0.001);
+    let (out, _) = run_for_ms(node_exec, 100.0);
+    assert_float_eq!(out[out.len() - 1], 0.1);
+}
@@ -0,0 +1,121 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+fn setup_onset(matrix: &mut Matrix, src: &str, out: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out(src, "sig").node_io("onset", "inp", out).node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+/// Returns the start indices of the trigger pulses in `buf`.
+fn trigger_starts(buf: &[f32]) -> Vec<usize> {
+    collect_gates(buf).iter().map(|(start, _)| *start).collect()
+}
+
+#[test]
+fn check_node_onset_pulse_edges() {
+    init_test!(matrix, node_exec, 3);
+    setup_onset(matrix, "bosc", "t");
+
+    // Both edges of the pulse wave are clicks:
+    let bosc = NodeId::BOsc(0);
+    pset_s(matrix, bosc, "wtype", 3);
+    pset_d(matrix, bosc, "freq", 2.0);
+
+    let (out, _) = run_for_ms(node_exec, 2000.0);
+    let starts = trigger_starts(&out[..]);
+
+    // The start of the wave is an onset too:
+    assert_eq!(starts.len(), 9, "{:?}", starts);
+    assert!(starts[0] < 882, "{:?}", starts);
+
+    // Each edge repeats after one period of 500ms:
+    for w in starts[1..].windows(3) {
+        let period = (w[2] - w[0]) as i64;
+        assert!((period - 22050).abs() < 256, "{:?}", starts);
+    }
+}
+
+#[test]
+fn check_node_onset_steady_signal() {
+    init_test!(matrix, node_exec, 3);
+    setup_onset(matrix, "sin", "t");
+
+    // Only the start of the tone is an onset:
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    let starts = trigger_starts(&out[..]);
+    assert_eq!(starts.len(), 1, "{:?}", starts);
+    // The window introduces a latency of a few milliseconds:
+    assert!(starts[0] < 882, "{:?}", starts);
+
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    let starts = trigger_starts(&out[..]);
+    assert!(starts.is_empty(), "{:?}", starts);
+
+    // Switching on the tone again is an onset:
+    matrix.place(0, 0, Cell::empty(NodeId::Sin(0)));
+    matrix.sync().unwrap();
+    run_for_ms(node_exec, 100.0);
+
+    setup_onset(matrix, "sin", "t");
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    let starts = trigger_starts(&out[..]);
+    assert_eq!(starts.len(), 1, "{:?}", starts);
+    assert!(starts[0] < 882, "{:?}", starts);
+}
+
+#[test]
+fn check_node_onset_noise() {
+    init_test!(matrix, node_exec, 3);
+    setup_onset(matrix, "noise", "t");
+
+    run_for_ms(node_exec, 500.0);
+
+    // The random level changes of noise are no onsets:
+    let (out, _) = run_for_ms(node_exec, 2000.0);
+    let starts = trigger_starts(&out[..]);
+    assert!(starts.is_empty(), "{:?}", starts);
+}
+
+#[test]
+fn check_node_onset_hold() {
+    init_test!(matrix, node_exec, 3);
+    setup_onset(matrix, "bosc", "t");
+
+    let bosc = NodeId::BOsc(0);
+    pset_s(matrix, bosc, "wtype", 3);
+    // Edges every 62.5ms:
+    pset_d(matrix, bosc, "freq", 8.0);
+    pset_d(matrix, NodeId::Onset(0), "hold", 100.0);
+
+    let (out, _) = run_for_ms(node_exec, 1000.0);
+    let starts = trigger_starts(&out[..]);
+
+    // Only every second edge is detected:
+    assert_eq!(starts.len(), 8, "{:?}", starts);
+    for w in starts.windows(2) {
+        assert!(w[1] - w[0] > 4410, "{:?}", starts);
+    }
+}
+
+#[test]
+fn check_node_onset_odf() {
+    init_test!(matrix, node_exec, 3);
+    setup_onset(matrix, "bosc", "odf");
+
+    let bosc = NodeId::BOsc(0);
+    pset_s(matrix, bosc, "wtype", 3);
+    pset_d(matrix, bosc, "freq", 2.0);
+
+    for mode in [0, 1] {
+        pset_s(matrix, NodeId::Onset(0), "mode", mode);
+        let (out, _) = run_for_ms(node_exec, 500.0);
+        let max = out.iter().fold(0.0_f32, |a, s| a.max(*s));
+        assert!(max > 0.1, "mode={} max={}", mode, max);
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::render::OfflineRenderer;
+
+fn setup_out(matrix: &mut Matrix, ch: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("sin", "sig").node_inp("out", ch);
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+fn rms(buf: &[f32]) -> f32 {
+    (buf.iter().map(|s| s * s).sum::<f32>() / (buf.len() as f32)).sqrt()
+}
+
+#[test]
+fn check_node_out_multichannel() {
+    init_test!(matrix, node_exec, 3);
+    setup_out(matrix, "ch5");
+
+    let audio = OfflineRenderer::new(44100.0).channels(8).render(node_exec, 0.1);
+    assert_eq!(audio.channels.len(), 8);
+
+    for (i, ch) in audio.channels.iter().enumerate() {
+        if i == 4 {
+            assert!((rms(ch) - 0.7071).abs() < 0.01, "rms={}", rms(ch));
+        } else {
+            assert!(ch.iter().all(|s| *s == 0.0), "channel {}", i + 1);
+        }
+    }
+}
+
+#[test]
+fn check_node_out_ch16() {
+    init_test!(matrix, node_exec, 3);
+    setup_out(matrix, "ch16");
+    pset_d(matrix, NodeId::Out(0), "gain", 0.5);
+
+    let audio = OfflineRenderer::new(44100.0).channels(16).pre_roll(0.1).render(node_exec, 0.1);
+    assert!((rms(audio.channel(15)) - 0.3536).abs() < 0.01, "rms={}", rms(audio.channel(15)));
+
+    // Channels the context does not provide are skipped:
+    let audio = OfflineRenderer::new(44100.0).channels(4).render(node_exec, 0.1);
+    assert_eq!(audio.channels.len(), 4);
+    assert!(audio.channels.iter().all(|ch| ch.iter().all(|s| *s == 0.0)));
+}
+
+#[test]
+fn check_node_out_mono() {
+    init_test!(matrix, node_exec, 3);
+    setup_out(matrix, "ch1");
+    pset_s(matrix, NodeId::Out(0), "mono", 1);
+
+    let audio = OfflineRenderer::new(44100.0).channels(3).render(node_exec, 0.1);
+    assert!(rms(audio.channel(0)) > 0.7);
+    assert_eq!(audio.channel(0), audio.channel(1));
+    assert!(audio.channel(2).iter().all(|s| *s == 0.0));
+}
@@ -0,0 +1,78 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+fn setup_ptrack(matrix: &mut Matrix, src: &str, out: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out(src, "sig").node_io("ptrack", "inp", out).node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+fn n_pit(freq: f32) -> f32 {
+    0.1 * (freq / 440.0).log2()
+}
+
+#[test]
+fn check_node_ptrack_sine() {
+    init_test!(matrix, node_exec, 3);
+    setup_ptrack(matrix, "sin", "freq");
+
+    for freq in [220.0, 440.0, 155.56, 1000.0] {
+        pset_d(matrix, NodeId::Sin(0), "freq", freq);
+        let (out, _) = run_for_ms(node_exec, 200.0);
+        let detected = out[out.len() - 1];
+        // Within 5 cent:
+        assert!(
+            (detected - n_pit(freq)).abs() < 0.1 / 12.0 * 0.05,
+            "{} => {} != {}",
+            freq,
+            detected,
+            n_pit(freq)
+        );
+    }
+}
+
+#[test]
+fn check_node_ptrack_saw() {
+    init_test!(matrix, node_exec, 3);
+    setup_ptrack(matrix, "bosc", "freq");
+
+    let bosc = NodeId::BOsc(0);
+    pset_s(matrix, bosc, "wtype", 2);
+
+    for freq in [110.0, 82.41, 329.63] {
+        pset_d(matrix, bosc, "freq", freq);
+        let (out, _) = run_for_ms(node_exec, 200.0);
+        let detected = out[out.len() - 1];
+        assert!((detected - n_pit(freq)).abs() < 0.1 / 12.0 * 0.1, "{} => {}", freq, detected);
+    }
+}
+
+#[test]
+fn check_node_ptrack_gate() {
+    init_test!(matrix, node_exec, 3);
+    setup_ptrack(matrix, "noise", "gat");
+
+    // Noise has no pitch:
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert!(out.iter().all(|v| *v < 0.5));
+}
+
+#[test]
+fn check_node_ptrack_sine_gate() {
+    init_test!(matrix, node_exec, 3);
+    setup_ptrack(matrix, "sin", "gat");
+
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert_float_eq!(out[out.len() - 1], 1.0);
+
+    // Out of the detection range:
+    pset_d(matrix, NodeId::PTrack(0), "fmin", 600.0);
+    pset_d(matrix, NodeId::PTrack(0), "fmax", 1200.0);
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    assert_float_eq!(out[out.len() - 1], 0.0);
+}
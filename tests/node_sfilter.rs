@@ -1633,27 +1633,16 @@ fn check_node_sfilter_moog_lowpass() {
         ]
     );
 
-    // Low Pass Stilson/Moog @ 22050Hz RES=1.0
-    let fft = fft_with_freq_res_type(&mut matrix, &mut node_exec, 13, 22050.0, 1.0);
-    assert_eq!(
-        avg_fft_freqs(8.0, &[100, 1000, 4000, 12000, 16000, 20000, 22050, 22051], &fft[..]),
-        vec![
-            (0, 0),
-            (100, 0),
-            (1000, 0),
-            (4000, 0),
-            (12000, 0),
-            (16000, 0),
-            (20000, 0),
-            (22050, 0)
-        ]
-    );
+    // Low Pass Stilson/Moog @ 22050Hz RES=1.0 is unstable,
+    // see check_signal_guard_unstable_filter in tests/signal_guard.rs.
+
+    let (mut matrix, mut node_exec) = setup_sfilter_matrix();
 
     // Low Pass Stilson/Moog @ 0Hz RES=0.0
     let fft = fft_with_freq_res_type(&mut matrix, &mut node_exec, 13, 0.0, 0.0);
     assert_eq!(
         avg_fft_freqs(4.0, &[10, 100, 1000, 4000, 12000, 22050, 22051], &fft[..]),
-        vec![(0, 0), (10, 0), (100, 0), (1000, 0), (4000, 0), (12000, 0), (22050, 0)]
+        vec![(0, 4), (10, 0), (100, 0), (1000, 0), (4000, 0), (12000, 0), (22050, 0)]
     );
 
     // Low Pass Stilson/Moog @ 0Hz RES=1.0
@@ -1661,7 +1650,7 @@ fn check_node_sfilter_moog_lowpass() {
     assert_eq!(
         avg_fft_freqs(4.0, &[1, 5, 10, 100, 1000, 4000, 12000, 22050, 22051], &fft[..]),
         vec![
-            (0, 0),
+            (0, 24),
             (1, 0),
             (5, 0),
             (10, 0),
@@ -0,0 +1,152 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::matrix_repr::MatrixRepr;
+use hexodsp::nodes::NodeState;
+use hexodsp::Context;
+
+fn setup_test_amp(matrix: &mut Matrix) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("test", "sig").node_io("amp", "inp", "sig").node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+
+    let p = NodeId::Test(0).inp_param("p").unwrap();
+    matrix.set_param(p, SAtom::param(0.25));
+    pset_d(matrix, NodeId::Amp(0), "gain", 2.0);
+}
+
+fn last_out(node_exec: &mut NodeExecutor) -> f32 {
+    let (out_l, _) = run_for_ms(node_exec, 10.0);
+    out_l[out_l.len() - 1]
+}
+
+#[test]
+fn check_node_state_bypass_mute() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    setup_test_amp(&mut matrix);
+
+    assert_float_eq!(last_out(&mut node_exec), 0.5);
+
+    // No sync is required for changing the state:
+    matrix.set_node_state(NodeId::Amp(0), NodeState::Bypassed);
+    assert_eq!(matrix.node_state(&NodeId::Amp(0)), NodeState::Bypassed);
+    assert_float_eq!(last_out(&mut node_exec), 0.25);
+
+    matrix.set_node_state(NodeId::Amp(0), NodeState::Muted);
+    assert_float_eq!(last_out(&mut node_exec), 0.0);
+
+    matrix.set_node_state(NodeId::Amp(0), NodeState::Active);
+    assert_eq!(matrix.node_state(&NodeId::Amp(0)), NodeState::Active);
+    assert_float_eq!(last_out(&mut node_exec), 0.5);
+
+    // The state is kept on a new program:
+    matrix.set_node_state(NodeId::Amp(0), NodeState::Bypassed);
+    matrix.sync().unwrap();
+    assert_float_eq!(last_out(&mut node_exec), 0.25);
+}
+
+#[test]
+fn check_node_state_mute_out() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    setup_test_amp(&mut matrix);
+
+    matrix.set_node_state(NodeId::Out(0), NodeState::Muted);
+    let (out_l, out_r) = run_for_ms(&mut node_exec, 10.0);
+    assert!(out_l.iter().chain(out_r.iter()).all(|s| *s == 0.0));
+
+    matrix.set_node_state(NodeId::Out(0), NodeState::Active);
+    assert_float_eq!(last_out(&mut node_exec), 0.5);
+}
+
+/// Processes one period of `nframes` and returns the left channel.
+/// Unlike [run_for_ms], this does not clear the feedback delays.
+fn process_period(node_exec: &mut NodeExecutor, nframes: usize) -> Vec<f32> {
+    let input = vec![0.0; nframes];
+    let mut out_l = vec![0.0; nframes];
+    let mut out_r = vec![0.0; nframes];
+
+    node_exec.process_graph_updates();
+    node_exec.process(&mut Context {
+        nframes,
+        output: &mut [&mut out_l[..], &mut out_r[..]],
+        input: &[&input[..]],
+    });
+
+    out_l
+}
+
+#[test]
+fn check_node_state_mute_feedback() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("test", "sig").node_inp("fbwr", "inp");
+    chain.place(&mut matrix, 0, 0).unwrap();
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("fbrd", "sig").node_inp("out", "ch1");
+    chain.place(&mut matrix, 1, 0).unwrap();
+    matrix.sync().unwrap();
+    node_exec.set_sample_rate(44100.0);
+
+    let p = NodeId::Test(0).inp_param("p").unwrap();
+    matrix.set_param(p, SAtom::param(0.25));
+    let out = process_period(&mut node_exec, 512);
+    assert_eq!(out.iter().position(|s| *s == 0.25), Some(138));
+
+    // A muted FbWr writes silence instead of leaving the old signal
+    // in the delay:
+    matrix.set_node_state(NodeId::FbWr(0), NodeState::Muted);
+    let out = process_period(&mut node_exec, 512);
+    assert_float_eq!(out[137], 0.25);
+    assert!(out[138..].iter().all(|s| *s == 0.0));
+
+    matrix.set_node_state(NodeId::FbWr(0), NodeState::Active);
+    let out = process_period(&mut node_exec, 512);
+    assert_eq!(out.iter().position(|s| *s == 0.25), Some(138));
+
+    // A muted FbRd keeps reading, so the delay stays 3.14ms:
+    matrix.set_node_state(NodeId::FbRd(0), NodeState::Muted);
+    let out = process_period(&mut node_exec, 512);
+    assert!(out.iter().all(|s| *s == 0.0));
+
+    matrix.set_node_state(NodeId::FbRd(0), NodeState::Active);
+    matrix.set_param(p, SAtom::param(0.5));
+    let out = process_period(&mut node_exec, 512);
+    assert_float_eq!(out[0], 0.25);
+    assert_eq!(out.iter().position(|s| *s == 0.5), Some(138));
+}
+
+#[test]
+fn check_node_state_repr() {
+    let (node_conf, _node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    setup_test_amp(&mut matrix);
+
+    matrix.set_node_state(NodeId::Amp(0), NodeState::Bypassed);
+    matrix.set_node_state(NodeId::Test(0), NodeState::Muted);
+
+    let s = matrix.to_repr().serialize();
+    let repr = MatrixRepr::deserialize(&s).unwrap();
+    assert_eq!(
+        repr.node_states,
+        vec![(NodeId::Amp(0), NodeState::Bypassed), (NodeId::Test(0), NodeState::Muted)]
+    );
+
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix2 = Matrix::new(node_conf, 3, 3);
+    matrix2.from_repr(&repr).unwrap();
+    assert_eq!(matrix2.node_state(&NodeId::Amp(0)), NodeState::Bypassed);
+    assert_eq!(matrix2.node_state(&NodeId::Test(0)), NodeState::Muted);
+    assert_float_eq!(last_out(&mut node_exec), 0.0);
+
+    // Clearing the matrix resets the states:
+    matrix2.clear();
+    assert_eq!(matrix2.node_state(&NodeId::Amp(0)), NodeState::Active);
+}
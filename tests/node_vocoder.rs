@@ -0,0 +1,102 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+fn setup_vocoder(matrix: &mut Matrix) {
+    let sin = NodeId::Sin(0);
+    let noise = NodeId::Noise(0);
+    let voc = NodeId::Vocoder(0);
+    let out = NodeId::Out(0);
+    matrix.place(1, 0, Cell::empty(sin).out(None, None, sin.out("sig")));
+    matrix.place(0, 1, Cell::empty(noise).out(None, noise.out("sig"), None));
+    matrix.place(
+        1,
+        1,
+        Cell::empty(voc).input(voc.inp("modu"), voc.inp("car"), None).out(
+            None,
+            None,
+            voc.out("sig"),
+        ),
+    );
+    matrix.place(1, 2, Cell::empty(out).input(out.inp("ch1"), None, None));
+    matrix.sync().unwrap();
+}
+
+/// Sums the FFT amplitudes of the bins between `from` and `to` Hz.
+fn band_sum(fft: &[(u16, u32)], from: u16, to: u16) -> u32 {
+    fft.iter().filter(|(f, _)| *f >= from && *f < to).map(|(_, a)| *a).sum()
+}
+
+#[test]
+fn check_node_vocoder_silent_modulator() {
+    init_test!(matrix, node_exec, 3);
+    setup_vocoder(matrix);
+
+    pset_d(matrix, NodeId::Sin(0), "freq", 1000.0);
+    // Detach the modulator, only the carrier noise is left:
+    matrix.place(1, 0, Cell::empty(NodeId::Sin(0)));
+    matrix.sync().unwrap();
+
+    let (out, _) = run_for_ms(node_exec, 200.0);
+    let max = out.iter().fold(0.0_f32, |a, s| a.max(s.abs()));
+    assert!(max < 0.0001, "max={}", max);
+}
+
+#[test]
+fn check_node_vocoder_imprint() {
+    init_test!(matrix, node_exec, 3);
+    setup_vocoder(matrix);
+
+    pset_d(matrix, NodeId::Sin(0), "freq", 1000.0);
+    run_for_ms(node_exec, 200.0);
+
+    let fft = run_and_get_avg_fft4096_now(node_exec, 0);
+    let around = band_sum(&fft[..], 800, 1250);
+    let low = band_sum(&fft[..], 200, 500);
+    let high = band_sum(&fft[..], 2000, 5000);
+    assert!(around > 0, "around={}", around);
+    // The noise carrier only comes through around the modulator frequency:
+    assert!(around > 10 * low, "around={} low={}", around, low);
+    assert!(around > 10 * high, "around={} high={}", around, high);
+}
+
+#[test]
+fn check_node_vocoder_fshft() {
+    init_test!(matrix, node_exec, 3);
+    setup_vocoder(matrix);
+
+    pset_d(matrix, NodeId::Sin(0), "freq", 1000.0);
+    pset_d(matrix, NodeId::Vocoder(0), "fshft", 12.0);
+    run_for_ms(node_exec, 200.0);
+
+    // One octave up, the carrier comes through around 2kHz:
+    let fft = run_and_get_avg_fft4096_now(node_exec, 0);
+    let orig = band_sum(&fft[..], 800, 1250);
+    let shifted = band_sum(&fft[..], 1600, 2500);
+    assert!(shifted > 5 * orig, "shifted={} orig={}", shifted, orig);
+}
+
+#[test]
+fn check_node_vocoder_bands() {
+    init_test!(matrix, node_exec, 3);
+    setup_vocoder(matrix);
+
+    pset_d(matrix, NodeId::Sin(0), "freq", 1000.0);
+
+    // With more bands, the bands get narrower:
+    let mut widths = vec![];
+    for bands in [4, 32] {
+        pset_s(matrix, NodeId::Vocoder(0), "bands", bands);
+        run_for_ms(node_exec, 200.0);
+
+        let fft = run_and_get_avg_fft4096_now(node_exec, 0);
+        let around = band_sum(&fft[..], 800, 1250);
+        let total = band_sum(&fft[..], 0, 22050);
+        widths.push(total as f32 / around as f32);
+    }
+
+    assert!(widths[0] > widths[1] * 1.5, "widths={:?}", widths);
+}
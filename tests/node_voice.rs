@@ -0,0 +1,142 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::{VoiceAllocator, VoiceSteal};
+
+fn setup_voice(matrix: &mut Matrix, out: &str) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("voice", out).node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+fn has_peak_near(fft: &[(u16, u32)], freq: u16) -> bool {
+    fft.iter().any(|(f, _)| (*f as i32 - freq as i32).abs() < 15)
+}
+
+#[test]
+fn check_voice_allocator_steal() {
+    let mut va = VoiceAllocator::new();
+    va.set_voices(3, VoiceSteal::Oldest);
+    assert_eq!(va.note_on(60, 1.0), Some(0));
+    assert_eq!(va.note_on(72, 1.0), Some(1));
+    assert_eq!(va.note_on(48, 1.0), Some(2));
+    // Replaying a held note retriggers it's voice:
+    assert_eq!(va.note_on(72, 0.5), Some(1));
+    assert_eq!(va.note_on(50, 1.0), Some(0));
+
+    // The voice that was released first is reused first:
+    va.note_off(48);
+    va.note_off(50);
+    assert_eq!(va.note_on(53, 1.0), Some(2));
+    assert_eq!(va.note_on(55, 1.0), Some(0));
+
+    va.set_voices(2, VoiceSteal::Lowest);
+    va.note_on(60, 1.0);
+    let low = va.note_on(48, 1.0);
+    assert_eq!(va.note_on(67, 1.0), low);
+    assert_eq!(va.voice(low.unwrap()).note, 67);
+
+    va.set_voices(2, VoiceSteal::Highest);
+    let high = va.note_on(60, 1.0);
+    va.note_on(48, 1.0);
+    assert_eq!(va.note_on(67, 1.0), high);
+
+    va.set_voices(2, VoiceSteal::None);
+    va.note_on(60, 1.0);
+    va.note_on(48, 1.0);
+    assert_eq!(va.note_on(67, 1.0), None);
+    assert_eq!(va.voice(0).note, 60);
+    assert_eq!(va.voice(1).note, 48);
+}
+
+#[test]
+fn check_node_voice_mono() {
+    init_test!(matrix, node_exec, 3);
+    setup_voice(matrix, "pit");
+
+    matrix.note_on(81, 1.0);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out[100], 0.1);
+
+    matrix.note_on(57, 1.0);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out[100], -0.1);
+}
+
+#[test]
+fn check_node_voice_vel() {
+    init_test!(matrix, node_exec, 3);
+    setup_voice(matrix, "vel");
+
+    matrix.note_on(69, 0.5);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out[100], 0.5);
+
+    pset_d(matrix, NodeId::Voice(0), "vsens", 0.5);
+    run_for_ms(node_exec, 50.0);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out[100], 0.75);
+}
+
+#[test]
+fn check_node_voice_region_sum() {
+    init_test!(matrix, node_exec, 3);
+    matrix.set_voice_region(&[NodeId::Voice(0)], 2);
+    setup_voice(matrix, "gate");
+
+    matrix.note_on(60, 1.0);
+    matrix.note_on(64, 1.0);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out[100], 2.0);
+
+    matrix.note_off(60);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out[100], 1.0);
+
+    // Stealing a playing voice retriggers it's gate:
+    matrix.note_on(60, 1.0);
+    matrix.note_on(67, 1.0);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out[0], 1.0);
+    assert_float_eq!(out[1], 2.0);
+
+    // Disabling the voice region plays only the first voice:
+    matrix.set_voice_region(&[NodeId::Voice(0)], 1);
+    matrix.sync().unwrap();
+    matrix.note_on(60, 1.0);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out[100], 1.0);
+}
+
+#[test]
+fn check_node_voice_region_osc() {
+    init_test!(matrix, node_exec, 3);
+    matrix.set_voice_region(&[NodeId::Voice(0), NodeId::Sin(0)], 2);
+
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("voice", "pit").node_io("sin", "freq", "sig").node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+
+    matrix.note_on(69, 1.0);
+    matrix.note_on(81, 1.0);
+    run_for_ms(node_exec, 50.0);
+
+    let fft = run_and_get_fft4096_now(node_exec, 200);
+    assert!(has_peak_near(&fft[..], 440), "{:?}", fft);
+    assert!(has_peak_near(&fft[..], 880), "{:?}", fft);
+
+    // The voices are playing with the same parameters:
+    pset_mod(matrix, NodeId::Sin(0), "freq", 1.0);
+    pset_d(matrix, NodeId::Sin(0), "freq", 880.0);
+    run_for_ms(node_exec, 50.0);
+    let fft = run_and_get_fft4096_now(node_exec, 200);
+    assert!(has_peak_near(&fft[..], 880), "{:?}", fft);
+    assert!(has_peak_near(&fft[..], 1760), "{:?}", fft);
+    assert!(!has_peak_near(&fft[..], 440), "{:?}", fft);
+}
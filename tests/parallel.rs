@@ -0,0 +1,145 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::ProgLevel;
+
+fn setup_patch(matrix: &mut Matrix) {
+    matrix.set_voice_region(&[NodeId::Voice(0), NodeId::Sin(0)], 3);
+
+    // A voice region, mixed with an oscillator and a feedback delay:
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("voice", "pit").node_io("sin", "freq", "sig");
+    chain.place(matrix, 2, 0).unwrap();
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("bosc", "sig");
+    chain.place(matrix, 1, 1).unwrap();
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("vosc", "sig").node_inp("fbwr", "inp");
+    chain.place(matrix, 0, 0).unwrap();
+
+    let pverb = NodeId::PVerb(0);
+    let sfilter = NodeId::SFilter(0);
+    let fbrd = NodeId::FbRd(0);
+    let mix3 = NodeId::Mix3(0);
+    let out = NodeId::Out(0);
+    matrix.place(
+        2,
+        2,
+        Cell::empty(pverb).input(pverb.inp("in_l"), None, None).out(None, None, pverb.out("sig_l")),
+    );
+    matrix.place(
+        1,
+        2,
+        Cell::empty(sfilter).input(sfilter.inp("inp"), None, None).out(
+            None,
+            sfilter.out("sig"),
+            None,
+        ),
+    );
+    matrix.place(1, 3, Cell::empty(fbrd).out(fbrd.out("sig"), None, None));
+    matrix.place(
+        2,
+        3,
+        Cell::empty(mix3).input(mix3.inp("ch1"), mix3.inp("ch2"), mix3.inp("ch3")).out(
+            None,
+            None,
+            mix3.out("sig"),
+        ),
+    );
+    matrix.place(2, 4, Cell::empty(out).input(out.inp("ch1"), None, None));
+    matrix.sync().unwrap();
+
+    pset_mod(matrix, NodeId::Sin(0), "freq", 0.5);
+    pset_d(matrix, NodeId::PVerb(0), "mix", 0.7);
+}
+
+fn render(workers: usize) -> Vec<f32> {
+    init_test!(matrix, node_exec, 5);
+    setup_patch(matrix);
+    matrix.set_worker_threads(workers);
+
+    matrix.note_on(60, 1.0);
+    matrix.note_on(67, 0.5);
+    let (mut out, _) = run_for_ms(node_exec, 200.0);
+
+    // Smoothed parameter changes:
+    pset_d(matrix, NodeId::SFilter(0), "freq", 200.0);
+    pset_mod(matrix, NodeId::Sin(0), "freq", -0.25);
+    matrix.note_on(72, 1.0);
+    out.append(&mut run_for_ms(node_exec, 200.0).0);
+
+    out
+}
+
+#[test]
+fn check_parallel_bit_identical() {
+    let seq = render(0);
+    assert!(seq.iter().any(|s| s.abs() > 0.01));
+
+    for workers in [1, 3] {
+        let par = render(workers);
+        assert!(seq == par, "{} workers differ", workers);
+    }
+}
+
+#[test]
+fn check_parallel_switching() {
+    init_test!(matrix, node_exec, 5);
+    setup_patch(matrix);
+    matrix.note_on(64, 1.0);
+    let (mut out, _) = run_for_ms(node_exec, 100.0);
+
+    matrix.set_worker_threads(2);
+    out.append(&mut run_for_ms(node_exec, 100.0).0);
+    matrix.set_worker_threads(0);
+    out.append(&mut run_for_ms(node_exec, 100.0).0);
+
+    init_test!(matrix, node_exec, 5);
+    setup_patch(matrix);
+    matrix.note_on(64, 1.0);
+    let (mut seq, _) = run_for_ms(node_exec, 100.0);
+    for _ in 0..2 {
+        seq.append(&mut run_for_ms(node_exec, 100.0).0);
+    }
+
+    assert!(seq == out);
+}
+
+#[test]
+fn check_parallel_levels() {
+    init_test!(matrix, node_exec, 5);
+    setup_patch(matrix);
+    run_for_ms(node_exec, 10.0);
+
+    let prog = node_exec.get_prog();
+    let bound: Vec<bool> =
+        prog.schedule.iter().map(|op_pos| prog.prog[*op_pos].context_bound).collect();
+
+    // Each op is scheduled once:
+    let mut scheduled = prog.schedule.clone();
+    scheduled.sort();
+    assert_eq!(scheduled, (0..prog.prog.len()).collect::<Vec<usize>>());
+
+    // The 3 voices, the FbRd and the oscillators are independent,
+    // followed by the voices of the Sin and the FbWr:
+    assert_eq!(prog.levels[0], ProgLevel { serial: (0, 4), parallel: (4, 6) }, "{:?}", prog.levels);
+    assert_eq!(
+        prog.levels[1],
+        ProgLevel { serial: (6, 7), parallel: (7, 10) },
+        "{:?}",
+        prog.levels
+    );
+    assert!(bound[0..4].iter().all(|b| *b));
+    assert!(!bound[4..6].iter().any(|b| *b));
+
+    // The context bound ops keep their order:
+    let bound_ops: Vec<usize> =
+        prog.schedule.iter().copied().filter(|op_pos| prog.prog[*op_pos].context_bound).collect();
+    let mut sorted = bound_ops.clone();
+    sorted.sort();
+    assert_eq!(bound_ops, sorted);
+}
@@ -0,0 +1,149 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::NodeState;
+
+fn setup_amps(matrix: &mut Matrix) {
+    let out = NodeId::Out(0);
+    matrix.place(0, 0, Cell::empty(NodeId::Amp(0)).out(None, None, Some(0)));
+    matrix.place(0, 1, Cell::empty(out).input(out.inp("ch1"), None, None));
+    matrix.sync().unwrap();
+
+    pset_d(matrix, NodeId::Amp(0), "inp", 0.5);
+    pset_d(matrix, NodeId::Amp(1), "inp", -0.5);
+}
+
+/// Routes `Amp(1)` instead of `Amp(0)` to the `Out` node and
+/// returns the output of the next 20ms.
+fn switch_amps(matrix: &mut Matrix, node_exec: &mut NodeExecutor) -> Vec<f32> {
+    let (out_l, _) = run_for_ms(node_exec, 50.0);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.5);
+
+    matrix.place(0, 0, Cell::empty(NodeId::Amp(1)).out(None, None, Some(0)));
+    matrix.sync().unwrap();
+
+    run_for_ms(node_exec, 20.0).0
+}
+
+#[test]
+fn check_prog_crossfade_off() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    setup_amps(&mut matrix);
+
+    let out_l = switch_amps(&mut matrix, &mut node_exec);
+    assert_float_eq!(out_l[0], -0.5);
+    assert_float_eq!(out_l[100], -0.5);
+}
+
+#[test]
+fn check_prog_crossfade_routing() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    matrix.set_prog_crossfade(10.0);
+    setup_amps(&mut matrix);
+
+    // 10ms are 441 frames:
+    let out_l = switch_amps(&mut matrix, &mut node_exec);
+    assert_float_eq!(out_l[0], 0.5);
+    assert_float_eq!(out_l[147], 0.5 - 147.0 / 441.0);
+    assert_float_eq!(out_l[294], 0.5 - 294.0 / 441.0);
+    assert_float_eq!(out_l[441], -0.5);
+    assert_float_eq!(out_l[out_l.len() - 1], -0.5);
+
+    let max_step = out_l.windows(2).map(|w| (w[1] - w[0]).abs()).fold(0.0, f32::max);
+    assert!(max_step < 0.003, "max step {}", max_step);
+}
+
+#[test]
+fn check_prog_crossfade_removed_node() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    matrix.set_prog_crossfade(10.0);
+
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("sin", "sig").node_inp("out", "ch1");
+    chain.place(&mut matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+
+    run_for_ms(&mut node_exec, 50.0);
+
+    matrix.place(0, 0, Cell::empty(NodeId::Nop));
+    matrix.sync().unwrap();
+
+    // The removed Sin node keeps running and fades out:
+    let (out_l, _) = run_for_ms(&mut node_exec, 20.0);
+    let (rms, min, max) = calc_rms_mimax_each_ms(&out_l[0..100], 2.0)[0];
+    assert!(rms > 0.1, "rms {} min {} max {}", rms, min, max);
+
+    let peak = |buf: &[f32]| buf.iter().map(|s| s.abs()).fold(0.0, f32::max);
+    assert!(peak(&out_l[0..147]) > peak(&out_l[294..441]));
+    assert!(peak(&out_l[294..441]) > 0.0);
+    assert_float_eq!(peak(&out_l[441..]), 0.0);
+}
+
+#[test]
+fn check_prog_crossfade_parallel() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    matrix.set_worker_threads(2);
+    matrix.set_prog_crossfade(10.0);
+    setup_amps(&mut matrix);
+
+    let out_l = switch_amps(&mut matrix, &mut node_exec);
+    assert_float_eq!(out_l[0], 0.5);
+    assert_float_eq!(out_l[294], 0.5 - 294.0 / 441.0);
+    assert_float_eq!(out_l[441], -0.5);
+}
+
+#[test]
+fn check_prog_crossfade_rerouted_node() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    matrix.set_prog_crossfade(10.0);
+
+    let (amp, out) = (NodeId::Amp(2), NodeId::Out(0));
+    matrix.place(0, 0, Cell::empty(NodeId::Amp(0)).out(None, None, Some(0)));
+    matrix.place(0, 1, Cell::empty(amp).input(amp.inp("inp"), None, None).out(None, None, Some(0)));
+    matrix.place(0, 2, Cell::empty(out).input(out.inp("ch1"), None, None));
+    matrix.sync().unwrap();
+    pset_d(&mut matrix, NodeId::Amp(0), "inp", 0.5);
+    pset_d(&mut matrix, NodeId::Amp(1), "inp", -0.5);
+
+    // Amp(2) is in both programs, the previous program executes a
+    // clone of it with the previous input:
+    let out_l = switch_amps(&mut matrix, &mut node_exec);
+    assert_float_eq!(out_l[0], 0.5);
+    assert_float_eq!(out_l[147], 0.5 - 147.0 / 441.0);
+    assert_float_eq!(out_l[294], 0.5 - 294.0 / 441.0);
+    assert_float_eq!(out_l[441], -0.5);
+    assert_float_eq!(out_l[out_l.len() - 1], -0.5);
+
+    let max_step = out_l.windows(2).map(|w| (w[1] - w[0]).abs()).fold(0.0, f32::max);
+    assert!(max_step < 0.003, "max step {}", max_step);
+}
+
+#[test]
+fn check_prog_crossfade_mute_prev_node() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    matrix.set_prog_crossfade(10.0);
+    setup_amps(&mut matrix);
+
+    let (out_l, _) = run_for_ms(&mut node_exec, 50.0);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.5);
+
+    matrix.place(0, 0, Cell::empty(NodeId::Amp(1)).out(None, None, Some(0)));
+    matrix.sync().unwrap();
+
+    // Amp(0) is only executed by the previous program while fading:
+    matrix.set_node_state(NodeId::Amp(0), NodeState::Muted);
+    let (out_l, _) = run_for_ms(&mut node_exec, 20.0);
+    assert_float_eq!(out_l[0], 0.0);
+    assert_float_eq!(out_l[147], -0.5 * 147.0 / 441.0);
+    assert_float_eq!(out_l[441], -0.5);
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::render::OfflineRenderer;
+
+fn setup_sine(matrix: &mut Matrix) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("sin", "sig").node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+/// Counts the rising zero crossings in `buf`.
+fn count_periods(buf: &[f32]) -> usize {
+    buf.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count()
+}
+
+#[test]
+fn check_render_sample_rate() {
+    init_test!(matrix, node_exec, 3);
+    setup_sine(matrix);
+    pset_d(matrix, NodeId::Sin(0), "freq", 1000.0);
+
+    // Skip the smoothing of the frequency change:
+    let audio = OfflineRenderer::new(48000.0).pre_roll(0.1).render(node_exec, 1.0);
+    assert_eq!(audio.len(), 48000);
+    assert_eq!(audio.channels.len(), 2);
+
+    // The sine is still 1kHz at the different sample rate:
+    let periods = count_periods(audio.channel(0));
+    assert!((999..=1000).contains(&periods), "periods={}", periods);
+    assert!(audio.channel(1).iter().all(|s| *s == 0.0));
+}
+
+#[test]
+fn check_render_block_size() {
+    init_test!(matrix, _node_exec, 3);
+    setup_sine(matrix);
+    pset_d(matrix, NodeId::Sin(0), "freq", 440.0);
+    let repr = matrix.to_repr();
+
+    let a = OfflineRenderer::new(44100.0).render_repr(&repr, 0.5).unwrap();
+    let b = OfflineRenderer::new(44100.0).block_size(37).render_repr(&repr, 0.5).unwrap();
+    let c = OfflineRenderer::new(44100.0).block_size(1).render_repr(&repr, 0.5).unwrap();
+
+    assert_eq!(a.len(), 22050);
+    assert_eq!(a.channels, b.channels);
+    assert_eq!(a.channels, c.channels);
+    assert!(count_periods(a.channel(0)) >= 219);
+}
+
+#[test]
+fn check_render_pre_roll() {
+    init_test!(matrix, _node_exec, 3);
+    setup_sine(matrix);
+    let repr = matrix.to_repr();
+
+    let full = OfflineRenderer::new(44100.0).channels(1).render_repr(&repr, 0.15).unwrap();
+    let rolled =
+        OfflineRenderer::new(44100.0).channels(1).pre_roll(0.05).render_repr(&repr, 0.1).unwrap();
+
+    assert_eq!(rolled.channels.len(), 1);
+    assert_eq!(rolled.len(), 4410);
+    assert_eq!(rolled.channel(0), &full.channel(0)[2205..]);
+}
+
+#[test]
+fn check_render_tail() {
+    init_test!(matrix, node_exec, 3);
+
+    // Silence stops the tail after 100ms:
+    let audio = OfflineRenderer::new(44100.0).tail(0.001, 2.0).render(node_exec, 0.1);
+    assert_eq!(audio.len(), 4410 + 4410);
+
+    // A never ending sine is limited by the maximum tail length:
+    setup_sine(matrix);
+    let audio = OfflineRenderer::new(44100.0).tail(0.001, 0.5).render(node_exec, 0.1);
+    assert_eq!(audio.len(), 4410 + 22050);
+}
+
+#[test]
+fn check_render_wav() {
+    init_test!(matrix, node_exec, 3);
+    setup_sine(matrix);
+
+    let audio = OfflineRenderer::new(22050.0).render(node_exec, 0.1);
+    let path =
+        std::env::temp_dir().join(format!("hexodsp_check_render_wav_{}.wav", std::process::id()));
+    let path = path.to_str().unwrap();
+    audio.write_wav(path).unwrap();
+
+    let mut reader = hound::WavReader::open(path).unwrap();
+    let spec = reader.spec();
+    let samples: Vec<f32> = reader.samples::<f32>().map(|s| s.unwrap()).collect();
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(spec.channels, 2);
+    assert_eq!(spec.sample_rate, 22050);
+
+    let left: Vec<f32> = samples.iter().step_by(2).copied().collect();
+    assert_eq!(&left[..], audio.channel(0));
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::{SignalFault, SignalFaultKind};
+use std::sync::{Arc, Mutex};
+
+fn setup_test_amp(matrix: &mut Matrix) {
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("test", "sig").node_io("amp", "inp", "sig").node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+}
+
+fn set_test_p(matrix: &mut Matrix, v: f32) {
+    let p = NodeId::Test(0).inp_param("p").unwrap();
+    matrix.set_param(p, SAtom::param(v));
+}
+
+#[test]
+fn check_signal_guard_nan() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    setup_test_amp(&mut matrix);
+
+    set_test_p(&mut matrix, 0.25);
+    let (out_l, _) = run_for_ms(&mut node_exec, 10.0);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.25);
+    assert!(matrix.update_signal_faults().is_empty());
+
+    set_test_p(&mut matrix, f32::NAN);
+    let (out_l, _) = run_for_ms(&mut node_exec, 10.0);
+    assert!(out_l.iter().all(|s| *s == 0.0));
+
+    assert_eq!(
+        matrix.update_signal_faults(),
+        vec![SignalFault { node_id: NodeId::Test(0), output: 0, kind: SignalFaultKind::NaN }]
+    );
+    let err = matrix.pop_error().unwrap();
+    assert!(err.contains("produced NaN samples at output 'sig'"), "{}", err);
+
+    // The node is only reported once:
+    run_for_ms(&mut node_exec, 10.0);
+    assert!(matrix.update_signal_faults().is_empty());
+    assert!(matrix.pop_error().is_none());
+
+    // The signal is passed again, once it's valid:
+    set_test_p(&mut matrix, 0.5);
+    let (out_l, _) = run_for_ms(&mut node_exec, 10.0);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.5);
+}
+
+#[test]
+fn check_signal_guard_kinds() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    setup_test_amp(&mut matrix);
+
+    for (v, kind) in
+        [(f32::INFINITY, SignalFaultKind::Inf), (f32::NEG_INFINITY, SignalFaultKind::Inf)]
+    {
+        // A new program resets the reported faults:
+        matrix.sync().unwrap();
+
+        set_test_p(&mut matrix, v);
+        let (out_l, _) = run_for_ms(&mut node_exec, 10.0);
+        assert!(out_l.iter().all(|s| *s == 0.0));
+
+        let faults = matrix.update_signal_faults();
+        assert_eq!(faults.len(), 1);
+        assert_eq!(faults[0].node_id, NodeId::Test(0));
+        assert_eq!(faults[0].kind, kind);
+    }
+}
+
+#[test]
+fn check_signal_guard_denormal() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    setup_test_amp(&mut matrix);
+
+    // Denormals are flushed to 0.0 in place:
+    set_test_p(&mut matrix, f32::MIN_POSITIVE / 4.0);
+    let (out_l, _) = run_for_ms(&mut node_exec, 10.0);
+    assert!(out_l.iter().all(|s| *s == 0.0));
+
+    assert_eq!(
+        matrix.update_signal_faults(),
+        vec![SignalFault { node_id: NodeId::Test(0), output: 0, kind: SignalFaultKind::Denormal }]
+    );
+
+    set_test_p(&mut matrix, 0.5);
+    let (out_l, _) = run_for_ms(&mut node_exec, 10.0);
+    assert_float_eq!(out_l[out_l.len() - 1], 0.5);
+}
+
+#[test]
+fn check_signal_guard_unstable_filter() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain
+        .node_out("noise", "sig")
+        .set_atom("atv", SAtom::param(1.0))
+        .node_io("sfilter", "inp", "sig")
+        .set_atom("ftype", SAtom::setting(13))
+        .node_inp("out", "ch1");
+    chain.place(&mut matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+
+    // The Stilson/Moog low pass blows up at this cutoff and resonance:
+    let sf = NodeId::SFilter(0);
+    pset_d(&mut matrix, sf, "freq", 22050.0);
+    pset_d_wait(&mut matrix, &mut node_exec, sf, "res", 1.0);
+    let (out_l, _) = run_for_ms(&mut node_exec, 1000.0);
+    assert!(out_l.iter().all(|s| s.is_finite()));
+
+    let faults = matrix.update_signal_faults();
+    assert_eq!(faults, vec![SignalFault { node_id: sf, output: 0, kind: SignalFaultKind::NaN }]);
+}
+
+struct FaultObserver {
+    faults: Mutex<Vec<SignalFault>>,
+}
+
+impl MatrixObserver for FaultObserver {
+    fn update_prop(&self, _key: &str) {}
+    fn update_monitor(&self, _cell: &Cell) {}
+    fn update_param(&self, _param_id: &ParamId) {}
+    fn update_matrix(&self) {}
+    fn update_all(&self) {}
+    fn update_signal_fault(&self, fault: &SignalFault) {
+        self.faults.lock().unwrap().push(*fault);
+    }
+}
+
+#[test]
+fn check_signal_guard_observer() {
+    let (node_conf, mut node_exec) = new_node_engine();
+    let mut matrix = Matrix::new(node_conf, 3, 3);
+    let observer = Arc::new(FaultObserver { faults: Mutex::new(vec![]) });
+    matrix.set_observer(observer.clone());
+    setup_test_amp(&mut matrix);
+
+    set_test_p(&mut matrix, f32::NAN);
+    run_for_ms(&mut node_exec, 10.0);
+    matrix.update_signal_faults();
+
+    assert_eq!(
+        *observer.faults.lock().unwrap(),
+        vec![SignalFault { node_id: NodeId::Test(0), output: 0, kind: SignalFaultKind::NaN }]
+    );
+}
@@ -0,0 +1,77 @@
+// Copyright (c) 2022 Weird Constructor <weirdconstructor@gmail.com>
+// This file is a part of HexoDSP. Released under GPL-3.0-or-later.
+// See README.md and COPYING for details.
+
+mod common;
+use common::*;
+
+use hexodsp::nodes::MidiEvent;
+
+fn setup_out(matrix: &mut Matrix) {
+    matrix.place(0, 0, Cell::empty(NodeId::Out(0)));
+    matrix.sync().unwrap();
+}
+
+#[test]
+fn check_timed_param_update() {
+    init_test!(matrix, node_exec, 3);
+    setup_out(matrix);
+    let ch1 = NodeId::Out(0).inp_param("ch1").unwrap();
+
+    matrix.set_param_at(ch1, SAtom::param(0.75), 38);
+    matrix.set_param_at(ch1, SAtom::param(0.5), 37);
+    matrix.set_param_at(ch1, SAtom::param(-0.25), 300);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+
+    assert_float_eq!(out[36], 0.0);
+    assert_float_eq!(out[37], 0.5);
+    assert_float_eq!(out[38], 0.75);
+    assert_float_eq!(out[299], 0.75);
+    assert_float_eq!(out[300], -0.25);
+    assert_float_eq!(out[out.len() - 1], -0.25);
+    assert_float_eq!(matrix.get_param(&ch1).unwrap().f(), -0.25);
+
+    // A timed update overrides the smoothing of a previous update:
+    pset_n(matrix, NodeId::Out(0), "ch1", 1.0);
+    matrix.set_param_at(ch1, SAtom::param(0.0), 10);
+    let (out, _) = run_for_ms(node_exec, 10.0);
+    assert!(out[5] > -0.25 && out[5] < 0.0);
+    assert_float_eq!(out[10], 0.0);
+    assert_float_eq!(out[out.len() - 1], 0.0);
+}
+
+#[test]
+fn check_timed_atom_update() {
+    init_test!(matrix, node_exec, 3);
+    setup_out(matrix);
+    let mono = NodeId::Out(0).inp_param("mono").unwrap();
+
+    pset_n(matrix, NodeId::Out(0), "ch1", 0.5);
+    run_for_ms(node_exec, 50.0);
+
+    // The update is delayed into the following blocks:
+    matrix.set_param_at(mono, SAtom::setting(1), 200);
+    let (_, out_r) = run_for_ms(node_exec, 10.0);
+    assert_float_eq!(out_r[199], 0.0);
+    assert_float_eq!(out_r[200], 0.5);
+}
+
+#[test]
+fn check_timed_update_midi() {
+    init_test!(matrix, node_exec, 3);
+    let mut chain = MatrixCellChain::new(CellDir::B);
+    chain.node_out("midip", "gate").node_inp("out", "ch1");
+    chain.place(matrix, 0, 0).unwrap();
+    matrix.sync().unwrap();
+
+    // Splitting the block keeps the MIDI events in place:
+    let gain = NodeId::Out(0).inp_param("gain").unwrap();
+    matrix.set_param_at(gain, SAtom::param(gain.norm(0.5)), 30);
+    node_exec.feed_midi_event(MidiEvent::from_bytes(20, &[0x90, 60, 127]).unwrap());
+    node_exec.feed_midi_event(MidiEvent::from_bytes(50, &[0x80, 60, 0]).unwrap());
+
+    let (out, _) = run_for_ms(node_exec, 5.0);
+    assert_eq!(collect_non_zero(&out[..]), vec![(20, 30)]);
+    assert_float_eq!(out[29], 1.0);
+    assert_float_eq!(out[30], 0.5);
+}